@@ -3,8 +3,15 @@ use criterion::{
 };
 use rand::{Rng, SeedableRng};
 
+use std::convert::TryFrom;
+use wide::{f32x4, f32x8};
+
+#[cfg(target_arch = "x86_64")]
+use simdiir::{biquad_avx::BiQuadAVX, biquad_sse2::BiQuadSSE2};
 use simdiir::{
-    biquad_avx::BiQuadAVX, biquad_f32::BiQuadF32, biquad_sse2::BiQuadSSE2, ScopedFlushDenormals,
+    biquad_f32::BiQuadF32,
+    biquad_portable::{BiQuadPortable4, BiQuadPortable8},
+    ScopedFlushDenormals,
 };
 
 fn noise(c: &mut Criterion) {
@@ -33,7 +40,7 @@ fn run_tests_with_input(input: &[f32], group: &mut BenchmarkGroup<WallTime>) {
         let mut biquad = BiQuadF32::new();
         b.iter_batched_ref(
             || vec![0.0; input.len()],
-            |mut output| run_f32_bench(&mut biquad, &input, &mut output),
+            |output| run_f32_bench(&mut biquad, input, output),
             BatchSize::PerIteration,
         );
     });
@@ -42,19 +49,77 @@ fn run_tests_with_input(input: &[f32], group: &mut BenchmarkGroup<WallTime>) {
         let mut biquad = BiQuadF32::new();
         b.iter_batched_ref(
             || vec![0.0; input.len()],
-            |mut output| {
+            |output| {
+                let _guard = ScopedFlushDenormals::new();
+                run_f32_bench(&mut biquad, input, output)
+            },
+            BatchSize::PerIteration,
+        );
+    });
+
+    group.bench_with_input("f32 kernel flush denorm", &input, |b, input| {
+        let mut biquad = BiQuadF32::new();
+        biquad.set_flush_denormals(true);
+        b.iter_batched_ref(
+            || vec![0.0; input.len()],
+            |output| run_f32_bench(&mut biquad, input, output),
+            BatchSize::PerIteration,
+        );
+    });
+
+    #[cfg(target_arch = "x86_64")]
+    run_x86_tests_with_input(input, group);
+
+    group.bench_with_input("portable4 with denorm", &input, |b, input| {
+        let mut biquad = BiQuadPortable4::new();
+        b.iter_batched_ref(
+            || vec![0.0; input.len()],
+            |output| run_portable4_bench(&mut biquad, input, output),
+            BatchSize::PerIteration,
+        );
+    });
+
+    group.bench_with_input("portable4 flush denorm", &input, |b, input| {
+        let mut biquad = BiQuadPortable4::new();
+        b.iter_batched_ref(
+            || vec![0.0; input.len()],
+            |output| {
+                let _guard = ScopedFlushDenormals::new();
+                run_portable4_bench(&mut biquad, input, output)
+            },
+            BatchSize::PerIteration,
+        );
+    });
+
+    group.bench_with_input("portable8 with denorm", &input, |b, input| {
+        let mut biquad = BiQuadPortable8::new();
+        b.iter_batched_ref(
+            || vec![0.0; input.len()],
+            |output| run_portable8_bench(&mut biquad, input, output),
+            BatchSize::PerIteration,
+        );
+    });
+
+    group.bench_with_input("portable8 flush denorm", &input, |b, input| {
+        let mut biquad = BiQuadPortable8::new();
+        b.iter_batched_ref(
+            || vec![0.0; input.len()],
+            |output| {
                 let _guard = ScopedFlushDenormals::new();
-                run_f32_bench(&mut biquad, &input, &mut output)
+                run_portable8_bench(&mut biquad, input, output)
             },
             BatchSize::PerIteration,
         );
     });
+}
 
+#[cfg(target_arch = "x86_64")]
+fn run_x86_tests_with_input(input: &[f32], group: &mut BenchmarkGroup<WallTime>) {
     group.bench_with_input("sse2 with denorm", &input, |b, input| {
         let mut biquad = BiQuadSSE2::new();
         b.iter_batched_ref(
             || vec![0.0; input.len()],
-            |mut output| run_sse2_bench(&mut biquad, &input, &mut output),
+            |output| run_sse2_bench(&mut biquad, input, output),
             BatchSize::PerIteration,
         );
     });
@@ -63,19 +128,29 @@ fn run_tests_with_input(input: &[f32], group: &mut BenchmarkGroup<WallTime>) {
         let mut biquad = BiQuadSSE2::new();
         b.iter_batched_ref(
             || vec![0.0; input.len()],
-            |mut output| {
+            |output| {
                 let _guard = ScopedFlushDenormals::new();
-                run_sse2_bench(&mut biquad, &input, &mut output)
+                run_sse2_bench(&mut biquad, input, output)
             },
             BatchSize::PerIteration,
         );
     });
 
+    group.bench_with_input("sse2 kernel flush denorm", &input, |b, input| {
+        let mut biquad = BiQuadSSE2::new();
+        biquad.set_flush_denormals(true);
+        b.iter_batched_ref(
+            || vec![0.0; input.len()],
+            |output| run_sse2_bench(&mut biquad, input, output),
+            BatchSize::PerIteration,
+        );
+    });
+
     group.bench_with_input("avx2 with denorm", &input, |b, input| {
         let mut biquad = BiQuadAVX::new();
         b.iter_batched_ref(
             || vec![0.0; input.len()],
-            |mut output| run_avx_bench(&mut biquad, &input, &mut output),
+            |output| run_avx_bench(&mut biquad, input, output),
             BatchSize::PerIteration,
         );
     });
@@ -84,13 +159,23 @@ fn run_tests_with_input(input: &[f32], group: &mut BenchmarkGroup<WallTime>) {
         let mut biquad = BiQuadAVX::new();
         b.iter_batched_ref(
             || vec![0.0; input.len()],
-            |mut output| {
+            |output| {
                 let _guard = ScopedFlushDenormals::new();
-                run_avx_bench(&mut biquad, &input, &mut output)
+                run_avx_bench(&mut biquad, input, output)
             },
             BatchSize::PerIteration,
         );
     });
+
+    group.bench_with_input("avx2 kernel flush denorm", &input, |b, input| {
+        let mut biquad = BiQuadAVX::new();
+        biquad.set_flush_denormals(true);
+        b.iter_batched_ref(
+            || vec![0.0; input.len()],
+            |output| run_avx_bench(&mut biquad, input, output),
+            BatchSize::PerIteration,
+        );
+    });
 }
 
 fn run_f32_bench(biquad: &mut BiQuadF32, input: &[f32], output: &mut [f32]) {
@@ -99,32 +184,38 @@ fn run_f32_bench(biquad: &mut BiQuadF32, input: &[f32], output: &mut [f32]) {
     }
 }
 
+#[cfg(target_arch = "x86_64")]
 fn run_sse2_bench(biquad: &mut BiQuadSSE2, input: &[f32], output: &mut [f32]) {
-    use std::arch::x86_64::__m128;
-    let input = unsafe {
-        let ptr = input.as_ptr() as *const __m128;
-        std::slice::from_raw_parts(ptr, input.len() / 4)
-    };
-    let output = unsafe {
-        let ptr = output.as_mut_ptr() as *mut __m128;
-        std::slice::from_raw_parts_mut(ptr, output.len() / 4)
-    };
-    for (input, output) in input.iter().zip(output.iter_mut()) {
-        *output = biquad.process(*input);
+    use std::arch::x86_64::{_mm_loadu_ps, _mm_storeu_ps};
+    for (input, output) in input.chunks_exact(4).zip(output.chunks_exact_mut(4)) {
+        unsafe {
+            let y = biquad.process(_mm_loadu_ps(input.as_ptr()));
+            _mm_storeu_ps(output.as_mut_ptr(), y);
+        }
     }
 }
 
+#[cfg(target_arch = "x86_64")]
 fn run_avx_bench(biquad: &mut BiQuadAVX, input: &[f32], output: &mut [f32]) {
-    use std::arch::x86_64::__m256;
-    let input = unsafe {
-        let ptr = input.as_ptr() as *const __m256;
-        std::slice::from_raw_parts(ptr, input.len() / 8)
-    };
-    let output = unsafe {
-        let ptr = output.as_mut_ptr() as *mut __m256;
-        std::slice::from_raw_parts_mut(ptr, output.len() / 8)
-    };
-    for (input, output) in input.iter().zip(output.iter_mut()) {
-        *output = biquad.process(*input);
+    use std::arch::x86_64::{_mm256_loadu_ps, _mm256_storeu_ps};
+    for (input, output) in input.chunks_exact(8).zip(output.chunks_exact_mut(8)) {
+        unsafe {
+            let y = biquad.process(_mm256_loadu_ps(input.as_ptr()));
+            _mm256_storeu_ps(output.as_mut_ptr(), y);
+        }
+    }
+}
+
+fn run_portable4_bench(biquad: &mut BiQuadPortable4, input: &[f32], output: &mut [f32]) {
+    for (input, output) in input.chunks_exact(4).zip(output.chunks_exact_mut(4)) {
+        let x = f32x4::from(<[f32; 4]>::try_from(input).unwrap());
+        output.copy_from_slice(&biquad.process(x).to_array());
+    }
+}
+
+fn run_portable8_bench(biquad: &mut BiQuadPortable8, input: &[f32], output: &mut [f32]) {
+    for (input, output) in input.chunks_exact(8).zip(output.chunks_exact_mut(8)) {
+        let x = f32x8::from(<[f32; 8]>::try_from(input).unwrap());
+        output.copy_from_slice(&biquad.process(x).to_array());
     }
 }
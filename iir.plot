@@ -1,2 +0,0 @@
-set datafile separator ","
-plot 'iir.csv' using 0:1 with lines, 'iir.csv' using 0:2 with lines, 'iir.csv' using 0:3 with lines
@@ -0,0 +1,140 @@
+use std::{f64::consts::PI, fmt, str::FromStr};
+
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+use crate::{coeffs::BiQuadCoeffs, design::FilterError};
+
+// Measuring the rounding error of the f32 kernels against the same cascade
+// computed in double precision. The reference uses the exact same (f32)
+// coefficients, so any difference is arithmetic in the kernel rather than
+// coefficient quantization.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TestSignal {
+    // Unit impulse, shows how the error behaves as the response decays
+    Impulse,
+    // Uniform white noise in -1..1, from a fixed seed
+    Noise,
+    // Exponential sine sweep at half scale from 20 Hz to 20 kHz, or to 90% of
+    // Nyquist at low sample rates
+    Sweep,
+}
+
+impl TestSignal {
+    pub const ALL: [TestSignal; 3] = [TestSignal::Impulse, TestSignal::Noise, TestSignal::Sweep];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TestSignal::Impulse => "impulse",
+            TestSignal::Noise => "noise",
+            TestSignal::Sweep => "sweep",
+        }
+    }
+
+    pub fn generate(self, len: usize, sample_rate: f32) -> Vec<f32> {
+        match self {
+            TestSignal::Impulse => (0..len).map(|n| if n == 0 { 1.0 } else { 0.0 }).collect(),
+            TestSignal::Noise => {
+                let mut rng = XorShiftRng::seed_from_u64(0x5eed_1e55_f00d_cafe);
+                (0..len).map(|_| rng.gen::<f32>() * 2.0 - 1.0).collect()
+            }
+            TestSignal::Sweep => {
+                let sample_rate = sample_rate as f64;
+                let (from, to) = (20.0, 20000.0f64.min(0.45 * sample_rate));
+                let duration = len as f64 / sample_rate;
+                let rate = (to / from).ln() / duration;
+                (0..len)
+                    .map(|n| {
+                        let t = n as f64 / sample_rate;
+                        let phase = 2.0 * PI * from * ((rate * t).exp() - 1.0) / rate;
+                        (0.5 * phase.sin()) as f32
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+impl fmt::Display for TestSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for TestSignal {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TestSignal::ALL
+            .iter()
+            .copied()
+            .find(|signal| signal.name() == s)
+            .ok_or_else(|| FilterError(format!("unknown test signal '{}'", s)))
+    }
+}
+
+// The sections as Direct Form I in double precision, one after the other
+pub fn reference(sections: &[BiQuadCoeffs], input: &[f32]) -> Vec<f64> {
+    let mut signal = input.iter().map(|x| *x as f64).collect::<Vec<_>>();
+    for c in sections {
+        let (a0, a1, a2) = (c.a0 as f64, c.a1 as f64, c.a2 as f64);
+        let (b1, b2) = (c.b1 as f64, c.b2 as f64);
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        for x in &mut signal {
+            let y = a0 * *x + a1 * x1 + a2 * x2 - b1 * y1 - b2 * y2;
+            x2 = x1;
+            x1 = *x;
+            y2 = y1;
+            y1 = y;
+            *x = y;
+        }
+    }
+    signal
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ErrorStats {
+    pub max_abs_error: f64,
+    pub rms_error: f64,
+    // Reference power over error power, infinite when the output is exact
+    pub snr_db: f64,
+    // RMS error of the last quarter of the signal relative to the first
+    // quarter. Positive when the error builds up over time, as with limit
+    // cycles or a kernel drifting away from the reference. None when the
+    // first quarter is exact, or shorter than two blocks of the eight lane
+    // kernels, where there's nothing meaningful to compare against.
+    pub error_growth_db: Option<f64>,
+}
+
+const MIN_GROWTH_QUARTER: usize = 16;
+
+impl ErrorStats {
+    pub fn measure(output: &[f32], reference: &[f64]) -> Self {
+        assert_eq!(output.len(), reference.len());
+        let error = output
+            .iter()
+            .zip(reference.iter())
+            .map(|(o, r)| *o as f64 - r)
+            .collect::<Vec<_>>();
+
+        let power = |x: &[f64]| x.iter().map(|x| x * x).sum::<f64>() / x.len().max(1) as f64;
+        let ratio_db = |num: f64, den: f64| {
+            if num == den {
+                0.0
+            } else {
+                10.0 * (num / den).log10()
+            }
+        };
+
+        let quarter = error.len() / 4;
+        Self {
+            max_abs_error: error.iter().fold(0.0, |max, e| e.abs().max(max)),
+            rms_error: power(&error).sqrt(),
+            snr_db: ratio_db(power(reference), power(&error)),
+            error_growth_db: Some(power(&error[..quarter]))
+                .filter(|first| quarter >= MIN_GROWTH_QUARTER && *first > 0.0)
+                .map(|first| ratio_db(power(&error[error.len() - quarter..]), first)),
+        }
+    }
+}
@@ -0,0 +1,517 @@
+use std::f64::consts::PI;
+
+use crate::{
+    cascade::{Backend, Cascade},
+    coeffs::BiQuadCoeffs,
+    design::{FilterError, MAX_ORDER},
+    response::{self, Complex},
+};
+
+// The allpass with denominator 1 + b1 z^-1, first order like
+// `BiQuadCoeffs::allpass`, or 1 + b1 z^-1 + b2 z^-2, its numerator the
+// denominator reversed
+fn allpass_section(denominator: &[f64]) -> BiQuadCoeffs {
+    match *denominator {
+        [b1] => BiQuadCoeffs::new(b1 as f32, 1.0, 0.0, b1 as f32, 0.0),
+        [b1, b2] => {
+            let (b1, b2) = (b1 as f32, b2 as f32);
+            BiQuadCoeffs::new(b2, b1, 1.0, b1, b2)
+        }
+        _ => unreachable!(),
+    }
+}
+
+// Thiran allpass interpolator: the allpass of the order whose group delay is
+// maximally flat around DC at `delay` samples. Stable for delays above one
+// less than the order, and closest to a fractional delay across the band
+// with the delay within half a sample of the order.
+pub fn thiran(delay: f64, order: u32) -> Result<Vec<BiQuadCoeffs>, FilterError> {
+    if !(1..=MAX_ORDER).contains(&order) {
+        return Err(FilterError(format!(
+            "a Thiran allpass has order 1 to {}, not {}",
+            MAX_ORDER, order
+        )));
+    }
+    let n = order as f64;
+    if delay.is_nan() || delay <= n - 1.0 {
+        return Err(FilterError(format!(
+            "a Thiran allpass of order {} needs a delay above {} samples, not {}",
+            order,
+            n - 1.0,
+            delay
+        )));
+    }
+
+    // a_k = (-1)^k C(N, k) prod_i (D - N + i) / (D - N + k + i), i = 0..=N
+    let mut denominator = vec![1.0];
+    let mut binomial = 1.0;
+    for k in 1..=order {
+        let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+        let k = k as f64;
+        binomial *= (n - k + 1.0) / k;
+        let product = (0..=order)
+            .map(|i| (delay - n + i as f64) / (delay - n + k + i as f64))
+            .product::<f64>();
+        denominator.push(sign * binomial * product);
+    }
+
+    Ok(factor(&denominator)
+        .iter()
+        .map(|denominator| allpass_section(denominator))
+        .collect())
+}
+
+// Roots of 1 + c[1] z^-1 + ... + c[n] z^-n by Durand-Kerner iteration,
+// after taking out the roots at zero, which it only slowly homes in on
+fn roots(c: &[f64]) -> Vec<Complex> {
+    let zeros = c.iter().rev().take_while(|c| **c == 0.0).count();
+    let c = &c[..c.len() - zeros];
+    let degree = c.len() - 1;
+    let evaluate = |z: Complex| {
+        c.iter()
+            .fold(Complex::ZERO, |value, c| value * z + Complex::new(*c, 0.0))
+    };
+
+    let seed = Complex::new(0.4, 0.9);
+    let mut roots = (0..degree)
+        .scan(Complex::ONE, |power, _| {
+            *power = *power * seed;
+            Some(*power)
+        })
+        .collect::<Vec<_>>();
+    for _ in 0..500 {
+        let mut moved = 0.0f64;
+        for i in 0..degree {
+            let others = (0..degree)
+                .filter(|j| *j != i)
+                .fold(Complex::ONE, |product, j| product * (roots[i] - roots[j]));
+            let step = evaluate(roots[i]) / others;
+            roots[i] = roots[i] - step;
+            moved = moved.max(step.norm());
+        }
+        if moved < 1e-14 {
+            break;
+        }
+    }
+    roots.extend((0..zeros).map(|_| Complex::ZERO));
+    roots
+}
+
+// Splits the polynomial into second order factors [b1, b2], conjugate poles
+// together and real ones in pairs, with a first order factor [b1] for a last
+// real one
+fn factor(c: &[f64]) -> Vec<Vec<f64>> {
+    let roots = roots(c);
+    let is_real = |z: &Complex| z.im.abs() < 1e-9;
+
+    let mut factors = roots
+        .iter()
+        .filter(|z| z.im >= 1e-9)
+        .map(|z| vec![-2.0 * z.re, z.re * z.re + z.im * z.im])
+        .collect::<Vec<_>>();
+    let real = roots
+        .iter()
+        .filter(|z| is_real(z))
+        .map(|z| z.re)
+        .collect::<Vec<_>>();
+    for pair in real.chunks(2) {
+        match pair {
+            [p1, p2] => factors.push(vec![-(p1 + p2), p1 * p2]),
+            [p] => factors.push(vec![-p]),
+            _ => unreachable!(),
+        }
+    }
+    factors
+}
+
+// A fractional delay line: a Thiran allpass running as a cascade. The delay
+// can change while it runs without resetting the filter, as long as the
+// order stays the same.
+pub struct FractionalDelay {
+    delay: f64,
+    order: u32,
+    sections: Vec<BiQuadCoeffs>,
+    cascade: Cascade,
+}
+
+impl FractionalDelay {
+    // On the fastest backend the CPU supports
+    pub fn new(delay: f64, order: u32) -> Result<Self, FilterError> {
+        Self::with_backend(Backend::detect(), delay, order)
+    }
+
+    // Panics if the CPU doesn't support the backend, see
+    // `Backend::is_supported`
+    pub fn with_backend(backend: Backend, delay: f64, order: u32) -> Result<Self, FilterError> {
+        let sections = thiran(delay, order)?;
+        Ok(FractionalDelay {
+            delay,
+            order,
+            cascade: Cascade::new(backend, &sections),
+            sections,
+        })
+    }
+
+    pub fn delay(&self) -> f64 {
+        self.delay
+    }
+
+    pub fn order(&self) -> u32 {
+        self.order
+    }
+
+    pub fn sections(&self) -> &[BiQuadCoeffs] {
+        &self.sections
+    }
+
+    // On error the delay is left as it was
+    pub fn set_delay(&mut self, delay: f64) -> Result<(), FilterError> {
+        let sections = thiran(delay, self.order)?;
+        for (i, c) in sections.iter().enumerate() {
+            self.cascade.set_coeffs(i, c);
+        }
+        self.delay = delay;
+        self.sections = sections;
+        Ok(())
+    }
+
+    pub fn reset(&mut self) {
+        self.cascade = Cascade::new(self.cascade.backend(), &self.sections);
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        self.cascade.process(samples);
+    }
+}
+
+// Frequencies the group delay is fitted at, log spaced over the target
+const GRID_POINTS: usize = 64;
+
+// Pole radius the fit stays under, keeping clear of what f32 coefficients
+// can't place
+const MAX_RADIUS: f64 = 0.999;
+
+// The fit starts from pole pairs spread evenly over the band in log
+// frequency, once with each of these radii, and keeps the best
+const START_RADII: [f64; 5] = [0.5, 0.8, 0.9, 0.95, 0.98];
+
+const MAX_ITERATIONS: usize = 200;
+
+// The delay to add to the filter's own to make the sum flat over the band,
+// at the band's largest delay: the target for a `PhaseEqualizer`
+// straightening out a crossover or a minimum phase EQ
+pub fn flattening_target(
+    sections: &[BiQuadCoeffs],
+    sample_rate: f32,
+    low: f32,
+    high: f32,
+) -> Vec<(f32, f64)> {
+    let delays = response::log_frequencies(low, high, GRID_POINTS)
+        .into_iter()
+        .map(|f| (f, response::group_delay(sections, sample_rate, f)))
+        .collect::<Vec<_>>();
+    let max = delays.iter().map(|(_, d)| *d).fold(f64::MIN, f64::max);
+    delays.iter().map(|(f, d)| (*f, max - d)).collect()
+}
+
+// Group delay of one allpass pole at radius r and angle theta, and its
+// derivatives by r and theta
+fn pole_delay(w: f64, r: f64, theta: f64) -> (f64, f64, f64) {
+    let (sin, cos) = (w - theta).sin_cos();
+    let d = 1.0 - 2.0 * r * cos + r * r;
+    let value = (1.0 - r * r) / d;
+    let by_r = (-2.0 * r * d - (1.0 - r * r) * (2.0 * r - 2.0 * cos)) / (d * d);
+    let by_theta = 2.0 * r * (1.0 - r * r) * sin / (d * d);
+    (value, by_r, by_theta)
+}
+
+// Least squares fit of an allpass cascade's group delay to the target plus
+// a free offset. Parameters are the offset, then for each pole pair its
+// angle and its radius through a logistic, then a first order pole through
+// tanh, which keeps every pole inside MAX_RADIUS.
+struct Fit {
+    omegas: Vec<f64>,
+    target: Vec<f64>,
+    pairs: usize,
+    first_order: bool,
+}
+
+impl Fit {
+    fn radius(s: f64) -> (f64, f64) {
+        let r = MAX_RADIUS / (1.0 + (-s.clamp(-30.0, 30.0)).exp());
+        (r, r * (1.0 - r / MAX_RADIUS))
+    }
+
+    fn real_pole(s: f64) -> (f64, f64) {
+        let t = s.tanh();
+        (MAX_RADIUS * t, MAX_RADIUS * (1.0 - t * t))
+    }
+
+    // Residuals and, if asked, their derivatives by each parameter
+    fn evaluate(&self, params: &[f64], jacobian: Option<&mut Vec<Vec<f64>>>) -> Vec<f64> {
+        let mut columns = vec![vec![0.0; self.omegas.len()]; params.len()];
+        let residuals = self
+            .omegas
+            .iter()
+            .zip(self.target.iter())
+            .enumerate()
+            .map(|(k, (w, target))| {
+                let mut delay = 0.0;
+                columns[0][k] = -1.0;
+                for pair in 0..self.pairs {
+                    let (theta, s) = (params[1 + 2 * pair], params[2 + 2 * pair]);
+                    let (r, dr) = Self::radius(s);
+                    let (upper, upper_r, upper_theta) = pole_delay(*w, r, theta);
+                    let (lower, lower_r, lower_theta) = pole_delay(*w, r, -theta);
+                    delay += upper + lower;
+                    columns[1 + 2 * pair][k] = upper_theta - lower_theta;
+                    columns[2 + 2 * pair][k] = (upper_r + lower_r) * dr;
+                }
+                if self.first_order {
+                    let (a, da) = Self::real_pole(params[1 + 2 * self.pairs]);
+                    let (value, by_a, _) = pole_delay(*w, a, 0.0);
+                    delay += value;
+                    columns[1 + 2 * self.pairs][k] = by_a * da;
+                }
+                delay - target - params[0]
+            })
+            .collect();
+        if let Some(jacobian) = jacobian {
+            *jacobian = columns;
+        }
+        residuals
+    }
+
+    fn cost(residuals: &[f64]) -> f64 {
+        residuals.iter().map(|e| e * e).sum()
+    }
+
+    // Levenberg-Marquardt from the starting point, returning the parameters
+    // and the sum of squared residuals
+    fn refine(&self, mut params: Vec<f64>) -> (Vec<f64>, f64) {
+        let mut jacobian = Vec::new();
+        let mut residuals = self.evaluate(&params, Some(&mut jacobian));
+        let mut cost = Self::cost(&residuals);
+        let mut lambda = 1e-3;
+
+        for _ in 0..MAX_ITERATIONS {
+            let n = params.len();
+            let dot = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(a, b)| a * b).sum::<f64>();
+            let normal = (0..n)
+                .map(|i| (0..n).map(|j| dot(&jacobian[i], &jacobian[j])).collect())
+                .collect::<Vec<Vec<f64>>>();
+            let gradient = (0..n)
+                .map(|i| -dot(&jacobian[i], &residuals))
+                .collect::<Vec<_>>();
+
+            let improved = loop {
+                let mut damped = normal.clone();
+                for (i, row) in damped.iter_mut().enumerate() {
+                    row[i] += lambda * (normal[i][i] + 1e-12);
+                }
+                let step = solve(damped, gradient.clone());
+                let candidate = params
+                    .iter()
+                    .zip(step.iter())
+                    .map(|(p, s)| p + s)
+                    .collect::<Vec<_>>();
+                let candidate_residuals = self.evaluate(&candidate, None);
+                let candidate_cost = Self::cost(&candidate_residuals);
+                if candidate_cost < cost {
+                    let improvement = cost - candidate_cost;
+                    params = candidate;
+                    residuals = self.evaluate(&params, Some(&mut jacobian));
+                    cost = candidate_cost;
+                    lambda = (lambda / 3.0).max(1e-12);
+                    break improvement > 1e-10 * cost;
+                }
+                lambda *= 4.0;
+                if lambda > 1e10 {
+                    break false;
+                }
+            };
+            if !improved {
+                break;
+            }
+        }
+        (params, cost)
+    }
+}
+
+// Gaussian elimination with partial pivoting
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Vec<f64> {
+    let n = b.len();
+    for i in 0..n {
+        let pivot = (i..n)
+            .max_by(|x, y| a[*x][i].abs().total_cmp(&a[*y][i].abs()))
+            .unwrap();
+        a.swap(i, pivot);
+        b.swap(i, pivot);
+        for r in i + 1..n {
+            let factor = a[r][i] / a[i][i];
+            let pivot_row = a[i].clone();
+            for (x, p) in a[r].iter_mut().zip(pivot_row.iter()).skip(i) {
+                *x -= factor * p;
+            }
+            b[r] -= factor * b[i];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for i in (0..n).rev() {
+        let known = (i + 1..n).map(|c| a[i][c] * x[c]).sum::<f64>();
+        x[i] = (b[i] - known) / a[i][i];
+    }
+    x
+}
+
+// An allpass cascade whose group delay follows a target curve, up to a
+// constant: the extra delay that buys. Pole pairs make second order
+// sections, and an odd order adds a first order one. The target is a list
+// of frequencies and delays in samples, interpolated in log frequency; the
+// fit only looks between its first and last frequency, the delay elsewhere
+// being whatever the poles leave there.
+pub struct PhaseEqualizer {
+    sample_rate: f32,
+    sections: Vec<BiQuadCoeffs>,
+    offset: f64,
+    rms_error: f64,
+    cascade: Cascade,
+}
+
+impl PhaseEqualizer {
+    // On the fastest backend the CPU supports
+    pub fn new(sample_rate: f32, target: &[(f32, f64)], order: u32) -> Result<Self, FilterError> {
+        Self::with_backend(Backend::detect(), sample_rate, target, order)
+    }
+
+    // Panics if the CPU doesn't support the backend, see
+    // `Backend::is_supported`
+    pub fn with_backend(
+        backend: Backend,
+        sample_rate: f32,
+        target: &[(f32, f64)],
+        order: u32,
+    ) -> Result<Self, FilterError> {
+        if !(1..=MAX_ORDER).contains(&order) {
+            return Err(FilterError(format!(
+                "a phase equalizer has order 1 to {}, not {}",
+                MAX_ORDER, order
+            )));
+        }
+        if target.len() < 2
+            || target.windows(2).any(|w| w[0].0 >= w[1].0)
+            || target[0].0 <= 0.0
+            || target[target.len() - 1].0 >= sample_rate / 2.0
+            || target.iter().any(|(_, d)| !d.is_finite())
+        {
+            return Err(FilterError(
+                "a group delay target needs two or more finite points at rising frequencies between 0 Hz and Nyquist"
+                    .to_string(),
+            ));
+        }
+
+        let (low, high) = (target[0].0, target[target.len() - 1].0);
+        let frequencies = response::log_frequencies(low, high, GRID_POINTS);
+        let fit = Fit {
+            omegas: frequencies
+                .iter()
+                .map(|f| 2.0 * PI * *f as f64 / sample_rate as f64)
+                .collect(),
+            target: frequencies
+                .iter()
+                .map(|f| interpolate(target, *f))
+                .collect(),
+            pairs: order as usize / 2,
+            first_order: order % 2 == 1,
+        };
+
+        let (params, cost) = START_RADII
+            .iter()
+            .map(|r| {
+                let mut params = vec![0.0];
+                let (low, high) = (low.ln() as f64, high.ln() as f64);
+                for pair in 0..fit.pairs {
+                    let f = (low + (high - low) * (pair as f64 + 0.5) / fit.pairs as f64).exp();
+                    params.push(2.0 * PI * f / sample_rate as f64);
+                    params.push((r / (MAX_RADIUS - r)).ln());
+                }
+                if fit.first_order {
+                    params.push(0.0);
+                }
+                // Start the offset where the residuals are centered
+                let residuals = fit.evaluate(&params, None);
+                params[0] = residuals.iter().sum::<f64>() / residuals.len() as f64;
+                fit.refine(params)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+
+        let mut sections = (0..fit.pairs)
+            .map(|pair| {
+                let theta = params[1 + 2 * pair];
+                let (r, _) = Fit::radius(params[2 + 2 * pair]);
+                allpass_section(&[-2.0 * r * theta.cos(), r * r])
+            })
+            .collect::<Vec<_>>();
+        if fit.first_order {
+            let (a, _) = Fit::real_pole(params[1 + 2 * fit.pairs]);
+            sections.push(allpass_section(&[-a]));
+        }
+
+        Ok(PhaseEqualizer {
+            sample_rate,
+            cascade: Cascade::new(backend, &sections),
+            sections,
+            offset: params[0],
+            rms_error: (cost / GRID_POINTS as f64).sqrt(),
+        })
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    pub fn sections(&self) -> &[BiQuadCoeffs] {
+        &self.sections
+    }
+
+    // Samples of delay on top of the target
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+
+    // How far the delay strays from the target plus the offset, in samples,
+    // as designed
+    pub fn rms_error(&self) -> f64 {
+        self.rms_error
+    }
+
+    pub fn response(&self, frequency: f32) -> Complex {
+        response::response(&self.sections, self.sample_rate, frequency)
+    }
+
+    // In samples
+    pub fn group_delay(&self, frequency: f32) -> f64 {
+        response::group_delay(&self.sections, self.sample_rate, frequency)
+    }
+
+    pub fn reset(&mut self) {
+        self.cascade = Cascade::new(self.cascade.backend(), &self.sections);
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        self.cascade.process(samples);
+    }
+}
+
+// The target's delay at the frequency, linear in log frequency between its
+// points
+fn interpolate(target: &[(f32, f64)], frequency: f32) -> f64 {
+    let i = target
+        .windows(2)
+        .position(|w| frequency <= w[1].0)
+        .unwrap_or(target.len() - 2);
+    let ((f0, d0), (f1, d1)) = (target[i], target[i + 1]);
+    let t = ((frequency / f0).ln() / (f1 / f0).ln()) as f64;
+    d0 + (d1 - d0) * t.clamp(0.0, 1.0)
+}
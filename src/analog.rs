@@ -0,0 +1,349 @@
+use crate::{coeffs::BiQuadCoeffs, design::DesignMethod, response::Complex};
+
+// A first or second order analog filter: the coefficients of s^2, s and 1 of
+// its numerator and denominator, with s normalized to the cutoff. These are
+// the prototypes behind the RBJ cookbook designs in `coeffs`, which are
+// their bilinear transforms.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Prototype {
+    pub num: [f64; 3],
+    pub den: [f64; 3],
+}
+
+impl Prototype {
+    pub fn new(num: [f64; 3], den: [f64; 3]) -> Self {
+        Prototype { num, den }
+    }
+
+    pub fn lowpass(q: f64) -> Self {
+        Self::new([0.0, 0.0, 1.0], [1.0, 1.0 / q, 1.0])
+    }
+
+    pub fn highpass(q: f64) -> Self {
+        Self::new([1.0, 0.0, 0.0], [1.0, 1.0 / q, 1.0])
+    }
+
+    // Constant 0 dB peak gain
+    pub fn bandpass(q: f64) -> Self {
+        Self::new([0.0, 1.0 / q, 0.0], [1.0, 1.0 / q, 1.0])
+    }
+
+    pub fn notch(q: f64) -> Self {
+        Self::new([1.0, 0.0, 1.0], [1.0, 1.0 / q, 1.0])
+    }
+
+    pub fn allpass(q: f64) -> Self {
+        Self::new([1.0, -1.0 / q, 1.0], [1.0, 1.0 / q, 1.0])
+    }
+
+    pub fn peaking(q: f64, gain_db: f64) -> Self {
+        let a = 10f64.powf(gain_db / 40.0);
+        Self::new([1.0, a / q, 1.0], [1.0, 1.0 / (a * q), 1.0])
+    }
+
+    pub fn low_shelf(q: f64, gain_db: f64) -> Self {
+        let a = 10f64.powf(gain_db / 40.0);
+        let b = a.sqrt() / q;
+        Self::new([a, a * b, a * a], [a, b, 1.0])
+    }
+
+    pub fn high_shelf(q: f64, gain_db: f64) -> Self {
+        let a = 10f64.powf(gain_db / 40.0);
+        let b = a.sqrt() / q;
+        Self::new([a * a, a * b, a], [1.0, b, a])
+    }
+
+    pub fn first_order_lowpass() -> Self {
+        Self::new([0.0, 0.0, 1.0], [0.0, 1.0, 1.0])
+    }
+
+    pub fn first_order_highpass() -> Self {
+        Self::new([0.0, 1.0, 0.0], [0.0, 1.0, 1.0])
+    }
+
+    pub fn first_order_allpass() -> Self {
+        Self::new([0.0, -1.0, 1.0], [0.0, 1.0, 1.0])
+    }
+
+    fn is_first_order(&self) -> bool {
+        self.den[0] == 0.0
+    }
+
+    // Response at `ratio` times the cutoff
+    pub fn response(&self, ratio: f64) -> Complex {
+        let s = Complex::new(0.0, ratio);
+        let eval = |c: [f64; 3]| {
+            (Complex::new(c[0], 0.0) * s + Complex::new(c[1], 0.0)) * s + Complex::new(c[2], 0.0)
+        };
+        eval(self.num) / eval(self.den)
+    }
+
+    // The digital filter with the cutoff at `cutoff` Hz, by the given method
+    pub fn discretize(&self, method: DesignMethod, sample_rate: f32, cutoff: f32) -> BiQuadCoeffs {
+        // The cutoff in radians per sample, which is also the sampling
+        // period in the prototype's normalized time
+        let w0 = 2.0 * std::f64::consts::PI * cutoff as f64 / sample_rate as f64;
+        let (num, den) = match method {
+            DesignMethod::Bilinear => self.bilinear(w0),
+            DesignMethod::MatchedZ => self.matched_z(w0),
+            DesignMethod::ImpulseInvariant => self.impulse_invariant(w0),
+            DesignMethod::MagnitudeMatched => self.magnitude_matched(w0),
+        };
+        BiQuadCoeffs::normalized_f64(num, den)
+    }
+
+    // s = (1 - z^-1) / (1 + z^-1) / tan(w0 / 2), exact at the cutoff
+    fn bilinear(&self, w0: f64) -> ([f64; 3], [f64; 3]) {
+        let k = 1.0 / (w0 / 2.0).tan();
+        let transform = |c: [f64; 3]| {
+            if self.is_first_order() {
+                [c[1] * k + c[2], c[2] - c[1] * k, 0.0]
+            } else {
+                let a = c[0] * k * k;
+                [a + c[1] * k + c[2], 2.0 * (c[2] - a), a - c[1] * k + c[2]]
+            }
+        };
+        (transform(self.num), transform(self.den))
+    }
+
+    // Poles and zeros mapped by z = e^(s T). Zeros at infinity are left out
+    // rather than put at Nyquist, and the gain matches the prototype at DC,
+    // or at the cutoff for filters with none there.
+    fn matched_z(&self, w0: f64) -> ([f64; 3], [f64; 3]) {
+        let map = |c: [f64; 3]| {
+            let roots = roots(c)
+                .iter()
+                .map(|r| Complex::from_polar((r.re * w0).exp(), r.im * w0))
+                .collect::<Vec<_>>();
+            from_roots(&roots)
+        };
+        let (mut num, den) = (map(self.num), map(self.den));
+
+        let dc = self.response(0.0).re;
+        let gain = if dc.abs() > 1e-9 {
+            dc / (evaluate(num, 0.0) / evaluate(den, 0.0)).re
+        } else {
+            self.response(1.0).norm() / (evaluate(num, w0) / evaluate(den, w0)).norm()
+        };
+        num.iter_mut().for_each(|c| *c *= gain);
+        (num, den)
+    }
+
+    // The sampled impulse response, T h(nT), of the prototype after taking
+    // out its direct term. Each pole's step at t = 0 counts half, which
+    // removes most of the DC error of plain impulse invariance.
+    fn impulse_invariant(&self, w0: f64) -> ([f64; 3], [f64; 3]) {
+        let order = if self.is_first_order() { 1 } else { 2 };
+        let lead = self.den[2 - order];
+        let direct = self.num[2 - order] / lead;
+        // Numerator of the strictly proper remainder, over the monic
+        // denominator
+        let rest = [0, 1, 2].map(|i| (self.num[i] - direct * self.den[i]) / lead);
+
+        let poles = roots(self.den);
+        let z = poles
+            .iter()
+            .map(|p| Complex::from_polar((p.re * w0).exp(), p.im * w0))
+            .collect::<Vec<_>>();
+        let d = Complex::new(direct, 0.0);
+        let real = |c: [Complex; 3]| c.map(|c| c.re);
+
+        let num = match poles.len() {
+            1 => {
+                let c = Complex::new(w0 * rest[2], 0.0);
+                let k = d - c.scale(0.5);
+                [k + c, Complex::ZERO - k * z[0], Complex::ZERO]
+            }
+            _ if (poles[0] - poles[1]).norm() < 1e-6 * poles[0].norm().max(1.0) => {
+                // Double pole: a / (s - p) + b / (s - p)^2, and the second
+                // term samples to t e^(pt), which is zero at t = 0
+                let p = poles[0];
+                let a = Complex::new(rest[1], 0.0);
+                let b = a * p + Complex::new(rest[2], 0.0);
+                let c = a.scale(w0);
+                let k = d - c.scale(0.5);
+                [
+                    k + c,
+                    (b.scale(w0 * w0) - k.scale(2.0) - c) * z[0],
+                    k * z[0] * z[0],
+                ]
+            }
+            _ => {
+                // Residues R(p) / D'(p)
+                let residue = |p: Complex, other: Complex| {
+                    (Complex::new(rest[1], 0.0) * p + Complex::new(rest[2], 0.0)) / (p - other)
+                };
+                let c1 = residue(poles[0], poles[1]).scale(w0);
+                let c2 = residue(poles[1], poles[0]).scale(w0);
+                let k = d - (c1 + c2).scale(0.5);
+                [
+                    k + c1 + c2,
+                    Complex::ZERO - k * (z[0] + z[1]) - c1 * z[1] - c2 * z[0],
+                    k * z[0] * z[1],
+                ]
+            }
+        };
+        (real(num), from_roots(&z))
+    }
+
+    // Vicanek's magnitude matched design: the poles of impulse invariance,
+    // and the minimum phase numerator whose magnitude equals the prototype's
+    // at DC, the cutoff and Nyquist (only the two ends for first order).
+    // Zeros on the imaginary axis, as in a notch, are mapped exactly like
+    // matched-Z instead, and allpasses keep the mirror image of their
+    // denominator as numerator, which the magnitude alone can't tell. Where
+    // no real numerator has those magnitudes, as for deep cuts close to
+    // Nyquist, this falls back to the bilinear transform.
+    fn magnitude_matched(&self, w0: f64) -> ([f64; 3], [f64; 3]) {
+        if self.num == self.den {
+            return ([1.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        }
+        let (_, den) = self.matched_z(w0);
+        if self.num == [self.den[0], -self.den[1], self.den[2]] {
+            let num = if self.is_first_order() {
+                [den[1], 1.0, 0.0]
+            } else {
+                [den[2], den[1], 1.0]
+            };
+            return (num, den);
+        }
+        if self.num[1] == 0.0 && self.num[0] * self.num[2] > 0.0 {
+            return self.matched_z(w0);
+        }
+
+        // Zeros at DC stay there exactly, (1 - z^-1) each, and the rest of
+        // the numerator is fitted at the remaining points: |R|^2 is a
+        // polynomial in cos(w) of one degree less per zero
+        let zeros_at_dc = self.num.iter().rev().take_while(|c| **c == 0.0).count();
+        let order = if self.is_first_order() { 1 } else { 2 };
+        let pi = std::f64::consts::PI;
+        let points = if order == 1 {
+            vec![0.0, pi]
+        } else {
+            vec![0.0, pi, w0]
+        };
+        let fitted = points[zeros_at_dc..]
+            .iter()
+            .map(|w| {
+                let magnitude = self.response(w / w0).norm() * evaluate(den, *w).norm();
+                let zeros = (2.0 * (1.0 - w.cos())).powi(zeros_at_dc as i32);
+                (w.cos(), magnitude * magnitude / zeros)
+            })
+            .collect::<Vec<_>>();
+
+        let fitted = interpolate(&fitted);
+        if !positive(fitted) {
+            return self.bilinear(w0);
+        }
+        let mut num = spectral_factor(fitted);
+        for _ in 0..zeros_at_dc {
+            num = [num[0], num[1] - num[0], num[2] - num[1]];
+        }
+        (num, den)
+    }
+}
+
+// Coefficients of the polynomial of lowest degree through the points, at
+// most three
+fn interpolate(points: &[(f64, f64)]) -> [f64; 3] {
+    let mut p = [0.0; 3];
+    for (i, (xi, yi)) in points.iter().enumerate() {
+        // Lagrange basis polynomial of point i, lowest power first
+        let mut basis = [*yi, 0.0, 0.0];
+        for (_, (xj, _)) in points.iter().enumerate().filter(|(j, _)| *j != i) {
+            let scale = 1.0 / (xi - xj);
+            basis = [
+                -xj * basis[0] * scale,
+                (basis[0] - xj * basis[1]) * scale,
+                (basis[1] - xj * basis[2]) * scale,
+            ];
+        }
+        for (p, b) in p.iter_mut().zip(basis.iter()) {
+            *p += b;
+        }
+    }
+    p
+}
+
+// Whether p0 + p1 c + p2 c^2 is positive for all -1 <= c <= 1
+fn positive(p: [f64; 3]) -> bool {
+    let at = |c: f64| p[0] + (p[1] + p[2] * c) * c;
+    let vertex = -p[1] / (2.0 * p[2]);
+    let lowest = if p[2] > 0.0 && vertex.abs() < 1.0 {
+        at(vertex)
+    } else {
+        at(-1.0).min(at(1.0))
+    };
+    lowest > 0.0
+}
+
+// Roots of c[0] s^2 + c[1] s + c[2], of whatever degree it actually has
+fn roots(c: [f64; 3]) -> Vec<Complex> {
+    if c[0] != 0.0 {
+        let root = Complex::new(c[1] * c[1] - 4.0 * c[0] * c[2], 0.0).sqrt();
+        let a = Complex::new(2.0 * c[0], 0.0);
+        vec![
+            (Complex::new(-c[1], 0.0) + root) / a,
+            (Complex::new(-c[1], 0.0) - root) / a,
+        ]
+    } else if c[1] != 0.0 {
+        vec![Complex::new(-c[2] / c[1], 0.0)]
+    } else {
+        Vec::new()
+    }
+}
+
+// Coefficients of z^0, z^-1 and z^-2 of the product of (1 - r z^-1), real
+// as long as complex roots come in conjugate pairs
+fn from_roots(roots: &[Complex]) -> [f64; 3] {
+    let mut c = [Complex::ONE, Complex::ZERO, Complex::ZERO];
+    for (k, r) in roots.iter().enumerate() {
+        for i in (1..=k + 1).rev() {
+            c[i] = c[i] - *r * c[i - 1];
+        }
+    }
+    c.map(|c| c.re)
+}
+
+// c[0] + c[1] z^-1 + c[2] z^-2 at z = e^jw
+fn evaluate(c: [f64; 3], w: f64) -> Complex {
+    c.iter().enumerate().fold(Complex::ZERO, |sum, (k, c)| {
+        sum + Complex::from_polar(*c, -w * k as f64)
+    })
+}
+
+// The polynomial g0 + g1 z^-1 + g2 z^-2 with all roots inside the unit
+// circle whose squared magnitude is p0 + p1 c + p2 c^2, with c = cos(w),
+// which must be positive on -1 <= c <= 1. Each root r of the quadratic in c
+// splits into q + 1/q = 2r, and (c - r) = -|1 - q z^-1|^2 / 2q on the unit
+// circle.
+pub(crate) fn spectral_factor(p: [f64; 3]) -> [f64; 3] {
+    let roots = if p[2].abs() > 1e-12 * p[0].abs().max(p[1].abs()) {
+        let root = Complex::new(p[1] * p[1] - 4.0 * p[2] * p[0], 0.0).sqrt();
+        let a = Complex::new(2.0 * p[2], 0.0);
+        vec![
+            (Complex::new(-p[1], 0.0) + root) / a,
+            (Complex::new(-p[1], 0.0) - root) / a,
+        ]
+    } else if p[1].abs() > 1e-12 * p[0].abs() {
+        vec![Complex::new(-p[0] / p[1], 0.0)]
+    } else {
+        vec![]
+    };
+
+    let mut gain = Complex::new(p[roots.len()], 0.0);
+    let mut g = [Complex::ONE, Complex::ZERO, Complex::ZERO];
+    for (k, r) in roots.iter().enumerate() {
+        let mut q = *r - (*r * *r - Complex::ONE).sqrt();
+        if q.norm() > 1.0 {
+            q = Complex::ONE / q;
+        }
+        for i in (1..=k + 1).rev() {
+            g[i] = g[i] - q * g[i - 1];
+        }
+        gain = gain * (Complex::new(-0.5, 0.0) / q);
+    }
+
+    let scale = gain.re.sqrt();
+    g.map(|g| g.re * scale)
+}
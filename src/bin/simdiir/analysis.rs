@@ -0,0 +1,169 @@
+use std::{error::Error, f64::consts::PI, io, slice};
+
+use simdiir::response::{frequency_table, log_frequencies, poles_zeros};
+
+use crate::{
+    chain::{value, Chain, ChainArgs},
+    table::{Cell, Format, Table},
+};
+
+type Args<'a> = slice::Iter<'a, String>;
+
+// Options shared by the analysis commands: the chain, the sample rate to
+// design it at and the output format
+pub struct Options {
+    pub chain: Chain,
+    pub sample_rate: f32,
+    pub format: Format,
+}
+
+pub fn parse<'a>(
+    args: &'a [String],
+    mut extra: impl FnMut(&str, &mut Args<'a>) -> Result<bool, Box<dyn Error>>,
+) -> Result<Options, Box<dyn Error>> {
+    let mut chain = ChainArgs::default();
+    let mut sample_rate = 48000.0;
+    let mut format = Format::Csv;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if chain.parse(arg, &mut args)? || extra(arg, &mut args)? {
+            continue;
+        }
+        match arg.as_str() {
+            "-r" | "--sample-rate" => sample_rate = number(arg, &mut args)?,
+            "--format" => format = Format::parse(value(arg, &mut args)?)?,
+            _ => return Err(format!("unexpected argument '{}'", arg).into()),
+        }
+    }
+
+    Ok(Options {
+        chain: chain.build(sample_rate)?,
+        sample_rate,
+        format,
+    })
+}
+
+pub fn number<'a, T: std::str::FromStr>(
+    arg: &str,
+    args: &mut Args<'a>,
+) -> Result<T, Box<dyn Error>> {
+    let v = value(arg, args)?;
+    v.parse::<T>()
+        .map_err(|_| format!("invalid value '{}' for {}", v, arg).into())
+}
+
+pub fn impulse(args: &[String]) -> Result<(), Box<dyn Error>> {
+    time_response(args, |n| if n == 0 { 1.0 } else { 0.0 })
+}
+
+pub fn step(args: &[String]) -> Result<(), Box<dyn Error>> {
+    time_response(args, |_| 1.0)
+}
+
+fn time_response(args: &[String], input: fn(usize) -> f32) -> Result<(), Box<dyn Error>> {
+    let mut length = 256usize;
+    let options = parse(args, |arg, args| match arg {
+        "-n" | "--length" => {
+            length = number(arg, args)?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    })?;
+
+    let mut samples = (0..length).map(input).collect::<Vec<_>>();
+    options.chain.cascade().process(&mut samples);
+
+    let mut table = Table::new(&["sample", "time_ms", "output"]);
+    for (n, y) in samples.iter().enumerate() {
+        table.push(vec![
+            Cell::Int(n as i64),
+            Cell::Float(n as f64 * 1000.0 / options.sample_rate as f64),
+            Cell::Single(*y),
+        ]);
+    }
+    table.write(options.format, &mut io::stdout().lock())
+}
+
+// Magnitude, phase and group delay over a log frequency grid
+pub fn response(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut points = 200usize;
+    let mut from = 20.0f32;
+    let mut to = None;
+    let options = parse(args, |arg, args| {
+        match arg {
+            "--points" => points = number(arg, args)?,
+            "--from" => from = number(arg, args)?,
+            "--to" => to = Some(number(arg, args)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+
+    let frequencies = frequency_grid(options.sample_rate, from, to, points)?;
+    let rows = frequency_table(&options.chain.coeffs(), options.sample_rate, &frequencies);
+
+    let mut table = Table::new(&[
+        "frequency_hz",
+        "magnitude_db",
+        "phase_degrees",
+        "group_delay_ms",
+    ]);
+    for row in rows {
+        table.push(vec![
+            Cell::Single(row.frequency),
+            Cell::Float(row.magnitude_db),
+            Cell::Float(row.phase_degrees),
+            Cell::Float(row.group_delay_ms),
+        ]);
+    }
+    table.write(options.format, &mut io::stdout().lock())
+}
+
+// Log spaced frequencies from `from` to `to`, or to 20 kHz or as close to
+// Nyquist as the sample rate allows
+pub fn frequency_grid(
+    sample_rate: f32,
+    from: f32,
+    to: Option<f32>,
+    points: usize,
+) -> Result<Vec<f32>, Box<dyn Error>> {
+    let nyquist = sample_rate / 2.0;
+    let to = to.unwrap_or_else(|| 20000.0f32.min(0.99 * nyquist));
+    if !(from > 0.0 && from < to && to < nyquist) {
+        return Err(format!(
+            "the frequency range must be within 0 to {} Hz, not {} to {} Hz",
+            nyquist, from, to
+        )
+        .into());
+    }
+    if points < 2 {
+        return Err(format!("at least 2 frequency points are needed, not {}", points).into());
+    }
+    Ok(log_frequencies(from, to, points))
+}
+
+// Poles and zeros of every section, with their radius and the frequency of
+// their angle
+pub fn poles(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let options = parse(args, |_, _| Ok(false))?;
+    let sample_rate = options.sample_rate as f64;
+
+    let mut table = Table::new(&["section", "type", "re", "im", "radius", "frequency_hz"]);
+    for (i, c) in options.chain.coeffs().iter().enumerate() {
+        let roots = poles_zeros(c);
+        let zeros = roots.zeros.iter().map(|z| ("zero", z));
+        let poles = roots.poles.iter().map(|p| ("pole", p));
+        for (kind, root) in zeros.chain(poles) {
+            table.push(vec![
+                Cell::Int(i as i64),
+                Cell::Text(kind),
+                Cell::Float(root.re),
+                Cell::Float(root.im),
+                Cell::Float(root.norm()),
+                Cell::Float(root.arg().abs() * sample_rate / (2.0 * PI)),
+            ]);
+        }
+    }
+    table.write(options.format, &mut io::stdout().lock())
+}
@@ -0,0 +1,126 @@
+use std::{error::Error, fs, path::Path};
+
+use simdiir::{
+    cascade::{Backend, Cascade},
+    coeffs::BiQuadCoeffs,
+    config::ChainConfig,
+    design::{parse_chain, FilterSpec, RealizationKind},
+};
+
+// Where the filters of the chain come from, in command line order
+enum Source<'a> {
+    Spec(FilterSpec),
+    File(&'a str),
+}
+
+// The `--filter`, `--config` and `--backend` options every command that
+// works on a filter chain takes
+#[derive(Default)]
+pub struct ChainArgs<'a> {
+    sources: Vec<Source<'a>>,
+    backend: Option<Backend>,
+}
+
+pub struct Chain {
+    pub backend: Backend,
+    pub sections: Vec<(BiQuadCoeffs, RealizationKind)>,
+}
+
+impl<'a> ChainArgs<'a> {
+    // Consumes `arg` and its value if it's one of the chain options, returns
+    // false otherwise
+    pub fn parse(
+        &mut self,
+        arg: &'a str,
+        args: &mut impl Iterator<Item = &'a String>,
+    ) -> Result<bool, Box<dyn Error>> {
+        match arg {
+            "-f" | "--filter" => {
+                let spec = value(arg, args)?.parse::<FilterSpec>()?;
+                self.sources.push(Source::Spec(spec));
+            }
+            "-c" | "--config" => self.sources.push(Source::File(value(arg, args)?)),
+            "-b" | "--backend" => {
+                self.backend = match value(arg, args)? {
+                    "auto" => None,
+                    name => Some(name.parse::<Backend>()?),
+                }
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    pub fn push(&mut self, spec: FilterSpec) {
+        self.sources.push(Source::Spec(spec));
+    }
+
+    // The chain takes the backend hint of the first preset that has one,
+    // unless one was given on the command line
+    pub fn build(self, sample_rate: f32) -> Result<Chain, Box<dyn Error>> {
+        let mut chain = ChainConfig::default();
+        for source in self.sources {
+            match source {
+                Source::Spec(spec) => {
+                    spec.validate_at(sample_rate)
+                        .map_err(|e| format!("{}: {}", spec, e))?;
+                    chain.filters.push(spec);
+                }
+                Source::File(path) => {
+                    let preset = load(path, sample_rate).map_err(|e| format!("{}: {}", path, e))?;
+                    if chain.backend == Default::default() {
+                        chain.backend = preset.backend;
+                    }
+                    chain.filters.extend(preset.filters);
+                }
+            }
+        }
+
+        let backend = self.backend.unwrap_or_else(|| chain.backend.resolve());
+        if !backend.is_supported() {
+            return Err(format!("the {} backend isn't supported by this CPU", backend).into());
+        }
+
+        Ok(Chain {
+            backend,
+            sections: chain.sections(sample_rate)?,
+        })
+    }
+}
+
+impl Chain {
+    pub fn cascade(&self) -> Cascade {
+        Cascade::with_realizations(self.backend, &self.sections)
+    }
+
+    pub fn coeffs(&self) -> Vec<BiQuadCoeffs> {
+        self.sections.iter().map(|(c, _)| *c).collect()
+    }
+}
+
+pub fn value<'a>(
+    arg: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Result<&'a str, Box<dyn Error>> {
+    args.next()
+        .map(String::as_str)
+        .ok_or_else(|| format!("missing value for {}", arg).into())
+}
+
+// TOML and JSON presets by extension, anything else is a chain file with one
+// filter per line
+fn load(path: &str, sample_rate: f32) -> Result<ChainConfig, Box<dyn Error>> {
+    let preset = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("toml") | Some("json") => ChainConfig::load(path)?,
+        _ => ChainConfig {
+            filters: parse_chain(&fs::read_to_string(path)?)?,
+            ..Default::default()
+        },
+    };
+    preset.validate(sample_rate)?;
+    Ok(preset)
+}
@@ -0,0 +1,240 @@
+use simdiir::{
+    accuracy::{reference, ErrorStats, TestSignal},
+    biquad_f32::BiQuadF32,
+    biquad_portable::{BiQuadPortable4, BiQuadPortable8},
+    cascade::{Backend, Cascade},
+    design::{FilterKind, FilterSpec},
+    ScopedFlushDenormals,
+};
+#[cfg(target_arch = "x86_64")]
+use simdiir::{biquad_avx::BiQuadAVX, biquad_sse2::BiQuadSSE2};
+use std::{convert::TryFrom, error::Error, io};
+use wide::{f32x4, f32x8};
+
+use crate::{
+    analysis::number,
+    chain::{value, ChainArgs},
+    table::{Cell, Format, Table},
+};
+
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    if args.iter().any(|arg| arg == "--report") {
+        let args = args
+            .iter()
+            .filter(|arg| *arg != "--report")
+            .cloned()
+            .collect::<Vec<_>>();
+        return report(&args);
+    }
+    if let Some(arg) = args.first() {
+        return Err(format!("unexpected argument '{}'", arg).into());
+    }
+    dump()
+}
+
+// Error of every supported backend against the chain computed in double
+// precision, for each test signal. Without filters the chain is the 1200 Hz
+// allpass `dump` runs.
+fn report(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut chain = ChainArgs::default();
+    let mut sample_rate = 48000.0;
+    let mut format = Format::Text;
+    let mut length = 65536usize;
+    let mut signals = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if chain.parse(arg, &mut args)? {
+            continue;
+        }
+        match arg.as_str() {
+            "-r" | "--sample-rate" => sample_rate = number(arg, &mut args)?,
+            "--format" => format = Format::parse(value(arg, &mut args)?)?,
+            "-n" | "--length" => length = number(arg, &mut args)?,
+            "-s" | "--signal" => signals.push(value(arg, &mut args)?.parse::<TestSignal>()?),
+            _ => return Err(format!("unexpected argument '{}'", arg).into()),
+        }
+    }
+    if chain.is_empty() {
+        chain.push(FilterSpec::new(FilterKind::Allpass, 1200.0));
+    }
+    if signals.is_empty() {
+        signals = TestSignal::ALL.to_vec();
+    }
+    if length < 4 {
+        return Err("the signals must be at least 4 samples long".into());
+    }
+
+    let chain = chain.build(sample_rate)?;
+    let coeffs = chain.coeffs();
+
+    // Every supported backend, and the scalar one once more with denormals
+    // flushed
+    let mut runs = Backend::ALL
+        .iter()
+        .filter(|backend| backend.is_supported())
+        .map(|backend| (backend.name(), *backend, false))
+        .collect::<Vec<_>>();
+    runs.insert(1, ("scalar flush", Backend::Scalar, true));
+
+    let mut table = Table::new(&[
+        "signal",
+        "backend",
+        "max_abs_error",
+        "rms_error",
+        "snr_db",
+        "error_growth_db",
+    ]);
+    for signal in signals {
+        let input = signal.generate(length, sample_rate);
+        let expected = reference(&coeffs, &input);
+
+        for (name, backend, flush) in &runs {
+            let mut output = input.clone();
+            let mut cascade = Cascade::with_realizations(*backend, &chain.sections);
+            let guard = if *flush {
+                let guard = ScopedFlushDenormals::new();
+                if !guard.is_active() {
+                    cascade.set_flush_denormals(true);
+                }
+                Some(guard)
+            } else {
+                None
+            };
+            cascade.process(&mut output);
+            drop(guard);
+
+            let stats = ErrorStats::measure(&output, &expected);
+            table.push(vec![
+                Cell::Text(signal.name()),
+                Cell::Text(name),
+                Cell::Float(stats.max_abs_error),
+                Cell::Float(stats.rms_error),
+                Cell::Float(stats.snr_db),
+                stats.error_growth_db.map_or(Cell::Missing, Cell::Float),
+            ]);
+        }
+    }
+    table.write(format, &mut io::stdout().lock())
+}
+
+// Impulse response of the default 1200 Hz allpass through every kernel, side
+// by side as CSV
+fn dump() -> Result<(), Box<dyn Error>> {
+    let impulse = {
+        let mut data = vec![0.0; 256];
+        data[0] = 1.0;
+        data
+    };
+
+    let mut columns: Vec<(&str, Vec<f32>)> = vec![("input", impulse.clone())];
+
+    columns.push(("f32 denorm", {
+        let mut b = BiQuadF32::new();
+        let mut output = vec![0.0; impulse.len()];
+        run_f32(&mut b, &impulse, &mut output);
+        output
+    }));
+
+    columns.push(("f32 flush denorm", {
+        let mut b = BiQuadF32::new();
+        let mut output = vec![0.0; impulse.len()];
+        run_f32_no_denorm(&mut b, &impulse, &mut output);
+        output
+    }));
+
+    #[cfg(target_arch = "x86_64")]
+    columns.push(("sse2", {
+        let mut b = BiQuadSSE2::new();
+        let mut output = vec![0.0; impulse.len()];
+        run_sse2(&mut b, &impulse, &mut output);
+        output
+    }));
+
+    #[cfg(target_arch = "x86_64")]
+    columns.push(("avx", {
+        let mut b = BiQuadAVX::new();
+        let mut output = vec![0.0; impulse.len()];
+        run_avx(&mut b, &impulse, &mut output);
+        output
+    }));
+
+    columns.push(("portable4", {
+        let mut b = BiQuadPortable4::new();
+        let mut output = vec![0.0; impulse.len()];
+        run_portable4(&mut b, &impulse, &mut output);
+        output
+    }));
+
+    columns.push(("portable8", {
+        let mut b = BiQuadPortable8::new();
+        let mut output = vec![0.0; impulse.len()];
+        run_portable8(&mut b, &impulse, &mut output);
+        output
+    }));
+
+    let header = columns.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    println!("{}", header.join(","));
+    for i in 0..impulse.len() {
+        let row = columns
+            .iter()
+            .map(|(_, data)| data[i].to_string())
+            .collect::<Vec<_>>();
+        println!("{}", row.join(","));
+    }
+
+    Ok(())
+}
+
+fn run_f32(b: &mut BiQuadF32, input: &[f32], output: &mut [f32]) {
+    for (input, output) in input.iter().zip(output.iter_mut()) {
+        *output = b.process(*input)
+    }
+}
+
+fn run_f32_no_denorm(b: &mut BiQuadF32, input: &[f32], output: &mut [f32]) {
+    let guard = ScopedFlushDenormals::new();
+    if !guard.is_active() {
+        b.set_flush_denormals(true);
+    }
+
+    for (input, output) in input.iter().zip(output.iter_mut()) {
+        *output = b.process(*input)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn run_sse2(b: &mut BiQuadSSE2, input: &[f32], output: &mut [f32]) {
+    use std::arch::x86_64::{_mm_loadu_ps, _mm_storeu_ps};
+    for (input, output) in input.chunks_exact(4).zip(output.chunks_exact_mut(4)) {
+        unsafe {
+            let y = b.process(_mm_loadu_ps(input.as_ptr()));
+            _mm_storeu_ps(output.as_mut_ptr(), y);
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn run_avx(b: &mut BiQuadAVX, input: &[f32], output: &mut [f32]) {
+    use std::arch::x86_64::{_mm256_loadu_ps, _mm256_storeu_ps};
+    for (input, output) in input.chunks_exact(8).zip(output.chunks_exact_mut(8)) {
+        unsafe {
+            let y = b.process(_mm256_loadu_ps(input.as_ptr()));
+            _mm256_storeu_ps(output.as_mut_ptr(), y);
+        }
+    }
+}
+
+fn run_portable4(b: &mut BiQuadPortable4, input: &[f32], output: &mut [f32]) {
+    for (input, output) in input.chunks_exact(4).zip(output.chunks_exact_mut(4)) {
+        let x = f32x4::from(<[f32; 4]>::try_from(input).unwrap());
+        output.copy_from_slice(&b.process(x).to_array());
+    }
+}
+
+fn run_portable8(b: &mut BiQuadPortable8, input: &[f32], output: &mut [f32]) {
+    for (input, output) in input.chunks_exact(8).zip(output.chunks_exact_mut(8)) {
+        let x = f32x8::from(<[f32; 8]>::try_from(input).unwrap());
+        output.copy_from_slice(&b.process(x).to_array());
+    }
+}
@@ -0,0 +1,42 @@
+use std::error::Error;
+
+use simdiir::ScopedFlushDenormals;
+
+use crate::{chain::ChainArgs, wav};
+
+// Filters a WAV file through a chain of biquads given with any number of
+// `--filter` and `--config` options, applied in the order they appear
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut chain = ChainArgs::default();
+    let mut paths = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if chain.parse(arg, &mut args)? {
+            continue;
+        }
+        if arg.starts_with('-') {
+            return Err(format!("unknown option '{}'", arg).into());
+        }
+        paths.push(arg.as_str());
+    }
+
+    let (input, output) = match paths.as_slice() {
+        [input, output] => (*input, *output),
+        _ => return Err("expected an input and an output file".into()),
+    };
+
+    let mut audio = wav::read(input)?;
+    let chain = chain.build(audio.sample_rate())?;
+
+    let guard = ScopedFlushDenormals::new();
+    for channel in &mut audio.channels {
+        let mut cascade = chain.cascade();
+        if !guard.is_active() {
+            cascade.set_flush_denormals(true);
+        }
+        cascade.process(channel);
+    }
+
+    wav::write(output, &audio)
+}
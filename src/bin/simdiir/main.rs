@@ -0,0 +1,85 @@
+mod analysis;
+mod chain;
+mod compare;
+mod filter;
+#[cfg(feature = "plot")]
+mod plot;
+mod table;
+mod wav;
+
+use std::{env, process};
+
+const USAGE: &str = "\
+usage: simdiir <command> [options]
+
+commands:
+  filter [chain options] <input.wav> <output.wav>
+      filter a WAV file, each channel on its own
+  impulse [chain options] [analysis options] [-n, --length <samples>]
+  step [chain options] [analysis options] [-n, --length <samples>]
+      print the impulse or step response, 256 samples by default
+  response [chain options] [analysis options] [--points <n>]
+           [--from <hz>] [--to <hz>]
+      print magnitude, phase and group delay over a log frequency grid,
+      200 points from 20 Hz to 20 kHz by default
+  poles [chain options] [analysis options]
+      print the poles and zeros of every section
+  plot [chain options] [-r, --sample-rate <hz>] [-o, --output <dir>]
+       [-n, --length <samples>] [--points <n>] [--from <hz>] [--to <hz>]
+      write bode.svg, group_delay.svg, impulse.svg and poles.svg into the
+      output directory, the current one by default
+  compare
+      print the impulse response of every kernel as CSV
+  compare --report [chain options] [analysis options] [-n, --length <samples>]
+                   [-s, --signal <impulse|noise|sweep>]
+      print the error of every backend against a double precision reference
+      for impulse, noise and sine sweep inputs, 65536 samples each, as text
+      by default; the chain is a 1200 Hz allpass unless filters are given
+  help
+      print this message
+
+chain options:
+  -f, --filter <type:frequency[:q[:gain_db]]>
+      add a biquad to the chain; type is one of lowpass, highpass, bandpass,
+      notch, peak, lowshelf, highshelf, allpass
+  -c, --config <file>
+      add the filters of a preset: TOML or JSON by extension, otherwise a
+      chain file with one filter per line in the --filter form
+  -b, --backend <auto|scalar|portable4|portable8|sse2|avx>
+      kernel to run the chain with, the fastest one by default
+
+analysis options:
+  -r, --sample-rate <hz>
+      sample rate to design the chain at, 48000 by default
+  --format <csv|json|text>
+      output format, CSV by default
+";
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let result = match args.first().map(String::as_str) {
+        Some("filter") => filter::run(&args[1..]),
+        Some("impulse") => analysis::impulse(&args[1..]),
+        Some("step") => analysis::step(&args[1..]),
+        Some("response") => analysis::response(&args[1..]),
+        Some("poles") => analysis::poles(&args[1..]),
+        #[cfg(feature = "plot")]
+        Some("plot") => plot::run(&args[1..]),
+        Some("compare") => compare::run(&args[1..]),
+        Some("help") | Some("-h") | Some("--help") => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        Some(command) => Err(format!("unknown command '{}'", command).into()),
+        None => {
+            eprint!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,55 @@
+use std::{error::Error, fs, path::Path};
+
+use simdiir::plot;
+
+use crate::{
+    analysis::{frequency_grid, number, parse},
+    chain::value,
+};
+
+// Writes Bode, group delay, impulse response and pole-zero plots of the
+// chain as SVG files into the output directory
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut output = ".".to_string();
+    let mut length = 256usize;
+    let mut points = 200usize;
+    let mut from = 20.0f32;
+    let mut to = None;
+    let options = parse(args, |arg, args| {
+        match arg {
+            "-o" | "--output" => output = value(arg, args)?.to_string(),
+            "-n" | "--length" => length = number(arg, args)?,
+            "--points" => points = number(arg, args)?,
+            "--from" => from = number(arg, args)?,
+            "--to" => to = Some(number(arg, args)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+
+    let sample_rate = options.sample_rate;
+    let coeffs = options.chain.coeffs();
+    let frequencies = frequency_grid(sample_rate, from, to, points)?;
+
+    let mut impulse = (0..length)
+        .map(|n| if n == 0 { 1.0 } else { 0.0 })
+        .collect::<Vec<_>>();
+    options.chain.cascade().process(&mut impulse);
+
+    let output = Path::new(&output);
+    fs::create_dir_all(output)?;
+    let plots = [
+        ("bode.svg", plot::bode(&coeffs, sample_rate, &frequencies)),
+        (
+            "group_delay.svg",
+            plot::group_delay(&coeffs, sample_rate, &frequencies),
+        ),
+        ("impulse.svg", plot::impulse(&impulse, sample_rate)),
+        ("poles.svg", plot::pole_zero(&coeffs)),
+    ];
+    for (name, svg) in plots.iter() {
+        let path = output.join(name);
+        fs::write(&path, svg).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use std::{error::Error, io::Write};
+
+use serde_json::{Map, Value};
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Format {
+    Csv,
+    Json,
+    // Aligned columns for reading in a terminal
+    Text,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Result<Self, Box<dyn Error>> {
+        match name {
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            "text" => Ok(Format::Text),
+            _ => Err(format!("unknown format '{}', expected csv, json or text", name).into()),
+        }
+    }
+}
+
+pub enum Cell {
+    Int(i64),
+    Float(f64),
+    // Printed with the shortest representation of the f32 value rather than
+    // all the digits of its exact value
+    Single(f32),
+    Text(&'static str),
+    // A value that couldn't be computed, n/a in text and null in JSON
+    Missing,
+}
+
+impl Cell {
+    fn to_csv(&self) -> String {
+        match self {
+            Cell::Int(v) => v.to_string(),
+            Cell::Float(v) => v.to_string(),
+            Cell::Single(v) => v.to_string(),
+            Cell::Text(v) => v.to_string(),
+            Cell::Missing => "n/a".to_string(),
+        }
+    }
+
+    // Four significant digits, in scientific notation for the very small
+    // and very large values errors tend to be
+    fn to_text(&self) -> String {
+        let float = |v: f64| {
+            if v == 0.0 || !v.is_finite() || (1e-3..1e6).contains(&v.abs()) {
+                format!("{:.3}", v)
+            } else {
+                format!("{:.3e}", v)
+            }
+        };
+        match self {
+            Cell::Float(v) => float(*v),
+            Cell::Single(v) => float(*v as f64),
+            _ => self.to_csv(),
+        }
+    }
+
+    // JSON has no NaN or infinity, those become null
+    fn to_json(&self) -> Value {
+        match self {
+            Cell::Int(v) => Value::from(*v),
+            Cell::Float(v) => Value::from(*v),
+            Cell::Single(v) => v
+                .to_string()
+                .parse::<f64>()
+                .map_or(Value::Null, Value::from),
+            Cell::Text(v) => Value::from(*v),
+            Cell::Missing => Value::Null,
+        }
+    }
+}
+
+// Rows of named columns, printed as CSV with a header line or as a JSON
+// array of objects, which keep their keys in column order
+pub struct Table {
+    columns: Vec<&'static str>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub fn new(columns: &[&'static str]) -> Self {
+        Table {
+            columns: columns.to_vec(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<Cell>) {
+        debug_assert_eq!(row.len(), self.columns.len());
+        self.rows.push(row);
+    }
+
+    pub fn write(&self, format: Format, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+        match format {
+            Format::Csv => {
+                writeln!(out, "{}", self.columns.join(","))?;
+                for row in &self.rows {
+                    let cells = row.iter().map(Cell::to_csv).collect::<Vec<_>>();
+                    writeln!(out, "{}", cells.join(","))?;
+                }
+            }
+            Format::Json => {
+                let rows = self
+                    .rows
+                    .iter()
+                    .map(|row| {
+                        let object = self
+                            .columns
+                            .iter()
+                            .zip(row.iter())
+                            .map(|(name, cell)| (name.to_string(), cell.to_json()))
+                            .collect::<Map<_, _>>();
+                        Value::Object(object)
+                    })
+                    .collect::<Vec<_>>();
+                serde_json::to_writer_pretty(&mut *out, &rows)?;
+                writeln!(out)?;
+            }
+            Format::Text => {
+                let rows = self
+                    .rows
+                    .iter()
+                    .map(|row| row.iter().map(Cell::to_text).collect::<Vec<_>>())
+                    .collect::<Vec<_>>();
+                let widths = self
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| {
+                        rows.iter()
+                            .map(|row| row[i].len())
+                            .fold(name.len(), usize::max)
+                    })
+                    .collect::<Vec<_>>();
+
+                // Text columns left aligned, numbers right aligned
+                let left = (0..self.columns.len())
+                    .map(|i| {
+                        self.rows
+                            .first()
+                            .map_or(true, |row| matches!(row[i], Cell::Text(_)))
+                    })
+                    .collect::<Vec<_>>();
+                let line = |cells: Vec<&str>| {
+                    let cells = cells
+                        .iter()
+                        .zip(widths.iter().zip(left.iter()))
+                        .map(|(cell, (width, left))| {
+                            if *left {
+                                format!("{:<1$}", cell, width)
+                            } else {
+                                format!("{:>1$}", cell, width)
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    cells.join("  ").trim_end().to_string()
+                };
+
+                writeln!(out, "{}", line(self.columns.clone()))?;
+                for row in &rows {
+                    writeln!(out, "{}", line(row.iter().map(String::as_str).collect()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
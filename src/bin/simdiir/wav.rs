@@ -0,0 +1,83 @@
+use std::error::Error;
+
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+
+// A whole file, one buffer per channel, samples scaled to [-1, 1)
+pub struct Audio {
+    pub spec: WavSpec,
+    pub channels: Vec<Vec<f32>>,
+}
+
+impl Audio {
+    pub fn sample_rate(&self) -> f32 {
+        self.spec.sample_rate as f32
+    }
+}
+
+pub fn read(path: &str) -> Result<Audio, Box<dyn Error>> {
+    let mut reader = WavReader::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let spec = reader.spec();
+    if spec.channels == 0 {
+        return Err(format!("{}: the header has no channels", path).into());
+    }
+
+    let interleaved = match (spec.sample_format, spec.bits_per_sample) {
+        (SampleFormat::Float, 32) => reader.samples::<f32>().collect::<Result<Vec<_>, _>>(),
+        (SampleFormat::Int, bits @ 8..=32) => {
+            let scale = 1.0 / full_scale(bits);
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| (s as f64 * scale) as f32))
+                .collect::<Result<Vec<_>, _>>()
+        }
+        (format, bits) => {
+            return Err(format!(
+                "{}: unsupported sample format, {} bit {:?}",
+                path, bits, format
+            )
+            .into())
+        }
+    }
+    .map_err(|e| format!("{}: {}", path, e))?;
+
+    let count = spec.channels as usize;
+    let mut channels = vec![Vec::with_capacity(interleaved.len() / count); count];
+    for frame in interleaved.chunks_exact(count) {
+        for (channel, sample) in channels.iter_mut().zip(frame.iter()) {
+            channel.push(*sample);
+        }
+    }
+
+    Ok(Audio { spec, channels })
+}
+
+// Writes in the same sample format the input had. Integer formats are
+// rounded and clipped to full scale.
+pub fn write(path: &str, audio: &Audio) -> Result<(), Box<dyn Error>> {
+    let spec = audio.spec;
+    let mut writer = WavWriter::create(path, spec).map_err(|e| format!("{}: {}", path, e))?;
+
+    let frames = audio.channels.first().map_or(0, Vec::len);
+    for i in 0..frames {
+        for channel in &audio.channels {
+            match spec.sample_format {
+                SampleFormat::Float => writer.write_sample(channel[i])?,
+                SampleFormat::Int => {
+                    writer.write_sample(quantize(channel[i], spec.bits_per_sample))?
+                }
+            }
+        }
+    }
+
+    writer.finalize()?;
+    Ok(())
+}
+
+fn full_scale(bits: u16) -> f64 {
+    (1u64 << (bits - 1)) as f64
+}
+
+fn quantize(sample: f32, bits: u16) -> i32 {
+    let full = full_scale(bits);
+    (sample as f64 * full).round().max(-full).min(full - 1.0) as i32
+}
@@ -1,5 +1,7 @@
 use std::arch::x86_64::*;
 
+use crate::{coeffs::BiQuadCoeffs, denormals::DENORMAL_FLUSH_THRESHOLD};
+
 pub struct BiQuadAVX {
     c_xp7: __m256,
     c_xp6: __m256,
@@ -18,6 +20,15 @@ pub struct BiQuadAVX {
     xm2: __m256,
     ym1: __m256,
     ym2: __m256,
+
+    flush_denormals: bool,
+    coeffs: BiQuadCoeffs,
+}
+
+impl Default for BiQuadAVX {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BiQuadAVX {
@@ -40,6 +51,8 @@ impl BiQuadAVX {
                 xm2: _mm256_setzero_ps(),
                 ym1: _mm256_setzero_ps(),
                 ym2: _mm256_setzero_ps(),
+                flush_denormals: false,
+                coeffs: BiQuadCoeffs::identity(),
             };
             b.update(44100.0, 1200.0);
             b
@@ -47,15 +60,13 @@ impl BiQuadAVX {
     }
 
     pub fn update(&mut self, sample_rate: f32, cutoff: f32) {
-        unsafe {
-            let t = (std::f32::consts::PI * cutoff / sample_rate).tan();
-            let alpha = (t - 1.0) / (t + 1.0);
+        self.set_coeffs(&BiQuadCoeffs::allpass(sample_rate, cutoff));
+    }
 
-            let a0 = alpha;
-            let a1 = 1.0;
-            let a2 = 0.0;
-            let b1 = alpha;
-            let b2 = 0.0;
+    pub fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs) {
+        unsafe {
+            self.coeffs = *coeffs;
+            let BiQuadCoeffs { a0, a1, a2, b1, b2 } = *coeffs;
 
             const COLUMNS: usize = 12;
             const ROWS: usize = 8;
@@ -73,6 +84,7 @@ impl BiQuadAVX {
                 /* y[n+7] = */ [     a0,    a1,    a2,   0.0,   0.0,   0.0,   0.0,   0.0,   0.0,   0.0,       0.0,   0.0], // - b1 * y[n-6] - b2 * y[n-5]
             ];
 
+            #[allow(clippy::needless_range_loop)]
             for col in 0..COLUMNS {
                 // Add -b1 * y[n] to y[n+1] to y[n+1]
                 coeffs[1][col] += -b1 * coeffs[0][col];
@@ -219,6 +231,12 @@ impl BiQuadAVX {
         }
     }
 
+    // Same as `BiQuadF32::set_flush_denormals`, applied to all lanes before
+    // the last two outputs are fed back
+    pub fn set_flush_denormals(&mut self, enabled: bool) {
+        self.flush_denormals = enabled;
+    }
+
     pub fn process(&mut self, input: __m256) -> __m256 {
         unsafe {
             let v_x0 = _mm256_permutevar8x32_ps(input, _mm256_set1_epi32(0));
@@ -245,12 +263,46 @@ impl BiQuadAVX {
             y1 = _mm256_fmadd_ps(self.c_ym1, self.ym1, y1);
             y2 = _mm256_fmadd_ps(self.c_ym2, self.ym2, y2);
 
-            let y = _mm256_add_ps(y1, y2);
+            let mut y = _mm256_add_ps(y1, y2);
+
+            if self.flush_denormals {
+                let magnitude = _mm256_andnot_ps(_mm256_set1_ps(-0.0), y);
+                let keep = _mm256_cmp_ps(
+                    magnitude,
+                    _mm256_set1_ps(DENORMAL_FLUSH_THRESHOLD),
+                    _CMP_GE_OQ,
+                );
+                y = _mm256_and_ps(y, keep);
+            }
+
+            // _mm256_shuffle_ps only shuffles within each 128-bit half, so
+            // broadcast the last two outputs across all eight lanes with a
+            // full permute
+            self.xm2 = v_xp6;
+            self.xm1 = v_xp7;
+            self.ym2 = _mm256_permutevar8x32_ps(y, _mm256_set1_epi32(6));
+            self.ym1 = _mm256_permutevar8x32_ps(y, _mm256_set1_epi32(7));
+
+            y
+        }
+    }
+
+    // One sample through the same recursion, for the samples at the end of a
+    // buffer that don't fill a vector. Leaves the state as `process` would.
+    pub fn process_sample(&mut self, input: f32) -> f32 {
+        unsafe {
+            let BiQuadCoeffs { a0, a1, a2, b1, b2 } = self.coeffs;
+            let (x1, x2) = (_mm256_cvtss_f32(self.xm1), _mm256_cvtss_f32(self.xm2));
+            let (y1, y2) = (_mm256_cvtss_f32(self.ym1), _mm256_cvtss_f32(self.ym2));
+            let mut y = a0 * input + a1 * x1 + a2 * x2 - b1 * y1 - b2 * y2;
+            if self.flush_denormals && y.abs() < DENORMAL_FLUSH_THRESHOLD {
+                y = 0.0;
+            }
 
-            self.xm2 = v_xp2;
-            self.xm1 = v_xp3;
-            self.ym2 = _mm256_shuffle_ps(y, y, 0b10_10_10_10);
-            self.ym1 = _mm256_shuffle_ps(y, y, 0b11_11_11_11);
+            self.xm2 = self.xm1;
+            self.xm1 = _mm256_set1_ps(input);
+            self.ym2 = self.ym1;
+            self.ym1 = _mm256_set1_ps(y);
 
             y
         }
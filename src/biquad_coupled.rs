@@ -0,0 +1,175 @@
+use wide::f32x4;
+
+use crate::coeffs::BiQuadCoeffs;
+
+// Coefficients of the coupled (Gold-Rader) form. The poles r e^(+-j theta)
+// are realized by rotating the two state variables by theta and scaling them
+// by r every sample:
+//
+//   s1[n+1] = r cos(theta) s1[n] - r sin(theta) s2[n] + x[n]
+//   s2[n+1] = r sin(theta) s1[n] + r cos(theta) s2[n]
+//   y[n]    = d x[n] + c1 s1[n] + c2 s2[n]
+//
+// Unlike the direct form, where the pole positions are quantized on a grid
+// that gets very coarse near z = 1, the possible pole positions are spread
+// uniformly over the unit disc.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CoupledFormCoeffs {
+    pub r_cos: f32,
+    pub r_sin: f32,
+    pub d: f32,
+    pub c1: f32,
+    pub c2: f32,
+}
+
+impl CoupledFormCoeffs {
+    // All-pole resonator 1 / (1 - 2 r cos(theta) z^-1 + r^2 z^-2)
+    pub fn from_pole(radius: f32, angle: f32) -> Self {
+        Self::from_pole_with_numerator(radius, angle, [1.0, 0.0, 0.0])
+    }
+
+    // Poles given by radius and angle, zeros by the direct form numerator
+    // a0 + a1 z^-1 + a2 z^-2. The angle must be strictly between 0 and pi.
+    pub fn from_pole_with_numerator(radius: f32, angle: f32, numerator: [f32; 3]) -> Self {
+        let r = radius as f64;
+        let (sin, cos) = (angle as f64).sin_cos();
+        let [a0, a1, a2] = numerator;
+        let (a0, a1, a2) = (a0 as f64, a1 as f64, a2 as f64);
+
+        let r_cos = r * cos;
+        let r_sin = r * sin;
+        let b1 = -2.0 * r_cos;
+        let b2 = r * r;
+
+        // Match d + (c1 (z - r cos) + c2 r sin) / (z^2 - 2 r cos z + r^2)
+        // against the direct form transfer function
+        let d = a0;
+        let c1 = a1 - b1 * a0;
+        let c2 = (a2 - a0 * b2 + c1 * r_cos) / r_sin;
+
+        CoupledFormCoeffs {
+            r_cos: r_cos as f32,
+            r_sin: r_sin as f32,
+            d: d as f32,
+            c1: c1 as f32,
+            c2: c2 as f32,
+        }
+    }
+
+    // Converts direct form coefficients, for example from `BiQuadF32::coeffs`.
+    // Returns `None` if the poles are real, which the coupled form can't
+    // represent.
+    pub fn from_biquad(c: &BiQuadCoeffs) -> Option<Self> {
+        let (b1, b2) = (c.b1 as f64, c.b2 as f64);
+        if b2 <= 0.0 || b1 * b1 >= 4.0 * b2 {
+            return None;
+        }
+
+        let radius = b2.sqrt();
+        let angle = (-b1 / (2.0 * radius)).acos();
+
+        Some(Self::from_pole_with_numerator(
+            radius as f32,
+            angle as f32,
+            [c.a0, c.a1, c.a2],
+        ))
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct CoupledFormBiQuad {
+    pub coeffs: CoupledFormCoeffs,
+
+    s1: f32,
+    s2: f32,
+}
+
+impl CoupledFormBiQuad {
+    pub fn new(coeffs: CoupledFormCoeffs) -> Self {
+        CoupledFormBiQuad {
+            coeffs,
+            s1: 0.0,
+            s2: 0.0,
+        }
+    }
+
+    pub fn set_coeffs(&mut self, coeffs: &CoupledFormCoeffs) {
+        self.coeffs = *coeffs;
+    }
+
+    pub fn reset(&mut self) {
+        self.s1 = 0.0;
+        self.s2 = 0.0;
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let c = &self.coeffs;
+        let y = c.d * input + c.c1 * self.s1 + c.c2 * self.s2;
+
+        let s1 = c.r_cos * self.s1 - c.r_sin * self.s2 + input;
+        let s2 = c.r_sin * self.s1 + c.r_cos * self.s2;
+        self.s1 = s1;
+        self.s2 = s2;
+
+        y
+    }
+}
+
+// Four independent coupled form sections, one channel per lane, each with
+// its own coefficients.
+pub struct CoupledFormPortable4 {
+    r_cos: f32x4,
+    r_sin: f32x4,
+    d: f32x4,
+    c1: f32x4,
+    c2: f32x4,
+
+    s1: f32x4,
+    s2: f32x4,
+}
+
+impl CoupledFormPortable4 {
+    pub fn new(coeffs: [CoupledFormCoeffs; 4]) -> Self {
+        let mut b = CoupledFormPortable4 {
+            r_cos: f32x4::ZERO,
+            r_sin: f32x4::ZERO,
+            d: f32x4::ZERO,
+            c1: f32x4::ZERO,
+            c2: f32x4::ZERO,
+            s1: f32x4::ZERO,
+            s2: f32x4::ZERO,
+        };
+        b.set_coeffs(&coeffs);
+        b
+    }
+
+    pub fn set_coeffs(&mut self, coeffs: &[CoupledFormCoeffs; 4]) {
+        let lanes = |f: fn(&CoupledFormCoeffs) -> f32| {
+            f32x4::from([f(&coeffs[0]), f(&coeffs[1]), f(&coeffs[2]), f(&coeffs[3])])
+        };
+
+        self.r_cos = lanes(|c| c.r_cos);
+        self.r_sin = lanes(|c| c.r_sin);
+        self.d = lanes(|c| c.d);
+        self.c1 = lanes(|c| c.c1);
+        self.c2 = lanes(|c| c.c2);
+    }
+
+    pub fn reset(&mut self) {
+        self.s1 = f32x4::ZERO;
+        self.s2 = f32x4::ZERO;
+    }
+
+    pub fn process(&mut self, input: f32x4) -> f32x4 {
+        let y = self
+            .c1
+            .mul_add(self.s1, self.c2.mul_add(self.s2, self.d * input));
+
+        let s1 = self.r_cos.mul_add(self.s1, input) - self.r_sin * self.s2;
+        let s2 = self.r_sin.mul_add(self.s1, self.r_cos * self.s2);
+        self.s1 = s1;
+        self.s2 = s2;
+
+        y
+    }
+}
@@ -0,0 +1,206 @@
+use wide::{f32x4, f64x4};
+
+use crate::{
+    coeffs::BiQuadCoeffs,
+    iir_block::{block_matrix, MAX_ORDER},
+};
+
+// Direct Form I biquad with error feedback. The accumulator is double
+// precision and the state is kept in f32, but the rounding residual of each
+// output is stored next to it and added back on the next samples, so the
+// feedback path sees the output at full accumulator precision. This removes
+// most of the quantization noise `BiQuadF32` has at low cutoffs, where the
+// poles sit right next to z = 1 and amplify it.
+#[derive(Copy, Clone)]
+pub struct BiQuadErrorFeedback {
+    pub a0: f32,
+    pub a1: f32,
+    pub a2: f32,
+    pub b1: f32,
+    pub b2: f32,
+
+    z_a1: f32,
+    z_a2: f32,
+    z_b1: f32,
+    z_b2: f32,
+
+    e_b1: f32,
+    e_b2: f32,
+}
+
+impl Default for BiQuadErrorFeedback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BiQuadErrorFeedback {
+    pub fn new() -> Self {
+        let mut b = Self {
+            a0: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            b1: 0.0,
+            b2: 0.0,
+            z_a1: 0.0,
+            z_a2: 0.0,
+            z_b1: 0.0,
+            z_b2: 0.0,
+            e_b1: 0.0,
+            e_b2: 0.0,
+        };
+        b.update(44100.0, 1200.0);
+        b
+    }
+
+    pub fn update(&mut self, sample_rate: f32, cutoff: f32) {
+        self.set_coeffs(&BiQuadCoeffs::allpass(sample_rate, cutoff));
+    }
+
+    pub fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs) {
+        self.a0 = coeffs.a0;
+        self.a1 = coeffs.a1;
+        self.a2 = coeffs.a2;
+        self.b1 = coeffs.b1;
+        self.b2 = coeffs.b2;
+    }
+
+    pub fn reset(&mut self) {
+        self.z_a1 = 0.0;
+        self.z_a2 = 0.0;
+        self.z_b1 = 0.0;
+        self.z_b2 = 0.0;
+        self.e_b1 = 0.0;
+        self.e_b2 = 0.0;
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let xn = input as f64;
+        let yn1 = self.z_b1 as f64 + self.e_b1 as f64;
+        let yn2 = self.z_b2 as f64 + self.e_b2 as f64;
+
+        let acc = self.a0 as f64 * xn
+            + self.a1 as f64 * self.z_a1 as f64
+            + self.a2 as f64 * self.z_a2 as f64
+            - self.b1 as f64 * yn1
+            - self.b2 as f64 * yn2;
+
+        let yn = acc as f32;
+
+        self.z_b2 = self.z_b1;
+        self.z_b1 = yn;
+        self.e_b2 = self.e_b1;
+        self.e_b1 = (acc - yn as f64) as f32;
+
+        self.z_a2 = self.z_a1;
+        self.z_a1 = input;
+
+        yn
+    }
+}
+
+// Four samples per step with the same block matrix as `BiQuadPortable4`, but
+// accumulated in double precision lanes, with the rounding residual of the
+// last two outputs fed back into the next block.
+pub struct BiQuadErrorFeedbackPortable4 {
+    c_x: [f64x4; 4],
+    c_xm1: f64x4,
+    c_xm2: f64x4,
+    c_ym1: f64x4,
+    c_ym2: f64x4,
+
+    xm1: f32,
+    xm2: f32,
+    ym1: f32,
+    ym2: f32,
+    em1: f32,
+    em2: f32,
+}
+
+impl Default for BiQuadErrorFeedbackPortable4 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BiQuadErrorFeedbackPortable4 {
+    pub fn new() -> Self {
+        let mut b = BiQuadErrorFeedbackPortable4 {
+            c_x: [f64x4::ZERO; 4],
+            c_xm1: f64x4::ZERO,
+            c_xm2: f64x4::ZERO,
+            c_ym1: f64x4::ZERO,
+            c_ym2: f64x4::ZERO,
+            xm1: 0.0,
+            xm2: 0.0,
+            ym1: 0.0,
+            ym2: 0.0,
+            em1: 0.0,
+            em2: 0.0,
+        };
+        b.update(44100.0, 1200.0);
+        b
+    }
+
+    pub fn update(&mut self, sample_rate: f32, cutoff: f32) {
+        self.set_coeffs(&BiQuadCoeffs::allpass(sample_rate, cutoff));
+    }
+
+    pub fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs) {
+        let mut num = [0.0f64; MAX_ORDER + 1];
+        let mut den = [0.0f64; MAX_ORDER + 1];
+        num[0] = coeffs.a0 as f64;
+        num[1] = coeffs.a1 as f64;
+        num[2] = coeffs.a2 as f64;
+        den[0] = 1.0;
+        den[1] = coeffs.b1 as f64;
+        den[2] = coeffs.b2 as f64;
+
+        let m = block_matrix::<4>(&num, &den, 2);
+        for (c, col) in self.c_x.iter_mut().zip(m.x.iter()) {
+            *c = f64x4::from(*col);
+        }
+        self.c_xm1 = f64x4::from(m.xm[0]);
+        self.c_xm2 = f64x4::from(m.xm[1]);
+        self.c_ym1 = f64x4::from(m.ym[0]);
+        self.c_ym2 = f64x4::from(m.ym[1]);
+    }
+
+    pub fn reset(&mut self) {
+        self.xm1 = 0.0;
+        self.xm2 = 0.0;
+        self.ym1 = 0.0;
+        self.ym2 = 0.0;
+        self.em1 = 0.0;
+        self.em2 = 0.0;
+    }
+
+    pub fn process(&mut self, input: f32x4) -> f32x4 {
+        let x = input.to_array();
+
+        let mut y = f64x4::ZERO;
+        for (c, x) in self.c_x.iter().zip(x.iter()) {
+            y = c.mul_add(f64x4::splat(*x as f64), y);
+        }
+        y = self.c_xm1.mul_add(f64x4::splat(self.xm1 as f64), y);
+        y = self.c_xm2.mul_add(f64x4::splat(self.xm2 as f64), y);
+        y = self
+            .c_ym1
+            .mul_add(f64x4::splat(self.ym1 as f64 + self.em1 as f64), y);
+        y = self
+            .c_ym2
+            .mul_add(f64x4::splat(self.ym2 as f64 + self.em2 as f64), y);
+
+        let acc = y.to_array();
+        let out = [acc[0] as f32, acc[1] as f32, acc[2] as f32, acc[3] as f32];
+
+        self.xm2 = x[2];
+        self.xm1 = x[3];
+        self.ym2 = out[2];
+        self.ym1 = out[3];
+        self.em2 = (acc[2] - out[2] as f64) as f32;
+        self.em1 = (acc[3] - out[3] as f64) as f32;
+
+        f32x4::from(out)
+    }
+}
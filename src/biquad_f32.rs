@@ -1,3 +1,5 @@
+use crate::{coeffs::BiQuadCoeffs, denormals::DENORMAL_FLUSH_THRESHOLD};
+
 #[derive(Copy, Clone)]
 pub struct BiQuadF32 {
     pub a0: f32,
@@ -10,6 +12,14 @@ pub struct BiQuadF32 {
     z_a2: f32,
     z_b1: f32,
     z_b2: f32,
+
+    flush_denormals: bool,
+}
+
+impl Default for BiQuadF32 {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BiQuadF32 {
@@ -24,29 +34,53 @@ impl BiQuadF32 {
             z_a2: 0.0,
             z_b1: 0.0,
             z_b2: 0.0,
+            flush_denormals: false,
         };
         b.update(44100.0, 1200.0);
         b
     }
 
     pub fn update(&mut self, sample_rate: f32, cutoff: f32) {
-        let t = (std::f32::consts::PI * cutoff / sample_rate).tan();
-        let alpha = (t - 1.0) / (t + 1.0);
+        self.set_coeffs(&BiQuadCoeffs::allpass(sample_rate, cutoff));
+    }
 
-        self.a0 = alpha;
-        self.a1 = 1.0;
-        self.a2 = 0.0;
-        self.b1 = alpha;
-        self.b2 = 0.0;
+    pub fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs) {
+        self.a0 = coeffs.a0;
+        self.a1 = coeffs.a1;
+        self.a2 = coeffs.a2;
+        self.b1 = coeffs.b1;
+        self.b2 = coeffs.b2;
+    }
+
+    pub fn coeffs(&self) -> BiQuadCoeffs {
+        BiQuadCoeffs::new(self.a0, self.a1, self.a2, self.b1, self.b2)
+    }
+
+    // Keeps the state free of denormals without touching the floating point
+    // control register, for hosts that don't allow changing it: outputs below
+    // `DENORMAL_FLUSH_THRESHOLD` are flushed to zero before they're fed back.
+    pub fn set_flush_denormals(&mut self, enabled: bool) {
+        self.flush_denormals = enabled;
+    }
+
+    pub fn reset(&mut self) {
+        self.z_a1 = 0.0;
+        self.z_a2 = 0.0;
+        self.z_b1 = 0.0;
+        self.z_b2 = 0.0;
     }
 
     pub fn process(&mut self, input: f32) -> f32 {
         let xn = input;
 
-        let yn = self.a0 * xn + self.a1 * self.z_a1 + self.a2 * self.z_a2
+        let mut yn = self.a0 * xn + self.a1 * self.z_a1 + self.a2 * self.z_a2
             - self.b1 * self.z_b1
             - self.b2 * self.z_b2;
 
+        if self.flush_denormals && yn.abs() < DENORMAL_FLUSH_THRESHOLD {
+            yn = 0.0;
+        }
+
         self.z_b2 = self.z_b1;
         self.z_b1 = yn;
 
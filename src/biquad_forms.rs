@@ -0,0 +1,304 @@
+use std::marker::PhantomData;
+
+use wide::f32x4;
+
+use crate::coeffs::BiQuadCoeffs;
+
+// A biquad realization structure. All of them compute the same transfer
+// function, but differ in what they keep as state and therefore in how
+// rounding errors and coefficient changes propagate. Structures that don't
+// work on the direct form coefficients derive their own `Coeffs` from them.
+pub trait Realization {
+    type Coeffs: Copy;
+    type State: Copy + Default;
+
+    fn coeffs(coeffs: &BiQuadCoeffs) -> Self::Coeffs;
+
+    // From a transfer function designed in double precision, for structures
+    // whose own coefficients are better computed before anything is rounded
+    fn coeffs_f64(num: [f64; 3], den: [f64; 3]) -> Self::Coeffs {
+        Self::coeffs(&BiQuadCoeffs::normalized_f64(num, den))
+    }
+
+    fn process(coeffs: &Self::Coeffs, state: &mut Self::State, input: f32) -> f32;
+}
+
+// Direct Form I, the structure used by `BiQuadF32`: two past inputs and two
+// past outputs.
+pub struct DirectForm1;
+
+// Direct Form II: poles first, then zeros, sharing a single two-element
+// delay line.
+pub struct DirectForm2;
+
+// Transposed Direct Form I: the all-pole section followed by the all-zero
+// section, each in transposed form.
+pub struct TransposedDirectForm1;
+
+// Transposed Direct Form II: two accumulating state variables.
+pub struct TransposedDirectForm2;
+
+impl Realization for DirectForm1 {
+    type Coeffs = BiQuadCoeffs;
+    // x[n-1], x[n-2], y[n-1], y[n-2]
+    type State = [f32; 4];
+
+    fn coeffs(coeffs: &BiQuadCoeffs) -> BiQuadCoeffs {
+        *coeffs
+    }
+
+    fn process(c: &BiQuadCoeffs, s: &mut [f32; 4], input: f32) -> f32 {
+        let y = c.a0 * input + c.a1 * s[0] + c.a2 * s[1] - c.b1 * s[2] - c.b2 * s[3];
+
+        s[1] = s[0];
+        s[0] = input;
+        s[3] = s[2];
+        s[2] = y;
+
+        y
+    }
+}
+
+impl Realization for DirectForm2 {
+    type Coeffs = BiQuadCoeffs;
+    // w[n-1], w[n-2]
+    type State = [f32; 2];
+
+    fn coeffs(coeffs: &BiQuadCoeffs) -> BiQuadCoeffs {
+        *coeffs
+    }
+
+    fn process(c: &BiQuadCoeffs, s: &mut [f32; 2], input: f32) -> f32 {
+        let w = input - c.b1 * s[0] - c.b2 * s[1];
+        let y = c.a0 * w + c.a1 * s[0] + c.a2 * s[1];
+
+        s[1] = s[0];
+        s[0] = w;
+
+        y
+    }
+}
+
+impl Realization for TransposedDirectForm1 {
+    type Coeffs = BiQuadCoeffs;
+    // Pole section s1, s2 followed by zero section s1, s2
+    type State = [f32; 4];
+
+    fn coeffs(coeffs: &BiQuadCoeffs) -> BiQuadCoeffs {
+        *coeffs
+    }
+
+    fn process(c: &BiQuadCoeffs, s: &mut [f32; 4], input: f32) -> f32 {
+        let v = input + s[0];
+        s[0] = s[1] - c.b1 * v;
+        s[1] = -c.b2 * v;
+
+        let y = c.a0 * v + s[2];
+        s[2] = s[3] + c.a1 * v;
+        s[3] = c.a2 * v;
+
+        y
+    }
+}
+
+impl Realization for TransposedDirectForm2 {
+    type Coeffs = BiQuadCoeffs;
+    // s1, s2
+    type State = [f32; 2];
+
+    fn coeffs(coeffs: &BiQuadCoeffs) -> BiQuadCoeffs {
+        *coeffs
+    }
+
+    fn process(c: &BiQuadCoeffs, s: &mut [f32; 2], input: f32) -> f32 {
+        let y = c.a0 * input + s[0];
+        s[0] = c.a1 * input - c.b1 * y + s[1];
+        s[1] = c.a2 * input - c.b2 * y;
+
+        y
+    }
+}
+
+pub struct BiQuad<R: Realization> {
+    pub coeffs: R::Coeffs,
+    state: R::State,
+    _realization: PhantomData<R>,
+}
+
+pub type BiQuadDf1 = BiQuad<DirectForm1>;
+pub type BiQuadDf2 = BiQuad<DirectForm2>;
+pub type BiQuadTdf1 = BiQuad<TransposedDirectForm1>;
+pub type BiQuadTdf2 = BiQuad<TransposedDirectForm2>;
+
+impl<R: Realization> Default for BiQuad<R> {
+    fn default() -> Self {
+        Self::new(BiQuadCoeffs::identity())
+    }
+}
+
+impl<R: Realization> Clone for BiQuad<R> {
+    fn clone(&self) -> Self {
+        BiQuad {
+            coeffs: self.coeffs,
+            state: self.state,
+            _realization: PhantomData,
+        }
+    }
+}
+
+impl<R: Realization> BiQuad<R> {
+    pub fn new(coeffs: BiQuadCoeffs) -> Self {
+        BiQuad {
+            coeffs: R::coeffs(&coeffs),
+            state: R::State::default(),
+            _realization: PhantomData,
+        }
+    }
+
+    pub fn from_f64(num: [f64; 3], den: [f64; 3]) -> Self {
+        BiQuad {
+            coeffs: R::coeffs_f64(num, den),
+            state: R::State::default(),
+            _realization: PhantomData,
+        }
+    }
+
+    pub fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs) {
+        self.coeffs = R::coeffs(coeffs);
+    }
+
+    pub fn reset(&mut self) {
+        self.state = R::State::default();
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        R::process(&self.coeffs, &mut self.state, input)
+    }
+}
+
+// Four samples per step in Transposed Direct Form II. Since the state is only
+// two variables, the block matrix has six columns (x[n]..x[n+3], s1, s2) and
+// a second set of rows producing the state after the block, instead of the
+// eight columns the Direct Form I kernels in `biquad_sse2` need.
+pub struct BiQuadTdf2Portable4 {
+    c_x: [f32x4; 4],
+    c_s1: f32x4,
+    c_s2: f32x4,
+
+    // Same columns, but rows are the new s1, s2 in lanes 0 and 1
+    c_state_x: [f32x4; 4],
+    c_state_s1: f32x4,
+    c_state_s2: f32x4,
+
+    s1: f32,
+    s2: f32,
+}
+
+impl Default for BiQuadTdf2Portable4 {
+    fn default() -> Self {
+        Self::new(BiQuadCoeffs::identity())
+    }
+}
+
+impl BiQuadTdf2Portable4 {
+    pub fn new(coeffs: BiQuadCoeffs) -> Self {
+        let mut b = BiQuadTdf2Portable4 {
+            c_x: [f32x4::ZERO; 4],
+            c_s1: f32x4::ZERO,
+            c_s2: f32x4::ZERO,
+            c_state_x: [f32x4::ZERO; 4],
+            c_state_s1: f32x4::ZERO,
+            c_state_s2: f32x4::ZERO,
+            s1: 0.0,
+            s2: 0.0,
+        };
+        b.set_coeffs(&coeffs);
+        b
+    }
+
+    pub fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs) {
+        const COLUMNS: usize = 6;
+        const S1: usize = 4;
+        const S2: usize = 5;
+
+        let a0 = coeffs.a0 as f64;
+        let a1 = coeffs.a1 as f64;
+        let a2 = coeffs.a2 as f64;
+        let b1 = coeffs.b1 as f64;
+        let b2 = coeffs.b2 as f64;
+
+        // Run the scalar recurrence symbolically: every quantity is a linear
+        // combination of x[n]..x[n+3] and the initial s1, s2.
+        let mut s1 = [0.0f64; COLUMNS];
+        let mut s2 = [0.0f64; COLUMNS];
+        s1[S1] = 1.0;
+        s2[S2] = 1.0;
+
+        let mut rows = [[0.0f64; COLUMNS]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            let mut x = [0.0f64; COLUMNS];
+            x[i] = 1.0;
+
+            let mut y = [0.0f64; COLUMNS];
+            let mut next_s1 = [0.0f64; COLUMNS];
+            let mut next_s2 = [0.0f64; COLUMNS];
+            for col in 0..COLUMNS {
+                y[col] = a0 * x[col] + s1[col];
+                next_s1[col] = a1 * x[col] - b1 * y[col] + s2[col];
+                next_s2[col] = a2 * x[col] - b2 * y[col];
+            }
+
+            *row = y;
+            s1 = next_s1;
+            s2 = next_s2;
+        }
+
+        let column = |col: usize| {
+            f32x4::from([
+                rows[0][col] as f32,
+                rows[1][col] as f32,
+                rows[2][col] as f32,
+                rows[3][col] as f32,
+            ])
+        };
+        let state_column = |col: usize| f32x4::from([s1[col] as f32, s2[col] as f32, 0.0, 0.0]);
+
+        for k in 0..4 {
+            self.c_x[k] = column(k);
+            self.c_state_x[k] = state_column(k);
+        }
+        self.c_s1 = column(S1);
+        self.c_s2 = column(S2);
+        self.c_state_s1 = state_column(S1);
+        self.c_state_s2 = state_column(S2);
+    }
+
+    pub fn reset(&mut self) {
+        self.s1 = 0.0;
+        self.s2 = 0.0;
+    }
+
+    pub fn process(&mut self, input: f32x4) -> f32x4 {
+        let x = input.to_array();
+        let v_s1 = f32x4::splat(self.s1);
+        let v_s2 = f32x4::splat(self.s2);
+
+        let mut y = f32x4::ZERO;
+        let mut s = f32x4::ZERO;
+        for ((c, c_state), x) in self.c_x.iter().zip(self.c_state_x.iter()).zip(x.iter()) {
+            let v_x = f32x4::splat(*x);
+            y = c.mul_add(v_x, y);
+            s = c_state.mul_add(v_x, s);
+        }
+        y = self.c_s1.mul_add(v_s1, y);
+        y = self.c_s2.mul_add(v_s2, y);
+        s = self.c_state_s1.mul_add(v_s1, s);
+        s = self.c_state_s2.mul_add(v_s2, s);
+
+        let s = s.to_array();
+        self.s1 = s[0];
+        self.s2 = s[1];
+
+        y
+    }
+}
@@ -0,0 +1,92 @@
+use wide::f32x8;
+
+use crate::coeffs::BiQuadCoeffs;
+
+// Eight independent biquads side by side, one per lane, each taking one
+// sample per step. Where the block kernels speed up one filter over time,
+// this runs several filters on the same sample at once: the bands of a
+// filter bank, or the channels of a multichannel signal.
+pub struct BiQuadLanes8 {
+    a0: f32x8,
+    a1: f32x8,
+    a2: f32x8,
+    b1: f32x8,
+    b2: f32x8,
+
+    x1: f32x8,
+    x2: f32x8,
+    y1: f32x8,
+    y2: f32x8,
+}
+
+impl Default for BiQuadLanes8 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BiQuadLanes8 {
+    pub const LANES: usize = 8;
+
+    // Every lane passes its input through unchanged
+    pub fn new() -> Self {
+        BiQuadLanes8 {
+            a0: f32x8::ONE,
+            a1: f32x8::ZERO,
+            a2: f32x8::ZERO,
+            b1: f32x8::ZERO,
+            b2: f32x8::ZERO,
+            x1: f32x8::ZERO,
+            x2: f32x8::ZERO,
+            y1: f32x8::ZERO,
+            y2: f32x8::ZERO,
+        }
+    }
+
+    pub fn set_coeffs(&mut self, lane: usize, coeffs: &BiQuadCoeffs) {
+        fn set(v: &mut f32x8, lane: usize, value: f32) {
+            let mut lanes = v.to_array();
+            lanes[lane] = value;
+            *v = f32x8::from(lanes);
+        }
+
+        set(&mut self.a0, lane, coeffs.a0);
+        set(&mut self.a1, lane, coeffs.a1);
+        set(&mut self.a2, lane, coeffs.a2);
+        set(&mut self.b1, lane, coeffs.b1);
+        set(&mut self.b2, lane, coeffs.b2);
+    }
+
+    pub fn coeffs(&self, lane: usize) -> BiQuadCoeffs {
+        BiQuadCoeffs::new(
+            self.a0.to_array()[lane],
+            self.a1.to_array()[lane],
+            self.a2.to_array()[lane],
+            self.b1.to_array()[lane],
+            self.b2.to_array()[lane],
+        )
+    }
+
+    pub fn reset(&mut self) {
+        self.x1 = f32x8::ZERO;
+        self.x2 = f32x8::ZERO;
+        self.y1 = f32x8::ZERO;
+        self.y2 = f32x8::ZERO;
+    }
+
+    // Direct Form I on every lane
+    pub fn process(&mut self, input: f32x8) -> f32x8 {
+        let mut y = self.a0 * input;
+        y = self.a1.mul_add(self.x1, y);
+        y = self.a2.mul_add(self.x2, y);
+        y = (-self.b1).mul_add(self.y1, y);
+        y = (-self.b2).mul_add(self.y2, y);
+
+        self.x2 = self.x1;
+        self.x1 = input;
+        self.y2 = self.y1;
+        self.y1 = y;
+
+        y
+    }
+}
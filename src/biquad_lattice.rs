@@ -0,0 +1,156 @@
+use crate::{biquad_forms::Realization, coeffs::BiQuadCoeffs};
+
+// Gray-Markel lattice-ladder: the poles are realized by a two stage lattice
+// with reflection coefficients k1, k2, the zeros by a ladder of taps v0..v2
+// summing the backward outputs of each stage.
+pub struct Lattice;
+
+// Lattice-ladder where every stage is a plane rotation by sqrt(1 - k^2), so
+// the state has the same energy as the input. This keeps the internal signal
+// levels bounded and well scaled even when the poles are very close to z = 1.
+pub struct NormalizedLattice;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LatticeCoeffs {
+    pub k1: f32,
+    pub k2: f32,
+    pub v0: f32,
+    pub v1: f32,
+    pub v2: f32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NormalizedLatticeCoeffs {
+    pub k1: f32,
+    pub c1: f32,
+    pub k2: f32,
+    pub c2: f32,
+    pub v0: f32,
+    pub v1: f32,
+    pub v2: f32,
+}
+
+impl LatticeCoeffs {
+    // Only as exact as the f32 direct form coefficients. Close to DC, where
+    // k1 and k2 sit right next to -1 and 1, rounding b1 and b2 first moves
+    // the poles noticeably, so prefer `from_f64` with the design itself.
+    pub fn from_biquad(c: &BiQuadCoeffs) -> Self {
+        Self::from_f64(
+            [c.a0 as f64, c.a1 as f64, c.a2 as f64],
+            [1.0, c.b1 as f64, c.b2 as f64],
+        )
+    }
+
+    pub fn from_f64(num: [f64; 3], den: [f64; 3]) -> Self {
+        let [k1, k2, v0, v1, v2] = lattice(num, den);
+        LatticeCoeffs {
+            k1: k1 as f32,
+            k2: k2 as f32,
+            v0: v0 as f32,
+            v1: v1 as f32,
+            v2: v2 as f32,
+        }
+    }
+}
+
+impl NormalizedLatticeCoeffs {
+    pub fn from_biquad(c: &BiQuadCoeffs) -> Self {
+        Self::from_f64(
+            [c.a0 as f64, c.a1 as f64, c.a2 as f64],
+            [1.0, c.b1 as f64, c.b2 as f64],
+        )
+    }
+
+    // Same reflection coefficients as `LatticeCoeffs`. Stage m's backward
+    // signal is scaled by the product of the rotation cosines above it, so the
+    // ladder taps are divided by the same amount. Only defined for stable
+    // filters, where |k1|, |k2| < 1.
+    pub fn from_f64(num: [f64; 3], den: [f64; 3]) -> Self {
+        let [k1, k2, v0, v1, v2] = lattice(num, den);
+        let c1 = (1.0 - k1 * k1).sqrt();
+        let c2 = (1.0 - k2 * k2).sqrt();
+
+        NormalizedLatticeCoeffs {
+            k1: k1 as f32,
+            c1: c1 as f32,
+            k2: k2 as f32,
+            c2: c2 as f32,
+            v0: (v0 / (c1 * c2)) as f32,
+            v1: (v1 / c2) as f32,
+            v2: v2 as f32,
+        }
+    }
+}
+
+// k1, k2, v0, v1, v2 of num / den. Step-down recursion from the denominator
+// 1 + b1 z^-1 + b2 z^-2, then matching the numerator against the reversed
+// stage polynomials B0 = 1, B1 = k1 + z^-1 and B2 = b2 + b1 z^-1 + z^-2.
+fn lattice(num: [f64; 3], den: [f64; 3]) -> [f64; 5] {
+    let [a0, a1, a2] = num.map(|a| a / den[0]);
+    let (b1, b2) = (den[1] / den[0], den[2] / den[0]);
+
+    let k2 = b2;
+    let k1 = b1 / (1.0 + b2);
+
+    let v2 = a2;
+    let v1 = a1 - v2 * b1;
+    let v0 = a0 - v1 * k1 - v2 * b2;
+
+    [k1, k2, v0, v1, v2]
+}
+
+impl Realization for Lattice {
+    type Coeffs = LatticeCoeffs;
+    // g0[n-1], g1[n-1]
+    type State = [f32; 2];
+
+    fn coeffs(coeffs: &BiQuadCoeffs) -> LatticeCoeffs {
+        LatticeCoeffs::from_biquad(coeffs)
+    }
+
+    fn coeffs_f64(num: [f64; 3], den: [f64; 3]) -> LatticeCoeffs {
+        LatticeCoeffs::from_f64(num, den)
+    }
+
+    fn process(c: &LatticeCoeffs, s: &mut [f32; 2], input: f32) -> f32 {
+        let f1 = input - c.k2 * s[1];
+        let f0 = f1 - c.k1 * s[0];
+
+        let g2 = c.k2 * f1 + s[1];
+        let g1 = c.k1 * f0 + s[0];
+        let g0 = f0;
+
+        s[0] = g0;
+        s[1] = g1;
+
+        c.v0 * g0 + c.v1 * g1 + c.v2 * g2
+    }
+}
+
+impl Realization for NormalizedLattice {
+    type Coeffs = NormalizedLatticeCoeffs;
+    // g0[n-1], g1[n-1]
+    type State = [f32; 2];
+
+    fn coeffs(coeffs: &BiQuadCoeffs) -> NormalizedLatticeCoeffs {
+        NormalizedLatticeCoeffs::from_biquad(coeffs)
+    }
+
+    fn coeffs_f64(num: [f64; 3], den: [f64; 3]) -> NormalizedLatticeCoeffs {
+        NormalizedLatticeCoeffs::from_f64(num, den)
+    }
+
+    fn process(c: &NormalizedLatticeCoeffs, s: &mut [f32; 2], input: f32) -> f32 {
+        let f1 = c.c2 * input - c.k2 * s[1];
+        let g2 = c.k2 * input + c.c2 * s[1];
+
+        let f0 = c.c1 * f1 - c.k1 * s[0];
+        let g1 = c.k1 * f1 + c.c1 * s[0];
+        let g0 = f0;
+
+        s[0] = g0;
+        s[1] = g1;
+
+        c.v0 * g0 + c.v1 * g1 + c.v2 * g2
+    }
+}
@@ -0,0 +1,269 @@
+use wide::{f32x4, f32x8};
+
+use crate::coeffs::BiQuadCoeffs;
+
+pub struct BiQuadPortable4 {
+    c_x: [f32x4; 4],
+    c_xm1: f32x4,
+    c_xm2: f32x4,
+    c_ym1: f32x4,
+    c_ym2: f32x4,
+
+    xm1: f32x4,
+    xm2: f32x4,
+    ym1: f32x4,
+    ym2: f32x4,
+
+    coeffs: BiQuadCoeffs,
+}
+
+impl Default for BiQuadPortable4 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BiQuadPortable4 {
+    pub fn new() -> Self {
+        let mut b = BiQuadPortable4 {
+            c_x: [f32x4::ZERO; 4],
+            c_xm1: f32x4::ZERO,
+            c_xm2: f32x4::ZERO,
+            c_ym1: f32x4::ZERO,
+            c_ym2: f32x4::ZERO,
+            xm1: f32x4::ZERO,
+            xm2: f32x4::ZERO,
+            ym1: f32x4::ZERO,
+            ym2: f32x4::ZERO,
+            coeffs: BiQuadCoeffs::identity(),
+        };
+        b.update(44100.0, 1200.0);
+        b
+    }
+
+    pub fn update(&mut self, sample_rate: f32, cutoff: f32) {
+        self.set_coeffs(&BiQuadCoeffs::allpass(sample_rate, cutoff));
+    }
+
+    pub fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs) {
+        self.coeffs = *coeffs;
+        let BiQuadCoeffs { a0, a1, a2, b1, b2 } = *coeffs;
+        let coeffs = block_coeffs::<4>(a0, a1, a2, b1, b2);
+
+        for (c, col) in self.c_x.iter_mut().zip(coeffs.x.iter()) {
+            *c = f32x4::from(*col);
+        }
+        self.c_xm1 = f32x4::from(coeffs.xm1);
+        self.c_xm2 = f32x4::from(coeffs.xm2);
+        self.c_ym1 = f32x4::from(coeffs.ym1);
+        self.c_ym2 = f32x4::from(coeffs.ym2);
+    }
+
+    pub fn process(&mut self, input: f32x4) -> f32x4 {
+        let x = input.to_array();
+
+        let mut y = f32x4::ZERO;
+        for (c, x) in self.c_x.iter().zip(x.iter()) {
+            y = c.mul_add(f32x4::splat(*x), y);
+        }
+        y = self.c_xm1.mul_add(self.xm1, y);
+        y = self.c_xm2.mul_add(self.xm2, y);
+        y = self.c_ym1.mul_add(self.ym1, y);
+        y = self.c_ym2.mul_add(self.ym2, y);
+
+        let out = y.to_array();
+        self.xm2 = f32x4::splat(x[2]);
+        self.xm1 = f32x4::splat(x[3]);
+        self.ym2 = f32x4::splat(out[2]);
+        self.ym1 = f32x4::splat(out[3]);
+
+        y
+    }
+
+    // One sample through the same recursion, for the samples at the end of a
+    // buffer that don't fill a vector. Leaves the state as `process` would.
+    pub fn process_sample(&mut self, input: f32) -> f32 {
+        let BiQuadCoeffs { a0, a1, a2, b1, b2 } = self.coeffs;
+        let (x1, x2) = (self.xm1.to_array()[0], self.xm2.to_array()[0]);
+        let (y1, y2) = (self.ym1.to_array()[0], self.ym2.to_array()[0]);
+        let y = a0 * input + a1 * x1 + a2 * x2 - b1 * y1 - b2 * y2;
+
+        self.xm2 = self.xm1;
+        self.xm1 = f32x4::splat(input);
+        self.ym2 = self.ym1;
+        self.ym1 = f32x4::splat(y);
+
+        y
+    }
+}
+
+pub struct BiQuadPortable8 {
+    c_x: [f32x8; 8],
+    c_xm1: f32x8,
+    c_xm2: f32x8,
+    c_ym1: f32x8,
+    c_ym2: f32x8,
+
+    xm1: f32x8,
+    xm2: f32x8,
+    ym1: f32x8,
+    ym2: f32x8,
+
+    coeffs: BiQuadCoeffs,
+}
+
+impl Default for BiQuadPortable8 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BiQuadPortable8 {
+    pub fn new() -> Self {
+        let mut b = BiQuadPortable8 {
+            c_x: [f32x8::ZERO; 8],
+            c_xm1: f32x8::ZERO,
+            c_xm2: f32x8::ZERO,
+            c_ym1: f32x8::ZERO,
+            c_ym2: f32x8::ZERO,
+            xm1: f32x8::ZERO,
+            xm2: f32x8::ZERO,
+            ym1: f32x8::ZERO,
+            ym2: f32x8::ZERO,
+            coeffs: BiQuadCoeffs::identity(),
+        };
+        b.update(44100.0, 1200.0);
+        b
+    }
+
+    pub fn update(&mut self, sample_rate: f32, cutoff: f32) {
+        self.set_coeffs(&BiQuadCoeffs::allpass(sample_rate, cutoff));
+    }
+
+    pub fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs) {
+        self.coeffs = *coeffs;
+        let BiQuadCoeffs { a0, a1, a2, b1, b2 } = *coeffs;
+        let coeffs = block_coeffs::<8>(a0, a1, a2, b1, b2);
+
+        for (c, col) in self.c_x.iter_mut().zip(coeffs.x.iter()) {
+            *c = f32x8::from(*col);
+        }
+        self.c_xm1 = f32x8::from(coeffs.xm1);
+        self.c_xm2 = f32x8::from(coeffs.xm2);
+        self.c_ym1 = f32x8::from(coeffs.ym1);
+        self.c_ym2 = f32x8::from(coeffs.ym2);
+    }
+
+    pub fn process(&mut self, input: f32x8) -> f32x8 {
+        let x = input.to_array();
+
+        // Two accumulators to shorten the dependency chain, same as the AVX kernel
+        let mut y1 = f32x8::ZERO;
+        let mut y2 = f32x8::ZERO;
+        for (i, (c, x)) in self.c_x.iter().zip(x.iter()).enumerate() {
+            if i % 2 == 0 {
+                y1 = c.mul_add(f32x8::splat(*x), y1);
+            } else {
+                y2 = c.mul_add(f32x8::splat(*x), y2);
+            }
+        }
+        y1 = self.c_xm1.mul_add(self.xm1, y1);
+        y2 = self.c_xm2.mul_add(self.xm2, y2);
+        y1 = self.c_ym1.mul_add(self.ym1, y1);
+        y2 = self.c_ym2.mul_add(self.ym2, y2);
+
+        let y = y1 + y2;
+
+        let out = y.to_array();
+        self.xm2 = f32x8::splat(x[6]);
+        self.xm1 = f32x8::splat(x[7]);
+        self.ym2 = f32x8::splat(out[6]);
+        self.ym1 = f32x8::splat(out[7]);
+
+        y
+    }
+
+    // One sample through the same recursion, for the samples at the end of a
+    // buffer that don't fill a vector. Leaves the state as `process` would.
+    pub fn process_sample(&mut self, input: f32) -> f32 {
+        let BiQuadCoeffs { a0, a1, a2, b1, b2 } = self.coeffs;
+        let (x1, x2) = (self.xm1.to_array()[0], self.xm2.to_array()[0]);
+        let (y1, y2) = (self.ym1.to_array()[0], self.ym2.to_array()[0]);
+        let y = a0 * input + a1 * x1 + a2 * x2 - b1 * y1 - b2 * y2;
+
+        self.xm2 = self.xm1;
+        self.xm1 = f32x8::splat(input);
+        self.ym2 = self.ym1;
+        self.ym1 = f32x8::splat(y);
+
+        y
+    }
+}
+
+// Block coefficient matrix in column form: `x[k]` holds the coefficients
+// applied to x[n+k] for each of the output rows y[n]..y[n+ROWS-1].
+struct BlockCoeffs<const ROWS: usize> {
+    x: [[f32; ROWS]; ROWS],
+    xm1: [f32; ROWS],
+    xm2: [f32; ROWS],
+    ym1: [f32; ROWS],
+    ym2: [f32; ROWS],
+}
+
+fn block_coeffs<const ROWS: usize>(
+    a0: f32,
+    a1: f32,
+    a2: f32,
+    b1: f32,
+    b2: f32,
+) -> BlockCoeffs<ROWS> {
+    let mut c = BlockCoeffs {
+        x: [[0.0; ROWS]; ROWS],
+        xm1: [0.0; ROWS],
+        xm2: [0.0; ROWS],
+        ym1: [0.0; ROWS],
+        ym2: [0.0; ROWS],
+    };
+
+    // Direct form terms, same layout as the matrix in `biquad_sse2`
+    for row in 0..ROWS {
+        c.x[row][row] = a0;
+        if row >= 1 {
+            c.x[row - 1][row] = a1;
+        } else {
+            c.xm1[row] = a1;
+        }
+        if row >= 2 {
+            c.x[row - 2][row] = a2;
+        } else if row == 1 {
+            c.xm1[row] = a2;
+        } else {
+            c.xm2[row] = a2;
+        }
+    }
+    c.ym1[0] = -b1;
+    c.ym2[0] = -b2;
+    if ROWS > 1 {
+        c.ym1[1] = -b2;
+    }
+
+    // Substitute the previously computed rows for y[n+row-1] and y[n+row-2]
+    for row in 1..ROWS {
+        let fold = |col: &mut [f32; ROWS]| {
+            let mut v = col[row] - b1 * col[row - 1];
+            if row >= 2 {
+                v -= b2 * col[row - 2];
+            }
+            col[row] = v;
+        };
+        for col in c.x.iter_mut() {
+            fold(col);
+        }
+        fold(&mut c.xm1);
+        fold(&mut c.xm2);
+        fold(&mut c.ym1);
+        fold(&mut c.ym2);
+    }
+
+    c
+}
@@ -1,5 +1,7 @@
 use std::arch::x86_64::*;
 
+use crate::{coeffs::BiQuadCoeffs, denormals::DENORMAL_FLUSH_THRESHOLD};
+
 pub struct BiQuadSSE2 {
     c_xp3: __m128,
     c_xp2: __m128,
@@ -14,6 +16,15 @@ pub struct BiQuadSSE2 {
     xm2: __m128,
     ym1: __m128,
     ym2: __m128,
+
+    flush_denormals: bool,
+    coeffs: BiQuadCoeffs,
+}
+
+impl Default for BiQuadSSE2 {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BiQuadSSE2 {
@@ -32,6 +43,8 @@ impl BiQuadSSE2 {
                 xm2: _mm_setzero_ps(),
                 ym1: _mm_setzero_ps(),
                 ym2: _mm_setzero_ps(),
+                flush_denormals: false,
+                coeffs: BiQuadCoeffs::identity(),
             };
             b.update(44100.0, 1200.0);
             b
@@ -39,15 +52,13 @@ impl BiQuadSSE2 {
     }
 
     pub fn update(&mut self, sample_rate: f32, cutoff: f32) {
-        unsafe {
-            let t = (std::f32::consts::PI * cutoff / sample_rate).tan();
-            let alpha = (t - 1.0) / (t + 1.0);
+        self.set_coeffs(&BiQuadCoeffs::allpass(sample_rate, cutoff));
+    }
 
-            let a0 = alpha;
-            let a1 = 1.0;
-            let a2 = 0.0;
-            let b1 = alpha;
-            let b2 = 0.0;
+    pub fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs) {
+        unsafe {
+            self.coeffs = *coeffs;
+            let BiQuadCoeffs { a0, a1, a2, b1, b2 } = *coeffs;
 
             const COLUMNS: usize = 8;
             const ROWS: usize = 4;
@@ -61,6 +72,7 @@ impl BiQuadSSE2 {
                 /* y[n+3] = */ [     a0,    a1,    a2,   0.0,   0.0,   0.0,       0.0,   0.0], // - b1 * y[n-2] - b2 * y[n-1]
             ];
 
+            #[allow(clippy::needless_range_loop)]
             for col in 0..COLUMNS {
                 // Add -b1 * y[n] to y[n+1] to y[n+1]
                 coeffs[1][col] += -b1 * coeffs[0][col];
@@ -83,6 +95,12 @@ impl BiQuadSSE2 {
         }
     }
 
+    // Same as `BiQuadF32::set_flush_denormals`, applied to all lanes before
+    // the last two outputs are fed back
+    pub fn set_flush_denormals(&mut self, enabled: bool) {
+        self.flush_denormals = enabled;
+    }
+
     pub fn process(&mut self, input: __m128) -> __m128 {
         unsafe {
             let v_x0 = _mm_shuffle_ps(input, input, 0b00_00_00_00);
@@ -100,6 +118,12 @@ impl BiQuadSSE2 {
             y = _mm_fmadd_ps(self.c_ym1, self.ym1, y);
             y = _mm_fmadd_ps(self.c_ym2, self.ym2, y);
 
+            if self.flush_denormals {
+                let magnitude = _mm_andnot_ps(_mm_set1_ps(-0.0), y);
+                let keep = _mm_cmpge_ps(magnitude, _mm_set1_ps(DENORMAL_FLUSH_THRESHOLD));
+                y = _mm_and_ps(y, keep);
+            }
+
             self.xm2 = v_xp2;
             self.xm1 = v_xp3;
             self.ym2 = _mm_shuffle_ps(y, y, 0b10_10_10_10);
@@ -108,4 +132,25 @@ impl BiQuadSSE2 {
             y
         }
     }
+
+    // One sample through the same recursion, for the samples at the end of a
+    // buffer that don't fill a vector. Leaves the state as `process` would.
+    pub fn process_sample(&mut self, input: f32) -> f32 {
+        unsafe {
+            let BiQuadCoeffs { a0, a1, a2, b1, b2 } = self.coeffs;
+            let (x1, x2) = (_mm_cvtss_f32(self.xm1), _mm_cvtss_f32(self.xm2));
+            let (y1, y2) = (_mm_cvtss_f32(self.ym1), _mm_cvtss_f32(self.ym2));
+            let mut y = a0 * input + a1 * x1 + a2 * x2 - b1 * y1 - b2 * y2;
+            if self.flush_denormals && y.abs() < DENORMAL_FLUSH_THRESHOLD {
+                y = 0.0;
+            }
+
+            self.xm2 = self.xm1;
+            self.xm1 = _mm_set1_ps(input);
+            self.ym2 = self.ym1;
+            self.ym1 = _mm_set1_ps(y);
+
+            y
+        }
+    }
 }
@@ -0,0 +1,600 @@
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use wide::{f32x4, f32x8};
+
+#[cfg(target_arch = "x86_64")]
+use crate::{biquad_avx::BiQuadAVX, biquad_sse2::BiQuadSSE2};
+use crate::{
+    biquad_coupled::{CoupledFormBiQuad, CoupledFormCoeffs},
+    biquad_error_feedback::BiQuadErrorFeedback,
+    biquad_f32::BiQuadF32,
+    biquad_forms::{
+        BiQuad, DirectForm2, Realization, TransposedDirectForm1, TransposedDirectForm2,
+    },
+    biquad_lattice::{Lattice, NormalizedLattice},
+    biquad_portable::{BiQuadPortable4, BiQuadPortable8},
+    coeffs::BiQuadCoeffs,
+    design::{FilterError, RealizationKind},
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Scalar,
+    Portable4,
+    Portable8,
+    #[cfg(target_arch = "x86_64")]
+    Sse2,
+    #[cfg(target_arch = "x86_64")]
+    Avx,
+}
+
+impl Backend {
+    // Every backend compiled in, whether the CPU supports it or not
+    pub const ALL: &'static [Backend] = &[
+        Backend::Scalar,
+        Backend::Portable4,
+        Backend::Portable8,
+        #[cfg(target_arch = "x86_64")]
+        Backend::Sse2,
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx,
+    ];
+
+    // The fastest backend the CPU we're running on supports. Both x86 kernels
+    // are written with FMA instructions, and the AVX one also needs AVX2 for
+    // its cross-lane permutes. Without them the four lane portable kernel
+    // wins: the eight lane one does 50% more work per sample, which only pays
+    // off with native 256-bit vectors.
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("fma") {
+                if is_x86_feature_detected!("avx2") {
+                    return Backend::Avx;
+                }
+                return Backend::Sse2;
+            }
+        }
+
+        Backend::Portable4
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Scalar => "scalar",
+            Backend::Portable4 => "portable4",
+            Backend::Portable8 => "portable8",
+            #[cfg(target_arch = "x86_64")]
+            Backend::Sse2 => "sse2",
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx => "avx",
+        }
+    }
+
+    // Whether the CPU supports the instructions the backend is built from
+    pub fn is_supported(self) -> bool {
+        match self {
+            #[cfg(target_arch = "x86_64")]
+            Backend::Sse2 => is_x86_feature_detected!("fma"),
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx => is_x86_feature_detected!("fma") && is_x86_feature_detected!("avx2"),
+            _ => true,
+        }
+    }
+
+    // Number of samples processed per step
+    pub fn block_size(self) -> usize {
+        match self {
+            Backend::Scalar => 1,
+            Backend::Portable4 => 4,
+            Backend::Portable8 => 8,
+            #[cfg(target_arch = "x86_64")]
+            Backend::Sse2 => 4,
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx => 8,
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Backend {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scalar" => Ok(Backend::Scalar),
+            "portable4" => Ok(Backend::Portable4),
+            "portable8" => Ok(Backend::Portable8),
+            #[cfg(target_arch = "x86_64")]
+            "sse2" => Ok(Backend::Sse2),
+            #[cfg(target_arch = "x86_64")]
+            "avx" => Ok(Backend::Avx),
+            _ => Err(FilterError(format!(
+                "unknown backend '{}' for this target",
+                s
+            ))),
+        }
+    }
+}
+
+// Backend preference that can be written down independently of the machine
+// it will be used on, e.g. in a preset. Backends that aren't available where
+// the preset is loaded fall back to `Backend::detect`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum BackendHint {
+    #[default]
+    Auto,
+    Scalar,
+    Portable4,
+    Portable8,
+    Sse2,
+    Avx,
+}
+
+impl BackendHint {
+    pub fn resolve(self) -> Backend {
+        let backend = match self {
+            BackendHint::Auto => None,
+            BackendHint::Scalar => Some(Backend::Scalar),
+            BackendHint::Portable4 => Some(Backend::Portable4),
+            BackendHint::Portable8 => Some(Backend::Portable8),
+            #[cfg(target_arch = "x86_64")]
+            BackendHint::Sse2 => Some(Backend::Sse2),
+            #[cfg(target_arch = "x86_64")]
+            BackendHint::Avx => Some(Backend::Avx),
+            #[cfg(not(target_arch = "x86_64"))]
+            BackendHint::Sse2 | BackendHint::Avx => None,
+        };
+
+        backend
+            .filter(|b| b.is_supported())
+            .unwrap_or_else(Backend::detect)
+    }
+}
+
+// The block kernels, processing N samples per step
+trait Kernel<const N: usize> {
+    type Vector: Copy;
+
+    fn with_coeffs(coeffs: &BiQuadCoeffs) -> Self;
+    fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs);
+    fn set_flush_denormals(&mut self, enabled: bool);
+    fn load(block: &[f32; N]) -> Self::Vector;
+    fn store(v: Self::Vector, block: &mut [f32; N]);
+    fn step(&mut self, input: Self::Vector) -> Self::Vector;
+    fn step_sample(&mut self, input: f32) -> f32;
+}
+
+impl Kernel<1> for BiQuadF32 {
+    type Vector = f32;
+
+    fn with_coeffs(coeffs: &BiQuadCoeffs) -> Self {
+        let mut b = BiQuadF32::new();
+        b.set_coeffs(coeffs);
+        b
+    }
+
+    fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs) {
+        BiQuadF32::set_coeffs(self, coeffs);
+    }
+
+    fn set_flush_denormals(&mut self, enabled: bool) {
+        BiQuadF32::set_flush_denormals(self, enabled);
+    }
+
+    fn load(block: &[f32; 1]) -> f32 {
+        block[0]
+    }
+
+    fn store(v: f32, block: &mut [f32; 1]) {
+        block[0] = v;
+    }
+
+    fn step(&mut self, input: f32) -> f32 {
+        self.process(input)
+    }
+
+    fn step_sample(&mut self, input: f32) -> f32 {
+        self.process(input)
+    }
+}
+
+impl Kernel<4> for BiQuadPortable4 {
+    type Vector = f32x4;
+
+    fn with_coeffs(coeffs: &BiQuadCoeffs) -> Self {
+        let mut b = BiQuadPortable4::new();
+        b.set_coeffs(coeffs);
+        b
+    }
+
+    fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs) {
+        BiQuadPortable4::set_coeffs(self, coeffs);
+    }
+
+    // The portable kernels don't have kernel level flushing
+    fn set_flush_denormals(&mut self, _enabled: bool) {}
+
+    fn load(block: &[f32; 4]) -> f32x4 {
+        f32x4::from(*block)
+    }
+
+    fn store(v: f32x4, block: &mut [f32; 4]) {
+        *block = v.to_array();
+    }
+
+    fn step(&mut self, input: f32x4) -> f32x4 {
+        self.process(input)
+    }
+
+    fn step_sample(&mut self, input: f32) -> f32 {
+        self.process_sample(input)
+    }
+}
+
+impl Kernel<8> for BiQuadPortable8 {
+    type Vector = f32x8;
+
+    fn with_coeffs(coeffs: &BiQuadCoeffs) -> Self {
+        let mut b = BiQuadPortable8::new();
+        b.set_coeffs(coeffs);
+        b
+    }
+
+    fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs) {
+        BiQuadPortable8::set_coeffs(self, coeffs);
+    }
+
+    fn set_flush_denormals(&mut self, _enabled: bool) {}
+
+    fn load(block: &[f32; 8]) -> f32x8 {
+        f32x8::from(*block)
+    }
+
+    fn store(v: f32x8, block: &mut [f32; 8]) {
+        *block = v.to_array();
+    }
+
+    fn step(&mut self, input: f32x8) -> f32x8 {
+        self.process(input)
+    }
+
+    fn step_sample(&mut self, input: f32) -> f32 {
+        self.process_sample(input)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl Kernel<4> for BiQuadSSE2 {
+    type Vector = std::arch::x86_64::__m128;
+
+    fn with_coeffs(coeffs: &BiQuadCoeffs) -> Self {
+        let mut b = BiQuadSSE2::new();
+        b.set_coeffs(coeffs);
+        b
+    }
+
+    fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs) {
+        BiQuadSSE2::set_coeffs(self, coeffs);
+    }
+
+    fn set_flush_denormals(&mut self, enabled: bool) {
+        BiQuadSSE2::set_flush_denormals(self, enabled);
+    }
+
+    fn load(block: &[f32; 4]) -> Self::Vector {
+        unsafe { std::arch::x86_64::_mm_loadu_ps(block.as_ptr()) }
+    }
+
+    fn store(v: Self::Vector, block: &mut [f32; 4]) {
+        unsafe { std::arch::x86_64::_mm_storeu_ps(block.as_mut_ptr(), v) }
+    }
+
+    fn step(&mut self, input: Self::Vector) -> Self::Vector {
+        self.process(input)
+    }
+
+    fn step_sample(&mut self, input: f32) -> f32 {
+        self.process_sample(input)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl Kernel<8> for BiQuadAVX {
+    type Vector = std::arch::x86_64::__m256;
+
+    fn with_coeffs(coeffs: &BiQuadCoeffs) -> Self {
+        let mut b = BiQuadAVX::new();
+        b.set_coeffs(coeffs);
+        b
+    }
+
+    fn set_coeffs(&mut self, coeffs: &BiQuadCoeffs) {
+        BiQuadAVX::set_coeffs(self, coeffs);
+    }
+
+    fn set_flush_denormals(&mut self, enabled: bool) {
+        BiQuadAVX::set_flush_denormals(self, enabled);
+    }
+
+    fn load(block: &[f32; 8]) -> Self::Vector {
+        unsafe { std::arch::x86_64::_mm256_loadu_ps(block.as_ptr()) }
+    }
+
+    fn store(v: Self::Vector, block: &mut [f32; 8]) {
+        unsafe { std::arch::x86_64::_mm256_storeu_ps(block.as_mut_ptr(), v) }
+    }
+
+    fn step(&mut self, input: Self::Vector) -> Self::Vector {
+        self.process(input)
+    }
+
+    fn step_sample(&mut self, input: f32) -> f32 {
+        self.process_sample(input)
+    }
+}
+
+type ScalarSection = Box<dyn FnMut(f32) -> f32 + Send>;
+
+// A section either runs on the backend's kernel or, for realizations the
+// kernels don't implement, one sample at a time
+enum Section<K> {
+    Kernel(K),
+    Scalar(ScalarSection),
+}
+
+impl<K> Section<K> {
+    fn new<const N: usize>(coeffs: &BiQuadCoeffs, realization: RealizationKind) -> Self
+    where
+        K: Kernel<N>,
+    {
+        fn form<R>(coeffs: &BiQuadCoeffs) -> ScalarSection
+        where
+            R: Realization + Send + 'static,
+            R::Coeffs: Send,
+            R::State: Send,
+        {
+            let mut b = BiQuad::<R>::new(*coeffs);
+            Box::new(move |x| b.process(x))
+        }
+
+        match realization {
+            RealizationKind::Df1 => Section::Kernel(K::with_coeffs(coeffs)),
+            RealizationKind::Df2 => Section::Scalar(form::<DirectForm2>(coeffs)),
+            RealizationKind::Tdf1 => Section::Scalar(form::<TransposedDirectForm1>(coeffs)),
+            RealizationKind::Tdf2 => Section::Scalar(form::<TransposedDirectForm2>(coeffs)),
+            RealizationKind::Lattice => Section::Scalar(form::<Lattice>(coeffs)),
+            RealizationKind::NormalizedLattice => {
+                Section::Scalar(form::<NormalizedLattice>(coeffs))
+            }
+            RealizationKind::Coupled => {
+                let c = CoupledFormCoeffs::from_biquad(coeffs)
+                    .expect("the coupled form can't realize real poles");
+                let mut b = CoupledFormBiQuad::new(c);
+                Section::Scalar(Box::new(move |x| b.process(x)))
+            }
+            RealizationKind::ErrorFeedback => {
+                let mut b = BiQuadErrorFeedback::new();
+                b.set_coeffs(coeffs);
+                Section::Scalar(Box::new(move |x| b.process(x)))
+            }
+        }
+    }
+}
+
+enum Stages {
+    Scalar(Vec<Section<BiQuadF32>>),
+    Portable4(Vec<Section<BiQuadPortable4>>),
+    Portable8(Vec<Section<BiQuadPortable8>>),
+    #[cfg(target_arch = "x86_64")]
+    Sse2(Vec<Section<BiQuadSSE2>>),
+    #[cfg(target_arch = "x86_64")]
+    Avx(Vec<Section<BiQuadAVX>>),
+}
+
+// Series of biquad sections running on a single channel with one of the
+// kernels. Every block of samples goes through all the sections before the
+// next one is loaded, so the signal stays in registers between sections.
+pub struct Cascade {
+    backend: Backend,
+    stages: Stages,
+    realizations: Vec<RealizationKind>,
+}
+
+impl Cascade {
+    // All sections in Direct Form I, on the backend's kernel. Panics if the
+    // CPU doesn't support the backend, see `Backend::is_supported`.
+    pub fn new(backend: Backend, sections: &[BiQuadCoeffs]) -> Self {
+        let sections = sections
+            .iter()
+            .map(|c| (*c, RealizationKind::Df1))
+            .collect::<Vec<_>>();
+        Self::with_realizations(backend, &sections)
+    }
+
+    // Sections with their own realization. Panics if a coupled form section
+    // has real poles, see `FilterSpec::validate_at`.
+    pub fn with_realizations(
+        backend: Backend,
+        sections: &[(BiQuadCoeffs, RealizationKind)],
+    ) -> Self {
+        assert!(
+            backend.is_supported(),
+            "the {} backend isn't supported by this CPU",
+            backend
+        );
+
+        fn build<K: Kernel<N>, const N: usize>(
+            sections: &[(BiQuadCoeffs, RealizationKind)],
+        ) -> Vec<Section<K>> {
+            sections
+                .iter()
+                .map(|(c, realization)| Section::new::<N>(c, *realization))
+                .collect()
+        }
+
+        let stages = match backend {
+            Backend::Scalar => Stages::Scalar(build(sections)),
+            Backend::Portable4 => Stages::Portable4(build(sections)),
+            Backend::Portable8 => Stages::Portable8(build(sections)),
+            #[cfg(target_arch = "x86_64")]
+            Backend::Sse2 => Stages::Sse2(build(sections)),
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx => Stages::Avx(build(sections)),
+        };
+
+        Cascade {
+            backend,
+            stages,
+            realizations: sections.iter().map(|(_, r)| *r).collect(),
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.stages {
+            Stages::Scalar(s) => s.len(),
+            Stages::Portable4(s) => s.len(),
+            Stages::Portable8(s) => s.len(),
+            #[cfg(target_arch = "x86_64")]
+            Stages::Sse2(s) => s.len(),
+            #[cfg(target_arch = "x86_64")]
+            Stages::Avx(s) => s.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Replaces the coefficients of one section. Kernel sections keep their
+    // state, so parameters can change while the cascade runs; sections in
+    // other realizations start over from silence.
+    pub fn set_coeffs(&mut self, index: usize, coeffs: &BiQuadCoeffs) {
+        fn set<K: Kernel<N>, const N: usize>(
+            section: &mut Section<K>,
+            coeffs: &BiQuadCoeffs,
+            realization: RealizationKind,
+        ) {
+            match section {
+                Section::Kernel(k) => k.set_coeffs(coeffs),
+                Section::Scalar(_) => *section = Section::new::<N>(coeffs, realization),
+            }
+        }
+
+        let realization = self.realizations[index];
+        match &mut self.stages {
+            Stages::Scalar(s) => set(&mut s[index], coeffs, realization),
+            Stages::Portable4(s) => set(&mut s[index], coeffs, realization),
+            Stages::Portable8(s) => set(&mut s[index], coeffs, realization),
+            #[cfg(target_arch = "x86_64")]
+            Stages::Sse2(s) => set(&mut s[index], coeffs, realization),
+            #[cfg(target_arch = "x86_64")]
+            Stages::Avx(s) => set(&mut s[index], coeffs, realization),
+        }
+    }
+
+    // Kernel level denormal flushing, for targets where
+    // `ScopedFlushDenormals` isn't active. The portable kernels and the
+    // scalar realizations don't have it and ignore the setting.
+    pub fn set_flush_denormals(&mut self, enabled: bool) {
+        fn set<K: Kernel<N>, const N: usize>(sections: &mut [Section<K>], enabled: bool) {
+            for section in sections {
+                if let Section::Kernel(k) = section {
+                    k.set_flush_denormals(enabled);
+                }
+            }
+        }
+
+        match &mut self.stages {
+            Stages::Scalar(s) => set(s, enabled),
+            Stages::Portable4(s) => set(s, enabled),
+            Stages::Portable8(s) => set(s, enabled),
+            #[cfg(target_arch = "x86_64")]
+            Stages::Sse2(s) => set(s, enabled),
+            #[cfg(target_arch = "x86_64")]
+            Stages::Avx(s) => set(s, enabled),
+        }
+    }
+
+    // Filters the samples in place, any number of them per call
+    pub fn process(&mut self, samples: &mut [f32]) {
+        match &mut self.stages {
+            Stages::Scalar(s) => process_sections(s, samples),
+            Stages::Portable4(s) => process_sections(s, samples),
+            Stages::Portable8(s) => process_sections(s, samples),
+            #[cfg(target_arch = "x86_64")]
+            Stages::Sse2(s) => process_sections(s, samples),
+            #[cfg(target_arch = "x86_64")]
+            Stages::Avx(s) => process_sections(s, samples),
+        }
+    }
+}
+
+// Runs each block through all sections, keeping it in a vector register
+// across consecutive kernel sections
+fn process_sections<K: Kernel<N>, const N: usize>(
+    sections: &mut [Section<K>],
+    samples: &mut [f32],
+) {
+    let mut chunks = samples.chunks_exact_mut(N);
+    for chunk in &mut chunks {
+        process_block(sections, <&mut [f32; N]>::try_from(chunk).unwrap());
+    }
+
+    // The samples that don't fill a block go through the kernels one at a
+    // time, on the same state, so the next call picks up where they left off
+    for x in chunks.into_remainder() {
+        for section in sections.iter_mut() {
+            *x = match section {
+                Section::Kernel(k) => k.step_sample(*x),
+                Section::Scalar(process) => process(*x),
+            };
+        }
+    }
+}
+
+fn process_block<K: Kernel<N>, const N: usize>(sections: &mut [Section<K>], block: &mut [f32; N]) {
+    let mut v = K::load(block);
+    let mut loaded = true;
+
+    for section in sections {
+        match section {
+            Section::Kernel(k) => {
+                if !loaded {
+                    v = K::load(block);
+                    loaded = true;
+                }
+                v = k.step(v);
+            }
+            Section::Scalar(process) => {
+                if loaded {
+                    K::store(v, block);
+                    loaded = false;
+                }
+                for x in block.iter_mut() {
+                    *x = process(*x);
+                }
+            }
+        }
+    }
+
+    if loaded {
+        K::store(v, block);
+    }
+}
@@ -0,0 +1,181 @@
+use std::f32::consts::PI;
+
+// Bilinear transform frequency prewarping, tan(pi * fc / fs)
+pub fn prewarp(sample_rate: f32, cutoff: f32) -> f32 {
+    (PI * cutoff / sample_rate).tan()
+}
+
+// Normalized biquad coefficients, using the same naming as `BiQuadF32`:
+//
+//   y[n] = a0 x[n] + a1 x[n-1] + a2 x[n-2] - b1 y[n-1] - b2 y[n-2]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BiQuadCoeffs {
+    pub a0: f32,
+    pub a1: f32,
+    pub a2: f32,
+    pub b1: f32,
+    pub b2: f32,
+}
+
+impl Default for BiQuadCoeffs {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl BiQuadCoeffs {
+    pub fn new(a0: f32, a1: f32, a2: f32, b1: f32, b2: f32) -> Self {
+        BiQuadCoeffs { a0, a1, a2, b1, b2 }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(1.0, 0.0, 0.0, 0.0, 0.0)
+    }
+
+    // First order allpass, the filter `BiQuadF32::update` has always built
+    pub fn allpass(sample_rate: f32, cutoff: f32) -> Self {
+        let t = prewarp(sample_rate, cutoff);
+        let alpha = (t - 1.0) / (t + 1.0);
+
+        Self::new(alpha, 1.0, 0.0, alpha, 0.0)
+    }
+
+    // Bilinear transform of 1 / (1 + s)
+    pub fn first_order_lowpass(sample_rate: f32, cutoff: f32) -> Self {
+        let t = prewarp(sample_rate, cutoff) as f64;
+
+        Self::normalized_f64([t, t, 0.0], [1.0 + t, t - 1.0, 0.0])
+    }
+
+    // Bilinear transform of s / (1 + s)
+    pub fn first_order_highpass(sample_rate: f32, cutoff: f32) -> Self {
+        let t = prewarp(sample_rate, cutoff) as f64;
+
+        Self::normalized_f64([1.0, -1.0, 0.0], [1.0 + t, t - 1.0, 0.0])
+    }
+
+    // RBJ cookbook lowpass. Designed in double precision: at low cutoffs
+    // 1 - cos(w0) loses most of its significant bits in f32.
+    pub fn lowpass(sample_rate: f32, cutoff: f32, q: f32) -> Self {
+        let (cos, alpha) = rbj(sample_rate, cutoff, q);
+
+        Self::normalized_f64(
+            [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    pub fn highpass(sample_rate: f32, cutoff: f32, q: f32) -> Self {
+        let (cos, alpha) = rbj(sample_rate, cutoff, q);
+
+        Self::normalized_f64(
+            [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    // Constant 0 dB peak gain
+    pub fn bandpass(sample_rate: f32, center: f32, q: f32) -> Self {
+        let (cos, alpha) = rbj(sample_rate, center, q);
+
+        Self::normalized_f64([alpha, 0.0, -alpha], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    pub fn notch(sample_rate: f32, center: f32, q: f32) -> Self {
+        let (cos, alpha) = rbj(sample_rate, center, q);
+
+        Self::normalized_f64(
+            [1.0, -2.0 * cos, 1.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    pub fn peaking(sample_rate: f32, center: f32, q: f32, gain_db: f32) -> Self {
+        let (cos, alpha) = rbj(sample_rate, center, q);
+        let a = 10f64.powf(gain_db as f64 / 40.0);
+
+        Self::normalized_f64(
+            [1.0 + alpha * a, -2.0 * cos, 1.0 - alpha * a],
+            [1.0 + alpha / a, -2.0 * cos, 1.0 - alpha / a],
+        )
+    }
+
+    pub fn low_shelf(sample_rate: f32, cutoff: f32, q: f32, gain_db: f32) -> Self {
+        let (cos, alpha) = rbj(sample_rate, cutoff, q);
+        let a = 10f64.powf(gain_db as f64 / 40.0);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        Self::normalized_f64(
+            [
+                a * ((a + 1.0) - (a - 1.0) * cos + sqrt_a_alpha),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                a * ((a + 1.0) - (a - 1.0) * cos - sqrt_a_alpha),
+            ],
+            [
+                (a + 1.0) + (a - 1.0) * cos + sqrt_a_alpha,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                (a + 1.0) + (a - 1.0) * cos - sqrt_a_alpha,
+            ],
+        )
+    }
+
+    pub fn high_shelf(sample_rate: f32, cutoff: f32, q: f32, gain_db: f32) -> Self {
+        let (cos, alpha) = rbj(sample_rate, cutoff, q);
+        let a = 10f64.powf(gain_db as f64 / 40.0);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        Self::normalized_f64(
+            [
+                a * ((a + 1.0) + (a - 1.0) * cos + sqrt_a_alpha),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                a * ((a + 1.0) + (a - 1.0) * cos - sqrt_a_alpha),
+            ],
+            [
+                (a + 1.0) - (a - 1.0) * cos + sqrt_a_alpha,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                (a + 1.0) - (a - 1.0) * cos - sqrt_a_alpha,
+            ],
+        )
+    }
+
+    // RBJ cookbook allpass, 360 degrees of phase shift with 180 at the center
+    pub fn second_order_allpass(sample_rate: f32, center: f32, q: f32) -> Self {
+        let (cos, alpha) = rbj(sample_rate, center, q);
+
+        Self::normalized_f64(
+            [1.0 - alpha, -2.0 * cos, 1.0 + alpha],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    // Builds coefficients from b[0..3] / a[0..3] in the usual textbook
+    // ordering (numerator first), dividing out the leading denominator term
+    pub fn normalized(num: [f32; 3], den: [f32; 3]) -> Self {
+        let d0 = den[0];
+        Self::new(
+            num[0] / d0,
+            num[1] / d0,
+            num[2] / d0,
+            den[1] / d0,
+            den[2] / d0,
+        )
+    }
+
+    pub fn normalized_f64(num: [f64; 3], den: [f64; 3]) -> Self {
+        let d0 = den[0];
+        Self::new(
+            (num[0] / d0) as f32,
+            (num[1] / d0) as f32,
+            (num[2] / d0) as f32,
+            (den[1] / d0) as f32,
+            (den[2] / d0) as f32,
+        )
+    }
+}
+
+// cos(w0) and alpha = sin(w0) / 2Q shared by all the RBJ cookbook designs
+fn rbj(sample_rate: f32, frequency: f32, q: f32) -> (f64, f64) {
+    let w0 = 2.0 * std::f64::consts::PI * frequency as f64 / sample_rate as f64;
+    let (sin, cos) = w0.sin_cos();
+    (cos, sin / (2.0 * q as f64))
+}
@@ -0,0 +1,157 @@
+use std::{error::Error, fmt, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cascade::{Backend, BackendHint, Cascade},
+    coeffs::BiQuadCoeffs,
+    design::{FilterError, FilterSpec, RealizationKind},
+};
+
+// A filter chain preset, stored as TOML or JSON. In TOML:
+//
+//   backend = "auto"
+//
+//   [[filter]]
+//   type = "highpass"
+//   frequency = 30.0
+//   order = 4
+//
+//   [[filter]]
+//   type = "peak"
+//   frequency = 120.0
+//   q = 4.0
+//   gain_db = -6.0
+//   realization = "normalized-lattice"
+//
+//   [[filter]]
+//   type = "highshelf"
+//   frequency = 10000.0
+//   gain_db = 3.0
+//   method = "magnitude-matched"
+//
+// Everything but `type` and `frequency` is optional, see `FilterSpec` for the
+// defaults.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainConfig {
+    #[serde(default)]
+    pub backend: BackendHint,
+    #[serde(default, rename = "filter", alias = "filters")]
+    pub filters: Vec<FilterSpec>,
+}
+
+impl ChainConfig {
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        toml::from_str(text).map_err(|e| ConfigError::Syntax(e.to_string()))
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str(text).map_err(|e| ConfigError::Syntax(e.to_string()))
+    }
+
+    // JSON for `.json` files, TOML for anything else
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(ConfigError::Io)?;
+        if path.extension().is_some_and(|ext| ext == "json") {
+            Self::from_json(&text)
+        } else {
+            Self::from_toml(&text)
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("chain configs always serialize")
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("chain configs always serialize")
+    }
+
+    // Checks every entry at the sample rate the chain will run at, and
+    // reports the first invalid one
+    pub fn validate(&self, sample_rate: f32) -> Result<(), ConfigError> {
+        for (index, spec) in self.filters.iter().enumerate() {
+            spec.validate_at(sample_rate)
+                .map_err(|error| ConfigError::Filter {
+                    index,
+                    spec: *spec,
+                    error,
+                })?;
+        }
+        Ok(())
+    }
+
+    // All second order sections of the chain, in processing order
+    pub fn sections(
+        &self,
+        sample_rate: f32,
+    ) -> Result<Vec<(BiQuadCoeffs, RealizationKind)>, ConfigError> {
+        self.validate(sample_rate)?;
+
+        Ok(self
+            .filters
+            .iter()
+            .flat_map(|spec| {
+                spec.sections(sample_rate)
+                    .into_iter()
+                    .map(move |c| (c, spec.realization))
+            })
+            .collect())
+    }
+
+    // Builds the chain on the backend the preset asks for, if it's available
+    pub fn build(&self, sample_rate: f32) -> Result<Cascade, ConfigError> {
+        self.build_with(self.backend.resolve(), sample_rate)
+    }
+
+    pub fn build_with(&self, backend: Backend, sample_rate: f32) -> Result<Cascade, ConfigError> {
+        Ok(Cascade::with_realizations(
+            backend,
+            &self.sections(sample_rate)?,
+        ))
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    // Malformed TOML or JSON, or a value of the wrong type. The message has
+    // the position in the file.
+    Syntax(String),
+    // A well formed entry with invalid parameters. `index` counts from zero,
+    // but is displayed counting from one.
+    Filter {
+        index: usize,
+        spec: FilterSpec,
+        error: FilterError,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "{}", e),
+            ConfigError::Syntax(message) => f.write_str(message),
+            ConfigError::Filter { index, spec, error } => write!(
+                f,
+                "filter {} ({} at {} Hz): {}",
+                index + 1,
+                spec.kind,
+                spec.frequency,
+                error
+            ),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Io(e) => Some(e),
+            ConfigError::Filter { error, .. } => Some(error),
+            ConfigError::Syntax(_) => None,
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    cascade::{Backend, Cascade},
+    coeffs::BiQuadCoeffs,
+    design::{FilterError, FilterKind, FilterSpec},
+};
+
+// Linkwitz-Riley crossovers: each side of a split is a Butterworth filter of
+// half the order applied twice, so both are -6 dB at the split frequency and
+// their sum is an allpass.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LinkwitzRiley {
+    Lr2,
+    Lr4,
+    Lr8,
+}
+
+impl LinkwitzRiley {
+    pub const ALL: [LinkwitzRiley; 3] =
+        [LinkwitzRiley::Lr2, LinkwitzRiley::Lr4, LinkwitzRiley::Lr8];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LinkwitzRiley::Lr2 => "lr2",
+            LinkwitzRiley::Lr4 => "lr4",
+            LinkwitzRiley::Lr8 => "lr8",
+        }
+    }
+
+    pub fn order(self) -> u32 {
+        match self {
+            LinkwitzRiley::Lr2 => 2,
+            LinkwitzRiley::Lr4 => 4,
+            LinkwitzRiley::Lr8 => 8,
+        }
+    }
+
+    fn butterworth(self, kind: FilterKind, sample_rate: f32, frequency: f32) -> Vec<BiQuadCoeffs> {
+        let half = FilterSpec::new(kind, frequency)
+            .with_order(self.order() / 2)
+            .sections(sample_rate);
+        half.iter().chain(half.iter()).copied().collect()
+    }
+
+    pub fn lowpass(self, sample_rate: f32, frequency: f32) -> Vec<BiQuadCoeffs> {
+        self.butterworth(FilterKind::Lowpass, sample_rate, frequency)
+    }
+
+    // The LR2 sides are in antiphase, so its highpass is inverted to make the
+    // sum an allpass rather than a notch
+    pub fn highpass(self, sample_rate: f32, frequency: f32) -> Vec<BiQuadCoeffs> {
+        let mut sections = self.butterworth(FilterKind::Highpass, sample_rate, frequency);
+        if self == LinkwitzRiley::Lr2 {
+            let c = &mut sections[0];
+            *c = BiQuadCoeffs::new(-c.a0, -c.a1, -c.a2, c.b1, c.b2);
+        }
+        sections
+    }
+
+    // Sum of the lowpass and highpass sides. With a Butterworth denominator
+    // D(s), that's D(-s) / D(s): the numerator of each section is its
+    // denominator reversed, without the extra delay for first order ones.
+    pub fn allpass(self, sample_rate: f32, frequency: f32) -> Vec<BiQuadCoeffs> {
+        FilterSpec::new(FilterKind::Lowpass, frequency)
+            .with_order(self.order() / 2)
+            .sections(sample_rate)
+            .iter()
+            .map(|c| {
+                if c.b2 == 0.0 {
+                    BiQuadCoeffs::new(c.b1, 1.0, 0.0, c.b1, 0.0)
+                } else {
+                    BiQuadCoeffs::new(c.b2, c.b1, 1.0, c.b1, c.b2)
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for LinkwitzRiley {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for LinkwitzRiley {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        LinkwitzRiley::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| FilterError(format!("unknown crossover type '{}'", s)))
+    }
+}
+
+pub const MIN_BANDS: usize = 2;
+pub const MAX_BANDS: usize = 5;
+
+// Splits a signal into bands at increasing frequencies. The splits are a
+// tree: band 0 is the lowpass of the first split, and the highpass goes on
+// to the next split. Bands that skip later splits get the allpass of those
+// splits instead, so all bands have the same phase and their sum is an
+// allpass of the input.
+pub struct Crossover {
+    kind: LinkwitzRiley,
+    frequencies: Vec<f32>,
+    // Per split: the lowpass plus compensation of the band it ends, and the
+    // highpass feeding the next split
+    lowpass: Vec<Cascade>,
+    highpass: Vec<Cascade>,
+    sections: Vec<Vec<BiQuadCoeffs>>,
+}
+
+impl Crossover {
+    // Panics if the CPU doesn't support the backend, see
+    // `Backend::is_supported`
+    pub fn new(
+        backend: Backend,
+        kind: LinkwitzRiley,
+        sample_rate: f32,
+        frequencies: &[f32],
+    ) -> Result<Self, FilterError> {
+        let bands = frequencies.len() + 1;
+        if !(MIN_BANDS..=MAX_BANDS).contains(&bands) {
+            return Err(FilterError(format!(
+                "a crossover has {} to {} bands, not {}",
+                MIN_BANDS, MAX_BANDS, bands
+            )));
+        }
+        for f in frequencies {
+            if !(f.is_finite() && *f > 0.0) {
+                return Err(FilterError(format!(
+                    "split frequency must be positive, got {}",
+                    f
+                )));
+            }
+            if *f >= sample_rate / 2.0 {
+                return Err(FilterError(format!(
+                    "split frequency {} Hz is above Nyquist ({} Hz)",
+                    f,
+                    sample_rate / 2.0
+                )));
+            }
+        }
+        if frequencies.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(FilterError(
+                "split frequencies must be increasing".to_string(),
+            ));
+        }
+
+        let allpass = |from: usize| {
+            frequencies[from..]
+                .iter()
+                .flat_map(|f| kind.allpass(sample_rate, *f))
+                .collect::<Vec<_>>()
+        };
+
+        let mut lowpass = Vec::new();
+        let mut highpass = Vec::new();
+        let mut sections = Vec::new();
+        let mut path = Vec::new();
+        for (i, f) in frequencies.iter().enumerate() {
+            let mut band = kind.lowpass(sample_rate, *f);
+            band.extend(allpass(i + 1));
+            lowpass.push(Cascade::new(backend, &band));
+            sections.push(path.iter().chain(band.iter()).copied().collect());
+
+            let split = kind.highpass(sample_rate, *f);
+            highpass.push(Cascade::new(backend, &split));
+            path.extend(split);
+        }
+        sections.push(path);
+
+        Ok(Crossover {
+            kind,
+            frequencies: frequencies.to_vec(),
+            lowpass,
+            highpass,
+            sections,
+        })
+    }
+
+    pub fn kind(&self) -> LinkwitzRiley {
+        self.kind
+    }
+
+    pub fn frequencies(&self) -> &[f32] {
+        &self.frequencies
+    }
+
+    pub fn bands(&self) -> usize {
+        self.frequencies.len() + 1
+    }
+
+    // Everything the input goes through on its way to the band, for
+    // computing its response
+    pub fn band_sections(&self, band: usize) -> &[BiQuadCoeffs] {
+        &self.sections[band]
+    }
+
+    pub fn set_flush_denormals(&mut self, enabled: bool) {
+        for cascade in self.lowpass.iter_mut().chain(self.highpass.iter_mut()) {
+            cascade.set_flush_denormals(enabled);
+        }
+    }
+
+    // Writes every band of the input to its own output, which must all be as
+    // long as the input
+    pub fn process(&mut self, input: &[f32], bands: &mut [&mut [f32]]) {
+        assert_eq!(bands.len(), self.bands(), "wrong number of bands");
+        assert!(
+            bands.iter().all(|band| band.len() == input.len()),
+            "band outputs must be as long as the input"
+        );
+
+        // The highpass side runs in place in the last band, each split
+        // copying it out before it moves on
+        let (rest, bands) = bands.split_last_mut().unwrap();
+        rest.copy_from_slice(input);
+        for ((band, lowpass), highpass) in bands
+            .iter_mut()
+            .zip(self.lowpass.iter_mut())
+            .zip(self.highpass.iter_mut())
+        {
+            band.copy_from_slice(rest);
+            lowpass.process(band);
+            highpass.process(rest);
+        }
+    }
+}
@@ -0,0 +1,577 @@
+use std::{f64::consts::PI, fmt, str::FromStr};
+
+use wide::f64x4;
+
+use crate::{
+    coeffs::BiQuadCoeffs,
+    design::{FilterError, MAX_ORDER},
+    response::Complex,
+};
+
+// Response of the anti-alias lowpass. Chebyshev (type I) has ripple in the
+// passband and a monotonic stopband; elliptic has ripple in both and gets
+// the steepest transition for the order.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AntiAliasKind {
+    Chebyshev,
+    Elliptic,
+}
+
+impl AntiAliasKind {
+    pub const ALL: [AntiAliasKind; 2] = [AntiAliasKind::Chebyshev, AntiAliasKind::Elliptic];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AntiAliasKind::Chebyshev => "chebyshev",
+            AntiAliasKind::Elliptic => "elliptic",
+        }
+    }
+}
+
+impl fmt::Display for AntiAliasKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for AntiAliasKind {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AntiAliasKind::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| FilterError(format!("unknown anti-alias filter '{}'", s)))
+    }
+}
+
+// The lowpass in front of a rate change by an integer factor. Its passband
+// ends at `passband` times the low rate's Nyquist, and its stopband starts
+// at that Nyquist, so nothing aliases onto the low rate's band that isn't
+// attenuated by `attenuation_db`. The order is the lowest that does it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AntiAlias {
+    pub kind: AntiAliasKind,
+    pub passband: f64,
+    pub ripple_db: f64,
+    pub attenuation_db: f64,
+}
+
+impl Default for AntiAlias {
+    fn default() -> Self {
+        Self::new(AntiAliasKind::Elliptic)
+    }
+}
+
+// Numerator and denominator of a section in double precision
+type Section = ([f64; 3], [f64; 3]);
+
+impl AntiAlias {
+    pub const DEFAULT_PASSBAND: f64 = 0.8;
+    pub const DEFAULT_RIPPLE_DB: f64 = 0.1;
+    pub const DEFAULT_ATTENUATION_DB: f64 = 80.0;
+
+    pub fn new(kind: AntiAliasKind) -> Self {
+        AntiAlias {
+            kind,
+            passband: Self::DEFAULT_PASSBAND,
+            ripple_db: Self::DEFAULT_RIPPLE_DB,
+            attenuation_db: Self::DEFAULT_ATTENUATION_DB,
+        }
+    }
+
+    pub fn with_passband(self, passband: f64) -> Self {
+        AntiAlias { passband, ..self }
+    }
+
+    pub fn with_ripple_db(self, ripple_db: f64) -> Self {
+        AntiAlias { ripple_db, ..self }
+    }
+
+    pub fn with_attenuation_db(self, attenuation_db: f64) -> Self {
+        AntiAlias {
+            attenuation_db,
+            ..self
+        }
+    }
+
+    pub fn validate(&self, factor: usize) -> Result<(), FilterError> {
+        if factor < 2 {
+            return Err(FilterError(format!(
+                "the rate changes by a factor of 2 or more, not {}",
+                factor
+            )));
+        }
+        if !(self.passband > 0.0 && self.passband < 1.0) {
+            return Err(FilterError(format!(
+                "the passband is between 0 and 1 of the low rate's Nyquist, not {}",
+                self.passband
+            )));
+        }
+        if !(self.ripple_db > 0.0 && self.ripple_db < self.attenuation_db) {
+            return Err(FilterError(format!(
+                "invalid ripple {} dB and attenuation {} dB",
+                self.ripple_db, self.attenuation_db
+            )));
+        }
+        Ok(())
+    }
+
+    // Analog passband and stopband edges prewarped, and the ripple factors
+    fn edges(&self, factor: usize) -> (f64, f64, f64, f64) {
+        let nyquist = PI / (2 * factor) as f64;
+        let pass = (nyquist * self.passband).tan();
+        let stop = nyquist.tan();
+        let ripple = (10f64.powf(self.ripple_db / 10.0) - 1.0).sqrt();
+        let stopband = (10f64.powf(self.attenuation_db / 10.0) - 1.0).sqrt();
+        (pass, stop, ripple, stopband)
+    }
+
+    pub fn order(&self, factor: usize) -> Result<u32, FilterError> {
+        self.validate(factor)?;
+        let (pass, stop, ripple, stopband) = self.edges(factor);
+        let order = match self.kind {
+            AntiAliasKind::Chebyshev => (stopband / ripple).acosh() / (stop / pass).acosh(),
+            AntiAliasKind::Elliptic => {
+                let (k, k1) = (pass / stop, ripple / stopband);
+                let complement = |k: f64| (1.0 - k * k).sqrt();
+                elliptic_k(k) * elliptic_k(complement(k1))
+                    / (elliptic_k(complement(k)) * elliptic_k(k1))
+            }
+        };
+        let order = (order - 1e-9).ceil().max(1.0) as u32;
+        if order > MAX_ORDER {
+            return Err(FilterError(format!(
+                "a {} anti-alias filter for a factor of {} needs order {}, more than {}",
+                self.kind, factor, order, MAX_ORDER
+            )));
+        }
+        Ok(order)
+    }
+
+    // The lowpass at the high rate, sections of conjugate poles with their
+    // zeros and a first order one last for odd orders. Every section has
+    // unit gain at DC, bar the ripple of even orders.
+    pub fn sections(&self, factor: usize) -> Result<Vec<BiQuadCoeffs>, FilterError> {
+        Ok(self
+            .design(factor)?
+            .iter()
+            .map(|(num, den)| BiQuadCoeffs::normalized_f64(*num, *den))
+            .collect())
+    }
+
+    // Analog prototype with its passband edge at 1 through the bilinear
+    // transform, in double precision
+    fn design(&self, factor: usize) -> Result<Vec<Section>, FilterError> {
+        let order = self.order(factor)?;
+        let (pass, _, ripple, stopband) = self.edges(factor);
+        let (zeros, poles, real_pole) = match self.kind {
+            AntiAliasKind::Chebyshev => chebyshev(order, ripple),
+            AntiAliasKind::Elliptic => elliptic(order, ripple, stopband),
+        };
+
+        // s = (1 - z^-1) / (pass (1 + z^-1)) puts the prototype's edge at
+        // the digital passband edge
+        let bilinear = |s: Complex| {
+            let s = s.scale(pass);
+            (Complex::ONE + s) / (Complex::ONE - s)
+        };
+        let quadratic = |z: Complex| [1.0, -2.0 * z.re, z.re * z.re + z.im * z.im];
+        let dc = |p: [f64; 3]| p.iter().sum::<f64>();
+
+        let mut sections = poles
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let den = quadratic(bilinear(*p));
+                let num = match zeros.get(i) {
+                    Some(z) => quadratic(bilinear(*z)),
+                    None => [1.0, 2.0, 1.0],
+                };
+                let gain = dc(den) / dc(num);
+                ([num[0] * gain, num[1] * gain, num[2] * gain], den)
+            })
+            .collect::<Vec<_>>();
+        if let Some(p) = real_pole {
+            let pole = bilinear(Complex::new(p, 0.0)).re;
+            let gain = (1.0 - pole) / 2.0;
+            sections.push(([gain, gain, 0.0], [1.0, -pole, 0.0]));
+        }
+        if order % 2 == 0 {
+            let ripple = 1.0 / (1.0 + ripple * ripple).sqrt();
+            let (num, _) = &mut sections[0];
+            num.iter_mut().for_each(|c| *c *= ripple);
+        }
+        Ok(sections)
+    }
+}
+
+// Upper half plane poles of the Chebyshev prototype, and the real pole of
+// odd orders. The zeros are all at infinity.
+fn chebyshev(order: u32, ripple: f64) -> (Vec<Complex>, Vec<Complex>, Option<f64>) {
+    let n = order as f64;
+    let mu = (1.0 / ripple).asinh() / n;
+    let poles = (1..=order / 2)
+        .map(|k| {
+            let theta = PI * (2 * k - 1) as f64 / (2.0 * n);
+            Complex::new(-mu.sinh() * theta.sin(), mu.cosh() * theta.cos())
+        })
+        .collect();
+    let real_pole = if order % 2 == 1 {
+        Some(-mu.sinh())
+    } else {
+        None
+    };
+    (Vec::new(), poles, real_pole)
+}
+
+// Descending Landen sequence of moduli, which gets to zero fast enough that
+// a handful of steps reach f64 precision
+const LANDEN_STEPS: usize = 7;
+
+fn landen(mut k: f64) -> [f64; LANDEN_STEPS] {
+    let mut moduli = [0.0; LANDEN_STEPS];
+    for m in &mut moduli {
+        k = (k / (1.0 + (1.0 - k * k).sqrt())).powi(2);
+        *m = k;
+    }
+    moduli
+}
+
+// Complete elliptic integral of the first kind
+fn elliptic_k(k: f64) -> f64 {
+    PI / 2.0 * landen(k).iter().map(|v| 1.0 + v).product::<f64>()
+}
+
+// Jacobi cd(uK, k) and sn(uK, k) from the cosine and sine of u pi / 2,
+// through the ascending Landen transformation
+fn ascend(w: Complex, k: f64) -> Complex {
+    landen(k).iter().rev().fold(w, |w, v| {
+        w.scale(1.0 + v) / (Complex::ONE + (w * w).scale(*v))
+    })
+}
+
+fn cd(u: Complex, k: f64) -> Complex {
+    // cos(a - jb) = cos a cosh b + j sin a sinh b, for u = a + jb
+    let (a, b) = (u.re * PI / 2.0, u.im * PI / 2.0);
+    ascend(Complex::new(a.cos() * b.cosh(), -a.sin() * b.sinh()), k)
+}
+
+fn sn(u: f64, k: f64) -> f64 {
+    ascend(Complex::new((u * PI / 2.0).sin(), 0.0), k).re
+}
+
+// The v with sn(j v K, k) = j y, by the descending transformation
+fn asn_imaginary(mut y: f64, k: f64) -> f64 {
+    let mut previous = k;
+    for v in landen(k).iter() {
+        y = y / (1.0 + (1.0 + y * y * previous * previous).sqrt()) * 2.0 / (1.0 + v);
+        previous = *v;
+    }
+    2.0 / PI * y.asinh()
+}
+
+// Elliptic prototype after Orfanidis: the selectivity follows from the
+// order and the ripple ratio by the degree equation, which leaves the
+// stopband edge at or below the one asked for
+fn elliptic(order: u32, ripple: f64, stopband: f64) -> (Vec<Complex>, Vec<Complex>, Option<f64>) {
+    let n = order as f64;
+    let k1 = ripple / stopband;
+    let k1_complement = (1.0 - k1 * k1).sqrt();
+    let half = (1..=order / 2).map(|i| (2 * i - 1) as f64 / n);
+    let k_complement = k1_complement.powf(n)
+        * half
+            .clone()
+            .map(|u| sn(u, k1_complement))
+            .product::<f64>()
+            .powi(4);
+    let k = (1.0 - k_complement * k_complement).sqrt();
+
+    let v0 = asn_imaginary(1.0 / ripple, k1) / n;
+    let zeros = half
+        .clone()
+        .map(|u| Complex::new(0.0, 1.0 / (k * cd(Complex::new(u, 0.0), k).re)))
+        .collect();
+    let poles = half
+        .map(|u| {
+            let c = cd(Complex::new(u, -v0), k);
+            Complex::new(-c.im, c.re)
+        })
+        .collect();
+    let real_pole = if order % 2 == 1 {
+        // j sn(j v0 K, k), with sn of an imaginary argument imaginary too
+        let w = Complex::new(0.0, (v0 * PI / 2.0).sinh());
+        Some(-ascend(w, k).im)
+    } else {
+        None
+    };
+    (zeros, poles, real_pole)
+}
+
+// The lowpass split for a rate change by `factor`, by pole multiplication:
+// each pole p of a section moves to p^factor at the low rate once the
+// section's numerator takes 1 + p z^-1 + ... + p^(factor-1) z^-(factor-1)
+// to make up for it. That leaves an FIR at the high rate whose output is
+// only needed at the low rate's samples, followed by all-pole sections at
+// the low rate.
+//
+// The split starts from the f32 sections, so it is exactly the filter they
+// are, and stays in double precision: the FIR's taps are far larger than its
+// output, and the low rate's poles only cancel what it adds if they aren't
+// rounded. In f32 either loses about 6 dB per doubling of the factor.
+fn split(sections: &[BiQuadCoeffs], factor: usize) -> (Vec<f64>, Vec<[f64; 2]>) {
+    let multiply = |a: &[f64], b: &[f64]| {
+        let mut product = vec![0.0; a.len() + b.len() - 1];
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() {
+                product[i + j] += x * y;
+            }
+        }
+        product
+    };
+
+    let mut fir = vec![1.0];
+    let mut poles = Vec::with_capacity(sections.len());
+    for c in sections {
+        let num = [c.a0 as f64, c.a1 as f64, c.a2 as f64];
+        let den = [1.0, c.b1 as f64, c.b2 as f64];
+        let (num, roots) = if den[2] == 0.0 && num[2] == 0.0 {
+            (&num[..2], vec![Complex::new(-den[1], 0.0)])
+        } else {
+            let root = Complex::new(den[1] * den[1] / 4.0 - den[2], 0.0).sqrt();
+            let half = Complex::new(-den[1] / 2.0, 0.0);
+            (&num[..], vec![half + root, half - root])
+        };
+
+        // The sum of powers of each root, multiplied out in complex
+        // arithmetic since conjugate roots only give a real product together
+        let mut compensation = vec![Complex::ONE];
+        let mut raised = Vec::with_capacity(roots.len());
+        for p in roots {
+            let mut power = Complex::ONE;
+            let mut sum = Vec::with_capacity(factor);
+            for _ in 0..factor {
+                sum.push(power);
+                power = power * p;
+            }
+            let mut product = vec![Complex::ZERO; compensation.len() + factor - 1];
+            for (i, x) in compensation.iter().enumerate() {
+                for (j, y) in sum.iter().enumerate() {
+                    product[i + j] = product[i + j] + *x * *y;
+                }
+            }
+            compensation = product;
+            raised.push(power);
+        }
+        let compensation = compensation.iter().map(|c| c.re).collect::<Vec<_>>();
+        fir = multiply(&multiply(&fir, num), &compensation);
+
+        poles.push(match raised[..] {
+            [p] => [-p.re, 0.0],
+            [p, q] => [-(p + q).re, (p * q).re],
+            _ => unreachable!(),
+        });
+    }
+    (fir, poles)
+}
+
+// Dot product of the taps and as many samples, accumulated in double
+// precision
+fn dot<T: Copy + Into<f64>>(taps: &[f64], samples: &[T]) -> f64 {
+    let (t_chunks, s_chunks) = (taps.chunks_exact(4), samples.chunks_exact(4));
+    let tail = t_chunks
+        .remainder()
+        .iter()
+        .zip(s_chunks.remainder())
+        .map(|(t, s)| t * (*s).into())
+        .sum::<f64>();
+    let mut sum = f64x4::ZERO;
+    for (t, s) in t_chunks.zip(s_chunks) {
+        let s = f64x4::from([s[0].into(), s[1].into(), s[2].into(), s[3].into()]);
+        sum = f64x4::from(t).mul_add(s, sum);
+    }
+    sum.reduce_add() + tail
+}
+
+// The all-pole half of the split, 1 / (1 + b1 z^-1 + b2 z^-2) per section.
+// It runs at the low rate, so double precision costs little next to the FIR.
+struct AllPole {
+    coeffs: Vec<[f64; 2]>,
+    state: Vec<[f64; 2]>,
+}
+
+impl AllPole {
+    fn new(coeffs: Vec<[f64; 2]>) -> Self {
+        AllPole {
+            state: vec![[0.0; 2]; coeffs.len()],
+            coeffs,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state.iter_mut().for_each(|s| *s = [0.0; 2]);
+    }
+
+    fn process(&mut self, mut x: f64) -> f64 {
+        for ([b1, b2], [y1, y2]) in self.coeffs.iter().zip(&mut self.state) {
+            x -= b1 * *y1 + b2 * *y2;
+            *y2 = *y1;
+            *y1 = x;
+        }
+        x
+    }
+}
+
+// Downsampling by an integer factor behind an elliptic or Chebyshev
+// lowpass. Filtering then dropping all but every `factor`th sample is the
+// same as the FIR half of the split computed at those samples alone, then
+// the all-pole half at the low rate, which is what runs here: the cost per
+// input sample is about one multiply per order, not five per section.
+pub struct Decimator {
+    factor: usize,
+    spec: AntiAlias,
+    sections: Vec<BiQuadCoeffs>,
+    // Reversed, so it lines up with the input in time order
+    taps: Vec<f64>,
+    // The inputs the taps reach back to, then the current block
+    history: Vec<f32>,
+    poles: AllPole,
+}
+
+impl Decimator {
+    pub fn new(factor: usize, spec: AntiAlias) -> Result<Self, FilterError> {
+        let sections = spec.sections(factor)?;
+        let (fir, poles) = split(&sections, factor);
+        let taps = fir.into_iter().rev().collect::<Vec<_>>();
+        Ok(Decimator {
+            factor,
+            spec,
+            sections,
+            history: vec![0.0; taps.len() - 1],
+            taps,
+            poles: AllPole::new(poles),
+        })
+    }
+
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    pub fn spec(&self) -> AntiAlias {
+        self.spec
+    }
+
+    // The anti-alias lowpass at the high rate this is equivalent to
+    pub fn sections(&self) -> &[BiQuadCoeffs] {
+        &self.sections
+    }
+
+    pub fn reset(&mut self) {
+        self.history.iter_mut().for_each(|x| *x = 0.0);
+        self.poles.reset();
+    }
+
+    // The input is `factor` times as long as the output, and output sample
+    // n is the filtered input sample n * factor
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        assert_eq!(
+            input.len(),
+            output.len() * self.factor,
+            "input length mismatch"
+        );
+        let kept = self.history.len();
+        self.history.extend_from_slice(input);
+        for (out, start) in output.iter_mut().zip((0..input.len()).step_by(self.factor)) {
+            let window = &self.history[start..start + self.taps.len()];
+            *out = self.poles.process(dot(&self.taps, window)) as f32;
+        }
+        self.history.drain(..self.history.len() - kept);
+    }
+}
+
+// Upsampling by an integer factor behind an elliptic or Chebyshev lowpass
+// that takes out the images of the low rate's band. The all-pole half of
+// the split runs at the low rate and the FIR half as a polyphase filter,
+// each output sample computed only from the input samples the zero stuffing
+// would leave.
+pub struct Interpolator {
+    factor: usize,
+    spec: AntiAlias,
+    sections: Vec<BiQuadCoeffs>,
+    // One reversed branch of the FIR per output phase, all as long as the
+    // first and scaled by the factor to make up for the zero stuffing
+    branches: Vec<Vec<f64>>,
+    // Outputs of the all-pole half the branches reach back to, then the
+    // current block
+    history: Vec<f64>,
+    poles: AllPole,
+}
+
+impl Interpolator {
+    pub fn new(factor: usize, spec: AntiAlias) -> Result<Self, FilterError> {
+        let sections = spec.sections(factor)?;
+        let (fir, poles) = split(&sections, factor);
+        let len = fir.len().div_ceil(factor);
+        let branches = (0..factor)
+            .map(|phase| {
+                let mut branch = (0..len)
+                    .map(|k| {
+                        fir.get(phase + k * factor)
+                            .map_or(0.0, |c| *c * factor as f64)
+                    })
+                    .collect::<Vec<_>>();
+                branch.reverse();
+                branch
+            })
+            .collect::<Vec<_>>();
+        Ok(Interpolator {
+            factor,
+            spec,
+            sections,
+            history: vec![0.0; len - 1],
+            branches,
+            poles: AllPole::new(poles),
+        })
+    }
+
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    pub fn spec(&self) -> AntiAlias {
+        self.spec
+    }
+
+    // The anti-image lowpass at the high rate this is equivalent to, less
+    // the gain of the factor
+    pub fn sections(&self) -> &[BiQuadCoeffs] {
+        &self.sections
+    }
+
+    pub fn reset(&mut self) {
+        self.history.iter_mut().for_each(|x| *x = 0.0);
+        self.poles.reset();
+    }
+
+    // The output is `factor` times as long as the input
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        assert_eq!(
+            output.len(),
+            input.len() * self.factor,
+            "output length mismatch"
+        );
+        let kept = self.history.len();
+        let poles = &mut self.poles;
+        self.history
+            .extend(input.iter().map(|x| poles.process(*x as f64)));
+        let len = self.branches[0].len();
+        for (start, out) in output.chunks_exact_mut(self.factor).enumerate() {
+            let window = &self.history[start..start + len];
+            for (y, branch) in out.iter_mut().zip(&self.branches) {
+                *y = dot(branch, window) as f32;
+            }
+        }
+        self.history.drain(..self.history.len() - kept);
+    }
+}
@@ -0,0 +1,178 @@
+use std::marker::PhantomData;
+
+// Magnitude below which the kernels' denormal protection flushes the
+// feedback state to zero. Well above the denormal range, so that products of
+// the state with small coefficients don't become denormal either, and far
+// below anything audible.
+pub const DENORMAL_FLUSH_THRESHOLD: f32 = 1e-30;
+
+// Flushes denormals to zero for as long as the guard is alive.
+//
+// The floating point control register is captured on creation and written
+// back on drop, so whatever policy the host application had set is restored
+// exactly, including rounding mode and exception masks. Guards can be nested:
+// each one restores the state that was active when it was created. The
+// register is per thread, so the guard can't be sent to another thread.
+//
+// On x86 and x86_64 this sets FTZ (and optionally DAZ) in MXCSR, on AArch64
+// the FZ bit in FPCR, which covers both denormal inputs and results. On every
+// other target the guard does nothing and `is_active` returns false, so the
+// caller knows to fall back to the kernels' own denormal protection, e.g.
+// `BiQuadF32::set_flush_denormals`.
+pub struct ScopedFlushDenormals {
+    saved: control::Register,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Default for ScopedFlushDenormals {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScopedFlushDenormals {
+    // Flush-to-zero: denormal results are replaced by zero
+    pub fn new() -> Self {
+        Self::with_flags(false)
+    }
+
+    // Flush-to-zero and denormals-are-zero: denormal inputs are also treated
+    // as zero, which avoids the slow path for denormals coming from outside
+    // the filter, e.g. from host buffers
+    pub fn with_daz() -> Self {
+        Self::with_flags(true)
+    }
+
+    // Whether denormals are actually being flushed on this thread right now
+    pub fn is_active(&self) -> bool {
+        is_flushing_denormals()
+    }
+
+    fn with_flags(daz: bool) -> Self {
+        let saved = control::read();
+        control::write(control::with_flush(saved, daz));
+
+        ScopedFlushDenormals {
+            saved,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Drop for ScopedFlushDenormals {
+    fn drop(&mut self) {
+        control::write(self.saved);
+    }
+}
+
+// Whether the current thread flushes denormals, whoever set it up
+pub fn is_flushing_denormals() -> bool {
+    control::is_flushing(control::read())
+}
+
+#[cfg(any(
+    target_arch = "x86_64",
+    all(target_arch = "x86", target_feature = "sse")
+))]
+mod control {
+    pub type Register = u32;
+
+    const FLUSH_TO_ZERO: u32 = 1 << 15;
+    const DENORMALS_ARE_ZERO: u32 = 1 << 6;
+
+    pub fn with_flush(mxcsr: u32, daz: bool) -> u32 {
+        if daz {
+            mxcsr | FLUSH_TO_ZERO | DENORMALS_ARE_ZERO
+        } else {
+            mxcsr | FLUSH_TO_ZERO
+        }
+    }
+
+    pub fn is_flushing(mxcsr: u32) -> bool {
+        mxcsr & FLUSH_TO_ZERO != 0
+    }
+
+    pub fn read() -> u32 {
+        let mut mxcsr = 0u32;
+        unsafe {
+            std::arch::asm!(
+                "stmxcsr [{}]",
+                in(reg) &mut mxcsr,
+                options(nostack, preserves_flags)
+            );
+        }
+        mxcsr
+    }
+
+    pub fn write(mxcsr: u32) {
+        unsafe {
+            std::arch::asm!(
+                "ldmxcsr [{}]",
+                in(reg) &mxcsr,
+                options(nostack, readonly, preserves_flags)
+            );
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod control {
+    pub type Register = u64;
+
+    const FLUSH_TO_ZERO: u64 = 1 << 24;
+
+    // FZ already applies to denormal inputs as well as results
+    pub fn with_flush(fpcr: u64, _daz: bool) -> u64 {
+        fpcr | FLUSH_TO_ZERO
+    }
+
+    pub fn is_flushing(fpcr: u64) -> bool {
+        fpcr & FLUSH_TO_ZERO != 0
+    }
+
+    pub fn read() -> u64 {
+        let fpcr: u64;
+        unsafe {
+            std::arch::asm!(
+                "mrs {}, fpcr",
+                out(reg) fpcr,
+                options(nomem, nostack, preserves_flags)
+            );
+        }
+        fpcr
+    }
+
+    pub fn write(fpcr: u64) {
+        unsafe {
+            std::arch::asm!(
+                "msr fpcr, {}",
+                in(reg) fpcr,
+                options(nostack, preserves_flags)
+            );
+        }
+    }
+}
+
+#[cfg(not(any(
+    target_arch = "x86_64",
+    all(target_arch = "x86", target_feature = "sse"),
+    target_arch = "aarch64"
+)))]
+mod control {
+    #[derive(Copy, Clone)]
+    pub struct Register;
+
+    pub fn with_flush(register: Register, _daz: bool) -> Register {
+        register
+    }
+
+    pub fn is_flushing(_: Register) -> bool {
+        false
+    }
+
+    pub fn read() -> Register {
+        Register
+    }
+
+    pub fn write(_: Register) {}
+}
@@ -0,0 +1,502 @@
+use std::{error::Error, fmt, str::FromStr};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{analog::Prototype, biquad_coupled::CoupledFormCoeffs, coeffs::BiQuadCoeffs};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum FilterKind {
+    Lowpass,
+    Highpass,
+    Bandpass,
+    Notch,
+    Peak,
+    LowShelf,
+    HighShelf,
+    Allpass,
+}
+
+impl FilterKind {
+    pub const ALL: [FilterKind; 8] = [
+        FilterKind::Lowpass,
+        FilterKind::Highpass,
+        FilterKind::Bandpass,
+        FilterKind::Notch,
+        FilterKind::Peak,
+        FilterKind::LowShelf,
+        FilterKind::HighShelf,
+        FilterKind::Allpass,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FilterKind::Lowpass => "lowpass",
+            FilterKind::Highpass => "highpass",
+            FilterKind::Bandpass => "bandpass",
+            FilterKind::Notch => "notch",
+            FilterKind::Peak => "peak",
+            FilterKind::LowShelf => "lowshelf",
+            FilterKind::HighShelf => "highshelf",
+            FilterKind::Allpass => "allpass",
+        }
+    }
+
+    // Whether the gain parameter has any effect
+    pub fn has_gain(self) -> bool {
+        matches!(
+            self,
+            FilterKind::Peak | FilterKind::LowShelf | FilterKind::HighShelf
+        )
+    }
+
+    // Order used when the spec doesn't give one. The allpass is the first
+    // order one `BiQuadF32::update` has always built.
+    pub fn default_order(self) -> u32 {
+        match self {
+            FilterKind::Allpass => 1,
+            _ => 2,
+        }
+    }
+}
+
+impl fmt::Display for FilterKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for FilterKind {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FilterKind::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| FilterError(format!("unknown filter type '{}'", s)))
+    }
+}
+
+// Structure a section is computed with. `Df1` runs on the block kernels of
+// whatever backend the cascade uses, the others on their scalar
+// implementation in `biquad_forms`, `biquad_lattice`, `biquad_coupled` and
+// `biquad_error_feedback`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum RealizationKind {
+    #[default]
+    Df1,
+    Df2,
+    Tdf1,
+    Tdf2,
+    Lattice,
+    NormalizedLattice,
+    Coupled,
+    ErrorFeedback,
+}
+
+// How the analog prototype of a section becomes digital. The bilinear
+// transform, the RBJ cookbook's method, is exact at the cutoff but squeezes
+// the whole frequency axis into Nyquist, so responses cramp as they near it:
+// a high shelf at 10 kHz never reaches its gain at 44.1 kHz. The other
+// methods keep the frequency axis as it is, see `analog::Prototype`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum DesignMethod {
+    #[default]
+    Bilinear,
+    // Poles and zeros mapped by z = e^(sT)
+    MatchedZ,
+    // Sampled impulse response, only sensible for lowpass and bandpass like
+    // responses that are well down by Nyquist
+    ImpulseInvariant,
+    // Matched poles, numerator fitted to the analog magnitude (Vicanek)
+    MagnitudeMatched,
+}
+
+impl DesignMethod {
+    pub const ALL: [DesignMethod; 4] = [
+        DesignMethod::Bilinear,
+        DesignMethod::MatchedZ,
+        DesignMethod::ImpulseInvariant,
+        DesignMethod::MagnitudeMatched,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DesignMethod::Bilinear => "bilinear",
+            DesignMethod::MatchedZ => "matched-z",
+            DesignMethod::ImpulseInvariant => "impulse-invariant",
+            DesignMethod::MagnitudeMatched => "magnitude-matched",
+        }
+    }
+}
+
+impl fmt::Display for DesignMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for DesignMethod {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DesignMethod::ALL
+            .iter()
+            .copied()
+            .find(|method| method.name() == s)
+            .ok_or_else(|| FilterError(format!("unknown design method '{}'", s)))
+    }
+}
+
+// Maximum order of the Butterworth lowpass and highpass designs
+pub const MAX_ORDER: u32 = 16;
+
+// A filter described by its design parameters rather than its coefficients,
+// so the same description can be used at any sample rate. Orders above two
+// are only supported for lowpass and highpass, as Butterworth cascades.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct FilterSpec {
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub kind: FilterKind,
+    pub frequency: f32,
+    #[cfg_attr(feature = "serde", serde(default = "default_q"))]
+    pub q: f32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub gain_db: f32,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub order: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub realization: RealizationKind,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub method: DesignMethod,
+}
+
+#[cfg(feature = "serde")]
+fn default_q() -> f32 {
+    FilterSpec::DEFAULT_Q
+}
+
+impl FilterSpec {
+    pub const DEFAULT_Q: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+    pub fn new(kind: FilterKind, frequency: f32) -> Self {
+        FilterSpec {
+            kind,
+            frequency,
+            q: Self::DEFAULT_Q,
+            gain_db: 0.0,
+            order: None,
+            realization: RealizationKind::Df1,
+            method: DesignMethod::Bilinear,
+        }
+    }
+
+    pub fn with_q(self, q: f32) -> Self {
+        FilterSpec { q, ..self }
+    }
+
+    pub fn with_gain_db(self, gain_db: f32) -> Self {
+        FilterSpec { gain_db, ..self }
+    }
+
+    pub fn with_order(self, order: u32) -> Self {
+        FilterSpec {
+            order: Some(order),
+            ..self
+        }
+    }
+
+    pub fn with_realization(self, realization: RealizationKind) -> Self {
+        FilterSpec {
+            realization,
+            ..self
+        }
+    }
+
+    pub fn with_method(self, method: DesignMethod) -> Self {
+        FilterSpec { method, ..self }
+    }
+
+    pub fn order(&self) -> u32 {
+        self.order.unwrap_or_else(|| self.kind.default_order())
+    }
+
+    // Checks the parameters that don't depend on the sample rate
+    pub fn validate(&self) -> Result<(), FilterError> {
+        let order = self.order();
+        let err = |message: String| Err(FilterError(message));
+
+        if !(self.frequency.is_finite() && self.frequency > 0.0) {
+            return err(format!(
+                "frequency must be positive, not {}",
+                self.frequency
+            ));
+        }
+        if !(self.q.is_finite() && self.q > 0.0) {
+            return err(format!("Q must be positive, not {}", self.q));
+        }
+        if !self.gain_db.is_finite() {
+            return err(format!("invalid gain {}", self.gain_db));
+        }
+        if self.gain_db != 0.0 && !self.kind.has_gain() {
+            return err(format!("a {} has no gain", self.kind));
+        }
+
+        let orders = match self.kind {
+            FilterKind::Lowpass | FilterKind::Highpass => 1..=MAX_ORDER,
+            FilterKind::Allpass => 1..=2,
+            _ => 2..=2,
+        };
+        if !orders.contains(&order) {
+            return err(if orders.start() == orders.end() {
+                format!("a {} can only have order {}", self.kind, orders.start())
+            } else {
+                format!(
+                    "a {} can't have order {}, only {} to {}",
+                    self.kind,
+                    order,
+                    orders.start(),
+                    orders.end()
+                )
+            });
+        }
+        if order != 2 && self.q != Self::DEFAULT_Q {
+            return err(format!(
+                "Q only applies to second order filters, order {} {}s are {}",
+                order,
+                self.kind,
+                if order == 1 { "fixed" } else { "Butterworth" }
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Checks everything, including the frequency against Nyquist and whether
+    // the chosen realization can represent the sections
+    pub fn validate_at(&self, sample_rate: f32) -> Result<(), FilterError> {
+        self.validate()?;
+
+        if self.frequency >= sample_rate / 2.0 {
+            return Err(FilterError(format!(
+                "frequency {} Hz is above Nyquist at {} Hz",
+                self.frequency, sample_rate
+            )));
+        }
+
+        if self.realization == RealizationKind::Coupled
+            && self
+                .sections(sample_rate)
+                .iter()
+                .any(|c| CoupledFormCoeffs::from_biquad(c).is_none())
+        {
+            return Err(FilterError(
+                "the coupled form can't realize real poles".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Second order sections for the spec, in processing order. Odd order
+    // lowpass and highpass filters end with a first order section.
+    pub fn sections(&self, sample_rate: f32) -> Vec<BiQuadCoeffs> {
+        if self.method != DesignMethod::Bilinear {
+            return self
+                .prototypes()
+                .iter()
+                .map(|p| p.discretize(self.method, sample_rate, self.frequency))
+                .collect();
+        }
+
+        let FilterSpec {
+            kind,
+            frequency,
+            q,
+            gain_db,
+            ..
+        } = *self;
+        let order = self.order();
+
+        match kind {
+            FilterKind::Lowpass | FilterKind::Highpass if order != 2 => {
+                let mut sections = butterworth_q(order)
+                    .map(|q| match kind {
+                        FilterKind::Lowpass => BiQuadCoeffs::lowpass(sample_rate, frequency, q),
+                        _ => BiQuadCoeffs::highpass(sample_rate, frequency, q),
+                    })
+                    .collect::<Vec<_>>();
+                if order % 2 == 1 {
+                    sections.push(match kind {
+                        FilterKind::Lowpass => {
+                            BiQuadCoeffs::first_order_lowpass(sample_rate, frequency)
+                        }
+                        _ => BiQuadCoeffs::first_order_highpass(sample_rate, frequency),
+                    });
+                }
+                sections
+            }
+            FilterKind::Lowpass => vec![BiQuadCoeffs::lowpass(sample_rate, frequency, q)],
+            FilterKind::Highpass => vec![BiQuadCoeffs::highpass(sample_rate, frequency, q)],
+            FilterKind::Bandpass => vec![BiQuadCoeffs::bandpass(sample_rate, frequency, q)],
+            FilterKind::Notch => vec![BiQuadCoeffs::notch(sample_rate, frequency, q)],
+            FilterKind::Peak => vec![BiQuadCoeffs::peaking(sample_rate, frequency, q, gain_db)],
+            FilterKind::LowShelf => {
+                vec![BiQuadCoeffs::low_shelf(sample_rate, frequency, q, gain_db)]
+            }
+            FilterKind::HighShelf => {
+                vec![BiQuadCoeffs::high_shelf(sample_rate, frequency, q, gain_db)]
+            }
+            FilterKind::Allpass if order == 2 => vec![BiQuadCoeffs::second_order_allpass(
+                sample_rate,
+                frequency,
+                q,
+            )],
+            FilterKind::Allpass => vec![BiQuadCoeffs::allpass(sample_rate, frequency)],
+        }
+    }
+
+    // The analog prototypes of the sections, in the same order
+    pub fn prototypes(&self) -> Vec<Prototype> {
+        let q = self.q as f64;
+        let gain_db = self.gain_db as f64;
+        let order = self.order();
+
+        match self.kind {
+            FilterKind::Lowpass | FilterKind::Highpass if order != 2 => {
+                let mut prototypes = butterworth_q(order)
+                    .map(|q| match self.kind {
+                        FilterKind::Lowpass => Prototype::lowpass(q as f64),
+                        _ => Prototype::highpass(q as f64),
+                    })
+                    .collect::<Vec<_>>();
+                if order % 2 == 1 {
+                    prototypes.push(match self.kind {
+                        FilterKind::Lowpass => Prototype::first_order_lowpass(),
+                        _ => Prototype::first_order_highpass(),
+                    });
+                }
+                prototypes
+            }
+            FilterKind::Lowpass => vec![Prototype::lowpass(q)],
+            FilterKind::Highpass => vec![Prototype::highpass(q)],
+            FilterKind::Bandpass => vec![Prototype::bandpass(q)],
+            FilterKind::Notch => vec![Prototype::notch(q)],
+            FilterKind::Peak => vec![Prototype::peaking(q, gain_db)],
+            FilterKind::LowShelf => vec![Prototype::low_shelf(q, gain_db)],
+            FilterKind::HighShelf => vec![Prototype::high_shelf(q, gain_db)],
+            FilterKind::Allpass if order == 2 => vec![Prototype::allpass(q)],
+            FilterKind::Allpass => vec![Prototype::first_order_allpass()],
+        }
+    }
+}
+
+// Q of each second order section of a Butterworth filter, from the angles
+// (N + 1 - 2k) pi / 2N of the complex pole pairs, measured from the negative
+// real axis
+fn butterworth_q(order: u32) -> impl Iterator<Item = f32> {
+    (1..=order / 2).map(move |k| {
+        let angle = (order + 1 - 2 * k) as f64 * std::f64::consts::PI / (2 * order) as f64;
+        (1.0 / (2.0 * angle.cos())) as f32
+    })
+}
+
+impl fmt::Display for FilterSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.kind, self.frequency, self.q)?;
+        if self.kind.has_gain() {
+            write!(f, ":{}", self.gain_db)?;
+        }
+        Ok(())
+    }
+}
+
+// Compact form used on the command line: `type:frequency[:q[:gain_db]]`,
+// e.g. `lowpass:1000`, `peak:3000:2:-6`
+impl FromStr for FilterSpec {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.trim().split(':');
+        let kind = fields.next().unwrap_or("").parse::<FilterKind>()?;
+
+        let mut number = |name: &str| -> Result<Option<f32>, FilterError> {
+            fields
+                .next()
+                .map(|field| {
+                    field.parse::<f32>().map_err(|_| {
+                        FilterError(format!("invalid {} '{}' in '{}'", name, field, s))
+                    })
+                })
+                .transpose()
+        };
+
+        let frequency = number("frequency")?
+            .ok_or_else(|| FilterError(format!("missing frequency in '{}'", s)))?;
+        let q = number("Q")?.unwrap_or(Self::DEFAULT_Q);
+        let gain_db = number("gain")?.unwrap_or(0.0);
+        if fields.next().is_some() {
+            return Err(FilterError(format!("too many fields in '{}'", s)));
+        }
+
+        let spec = FilterSpec::new(kind, frequency)
+            .with_q(q)
+            .with_gain_db(gain_db);
+        spec.validate()
+            .map_err(|e| FilterError(format!("{} in '{}'", e, s)))?;
+
+        Ok(spec)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilterError(pub String);
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for FilterError {}
+
+// Parses a chain file: one filter per line in the same form as `FilterSpec`'s
+// `FromStr`, blank lines and everything after a `#` ignored
+pub fn parse_chain(text: &str) -> Result<Vec<FilterSpec>, FilterError> {
+    let mut specs = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let spec = line
+            .parse::<FilterSpec>()
+            .map_err(|e| FilterError(format!("line {}: {}", i + 1, e)))?;
+        specs.push(spec);
+    }
+    Ok(specs)
+}
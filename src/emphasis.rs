@@ -0,0 +1,204 @@
+use std::{f64::consts::PI, fmt, str::FromStr};
+
+use crate::{analog::spectral_factor, coeffs::BiQuadCoeffs, design::FilterError};
+
+// Standard emphasis curves, given by their time constants. The de-emphasis
+// side is the one with the falling treble: RIAA playback, the FM receiver
+// and CD players. Pre-emphasis (RIAA record) is its exact inverse, so the
+// two in series are flat.
+//
+// The bilinear transform squeezes a falling first order slope into zero at
+// Nyquist, which at 44.1 kHz is already 6 dB off at 16 kHz. Instead every
+// first order stage is fitted as a biquad to the analog magnitude, see
+// `analog_matched`, which stays within a tenth of a dB up to 20 kHz from
+// 44.1 kHz up.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Emphasis {
+    // 3180, 318 and 75 us, normalized to 0 dB at 1 kHz
+    Riaa,
+    // FM broadcast in Europe and most of the world
+    Fm50,
+    // FM broadcast in the Americas and Korea
+    Fm75,
+    // 50/15 us shelf of the CD and DAT formats, 10 dB of treble
+    Cd,
+}
+
+// One stage of an emphasis curve, (1 + s zero) / (1 + s pole) with time
+// constants in seconds
+#[derive(Copy, Clone, Debug)]
+struct Stage {
+    zero: f64,
+    pole: f64,
+}
+
+impl Stage {
+    fn magnitude_squared(self, frequency: f64) -> f64 {
+        let w = 2.0 * PI * frequency;
+        (1.0 + (w * self.zero).powi(2)) / (1.0 + (w * self.pole).powi(2))
+    }
+}
+
+impl Emphasis {
+    pub const ALL: [Emphasis; 4] = [Emphasis::Riaa, Emphasis::Fm50, Emphasis::Fm75, Emphasis::Cd];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Emphasis::Riaa => "riaa",
+            Emphasis::Fm50 => "fm50",
+            Emphasis::Fm75 => "fm75",
+            Emphasis::Cd => "cd",
+        }
+    }
+
+    // The RIAA bass boost is its own stage, leaving the 75 us pole on its
+    // own as with FM
+    fn stages(self) -> &'static [Stage] {
+        match self {
+            Emphasis::Riaa => &[
+                Stage {
+                    zero: 318e-6,
+                    pole: 3180e-6,
+                },
+                Stage {
+                    zero: 0.0,
+                    pole: 75e-6,
+                },
+            ],
+            Emphasis::Fm50 => &[Stage {
+                zero: 0.0,
+                pole: 50e-6,
+            }],
+            Emphasis::Fm75 => &[Stage {
+                zero: 0.0,
+                pole: 75e-6,
+            }],
+            Emphasis::Cd => &[Stage {
+                zero: 15e-6,
+                pole: 50e-6,
+            }],
+        }
+    }
+
+    // Gain of the analog de-emphasis at DC, undoing the RIAA normalization
+    fn dc_gain(self) -> f64 {
+        match self {
+            Emphasis::Riaa => {
+                let at_1k = self.stages().iter().map(|s| s.magnitude_squared(1000.0));
+                1.0 / at_1k.product::<f64>().sqrt()
+            }
+            _ => 1.0,
+        }
+    }
+
+    // The analog de-emphasis curve in dB; pre-emphasis is its negation
+    pub fn reference_db(self, frequency: f64) -> f64 {
+        let squared = self
+            .stages()
+            .iter()
+            .map(|s| s.magnitude_squared(frequency))
+            .product::<f64>();
+        10.0 * squared.log10() + 20.0 * self.dc_gain().log10()
+    }
+
+    // RIAA playback, FM and CD de-emphasis, one section per stage
+    pub fn deemphasis(self, sample_rate: f32) -> Vec<BiQuadCoeffs> {
+        let mut sections = self
+            .stages()
+            .iter()
+            .map(|stage| analog_matched(sample_rate as f64, *stage))
+            .collect::<Vec<_>>();
+
+        let gain = self.dc_gain() as f32;
+        let c = &mut sections[0];
+        c.a0 *= gain;
+        c.a1 *= gain;
+        c.a2 *= gain;
+        sections
+    }
+
+    // RIAA record, FM and CD pre-emphasis: the de-emphasis sections turned
+    // upside down. Their zeros are inside the unit circle, so the inverse is
+    // stable.
+    pub fn preemphasis(self, sample_rate: f32) -> Vec<BiQuadCoeffs> {
+        self.deemphasis(sample_rate)
+            .iter()
+            .map(|c| {
+                let num = [c.a0 as f64, c.a1 as f64, c.a2 as f64];
+                BiQuadCoeffs::normalized_f64([1.0, c.b1 as f64, c.b2 as f64], num)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Emphasis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Emphasis {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Emphasis::ALL
+            .iter()
+            .copied()
+            .find(|emphasis| emphasis.name() == s)
+            .ok_or_else(|| FilterError(format!("unknown emphasis curve '{}'", s)))
+    }
+}
+
+// Frequencies, as fractions of the sample rate, where the fit matches the
+// analog magnitude on top of DC and Nyquist. Chosen for the smallest worst
+// case error of a first order lowpass at 44.1 and 48 kHz.
+const FIT_POINTS: [f64; 3] = [0.12, 0.33, 0.44];
+
+// The biquad whose magnitude equals the analog stage's at five frequencies.
+// With c = cos(w), the squared magnitude of a biquad is a ratio of two
+// quadratics in c, N(c) / D(c), and N(c) - |H|^2 D(c) = 0 at each frequency
+// is linear in their coefficients. The numerator and denominator are then
+// the minimum phase polynomials with those squared magnitudes.
+fn analog_matched(sample_rate: f64, stage: Stage) -> BiQuadCoeffs {
+    let mut frequencies = vec![0.0, sample_rate / 2.0];
+    frequencies.extend(FIT_POINTS.iter().map(|f| f * sample_rate));
+
+    // Unknowns n0, n1, n2, d1, d2, with d0 = 1
+    let mut system = [[0.0; 6]; 5];
+    for (row, f) in system.iter_mut().zip(frequencies.iter()) {
+        let c = (2.0 * PI * f / sample_rate).cos();
+        let t = stage.magnitude_squared(*f);
+        *row = [1.0, c, c * c, -t * c, -t * c * c, t];
+    }
+    let [n0, n1, n2, d1, d2] = solve(system);
+
+    let num = spectral_factor([n0, n1, n2]);
+    let den = spectral_factor([1.0, d1, d2]);
+    BiQuadCoeffs::normalized_f64(num, den)
+}
+
+// Gaussian elimination with partial pivoting, on rows of coefficients
+// followed by the right hand side
+fn solve(mut system: [[f64; 6]; 5]) -> [f64; 5] {
+    for i in 0..5 {
+        let pivot = (i..5)
+            .max_by(|a, b| system[*a][i].abs().total_cmp(&system[*b][i].abs()))
+            .unwrap();
+        system.swap(i, pivot);
+        for r in 0..5 {
+            if r != i {
+                let factor = system[r][i] / system[i][i];
+                let row = system[i];
+                for (x, y) in system[r].iter_mut().zip(row.iter()) {
+                    *x -= factor * y;
+                }
+            }
+        }
+    }
+
+    let mut x = [0.0; 5];
+    for (i, x) in x.iter_mut().enumerate() {
+        *x = system[i][5] / system[i][i];
+    }
+    x
+}
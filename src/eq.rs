@@ -0,0 +1,439 @@
+use crate::{
+    cascade::{Backend, Cascade},
+    coeffs::BiQuadCoeffs,
+    design::{FilterError, FilterKind, FilterSpec},
+    filter_bank::{self, Band},
+    response::{self, Complex, ResponsePoint},
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BandKind {
+    Bell,
+    LowShelf,
+    HighShelf,
+    LowCut,
+    HighCut,
+}
+
+// Steepness of the cut bands. Everything but 12 dB/octave is a Butterworth
+// cascade; 12 dB/octave is a single section, with the band's Q as resonance.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Slope {
+    Db6,
+    Db12,
+    Db18,
+    Db24,
+    Db36,
+    Db48,
+}
+
+impl Slope {
+    pub const ALL: [Slope; 6] = [
+        Slope::Db6,
+        Slope::Db12,
+        Slope::Db18,
+        Slope::Db24,
+        Slope::Db36,
+        Slope::Db48,
+    ];
+
+    pub fn order(self) -> u32 {
+        match self {
+            Slope::Db6 => 1,
+            Slope::Db12 => 2,
+            Slope::Db18 => 3,
+            Slope::Db24 => 4,
+            Slope::Db36 => 6,
+            Slope::Db48 => 8,
+        }
+    }
+
+    pub fn db_per_octave(self) -> u32 {
+        6 * self.order()
+    }
+}
+
+// One band of the equalizer. Gain only applies to bells and shelves, slope
+// only to cuts.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EqBand {
+    pub kind: BandKind,
+    pub frequency: f32,
+    pub q: f32,
+    pub gain_db: f32,
+    pub slope: Slope,
+    pub enabled: bool,
+}
+
+impl EqBand {
+    pub fn new(kind: BandKind, frequency: f32) -> Self {
+        EqBand {
+            kind,
+            frequency,
+            q: FilterSpec::DEFAULT_Q,
+            gain_db: 0.0,
+            slope: Slope::Db12,
+            enabled: true,
+        }
+    }
+
+    pub fn bell(frequency: f32, q: f32, gain_db: f32) -> Self {
+        Self::new(BandKind::Bell, frequency)
+            .with_q(q)
+            .with_gain_db(gain_db)
+    }
+
+    pub fn low_shelf(frequency: f32, gain_db: f32) -> Self {
+        Self::new(BandKind::LowShelf, frequency).with_gain_db(gain_db)
+    }
+
+    pub fn high_shelf(frequency: f32, gain_db: f32) -> Self {
+        Self::new(BandKind::HighShelf, frequency).with_gain_db(gain_db)
+    }
+
+    pub fn low_cut(frequency: f32, slope: Slope) -> Self {
+        Self::new(BandKind::LowCut, frequency).with_slope(slope)
+    }
+
+    pub fn high_cut(frequency: f32, slope: Slope) -> Self {
+        Self::new(BandKind::HighCut, frequency).with_slope(slope)
+    }
+
+    pub fn with_q(self, q: f32) -> Self {
+        EqBand { q, ..self }
+    }
+
+    pub fn with_gain_db(self, gain_db: f32) -> Self {
+        EqBand { gain_db, ..self }
+    }
+
+    pub fn with_slope(self, slope: Slope) -> Self {
+        EqBand { slope, ..self }
+    }
+
+    pub fn with_enabled(self, enabled: bool) -> Self {
+        EqBand { enabled, ..self }
+    }
+
+    // The filter the band stands for. The Q of Butterworth cuts is fixed, so
+    // it's dropped rather than rejected: the UI keeps one Q per band whatever
+    // the slope.
+    pub fn spec(&self) -> FilterSpec {
+        let spec = |kind| FilterSpec::new(kind, self.frequency).with_q(self.q);
+        match self.kind {
+            BandKind::Bell => spec(FilterKind::Peak).with_gain_db(self.gain_db),
+            BandKind::LowShelf => spec(FilterKind::LowShelf).with_gain_db(self.gain_db),
+            BandKind::HighShelf => spec(FilterKind::HighShelf).with_gain_db(self.gain_db),
+            BandKind::LowCut | BandKind::HighCut => {
+                let kind = match self.kind {
+                    BandKind::LowCut => FilterKind::Highpass,
+                    _ => FilterKind::Lowpass,
+                };
+                let order = self.slope.order();
+                let q = if order == 2 {
+                    self.q
+                } else {
+                    FilterSpec::DEFAULT_Q
+                };
+                FilterSpec::new(kind, self.frequency)
+                    .with_q(q)
+                    .with_order(order)
+            }
+        }
+    }
+
+    fn sections(&self, sample_rate: f32) -> Result<Vec<BiQuadCoeffs>, FilterError> {
+        let spec = self.spec();
+        spec.validate_at(sample_rate)?;
+        Ok(spec.sections(sample_rate))
+    }
+}
+
+struct BandState {
+    params: EqBand,
+    sections: Vec<BiQuadCoeffs>,
+    // Only enabled bands have one, disabled bands are skipped entirely
+    cascade: Option<Cascade>,
+}
+
+// A parametric equalizer with any number of bands, applied in order. Every
+// band runs as its own cascade on the chosen backend, so changing a band only
+// redesigns that band, and changes that keep the number of sections keep the
+// filter state too.
+pub struct ParametricEq {
+    backend: Backend,
+    sample_rate: f32,
+    flush_denormals: bool,
+    bands: Vec<BandState>,
+}
+
+impl ParametricEq {
+    // On the fastest backend the CPU supports
+    pub fn new(sample_rate: f32) -> Self {
+        Self::with_backend(Backend::detect(), sample_rate)
+    }
+
+    // Panics if the CPU doesn't support the backend, see
+    // `Backend::is_supported`
+    pub fn with_backend(backend: Backend, sample_rate: f32) -> Self {
+        assert!(
+            backend.is_supported(),
+            "the {} backend isn't supported by this CPU",
+            backend
+        );
+        ParametricEq {
+            backend,
+            sample_rate,
+            flush_denormals: false,
+            bands: Vec::new(),
+        }
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    pub fn len(&self) -> usize {
+        self.bands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bands.is_empty()
+    }
+
+    pub fn band(&self, index: usize) -> &EqBand {
+        &self.bands[index].params
+    }
+
+    pub fn bands(&self) -> impl Iterator<Item = &EqBand> {
+        self.bands.iter().map(|band| &band.params)
+    }
+
+    // Appends a band, returning its index
+    pub fn add_band(&mut self, params: EqBand) -> Result<usize, FilterError> {
+        let sections = params.sections(self.sample_rate)?;
+        let mut band = BandState {
+            params,
+            sections,
+            cascade: None,
+        };
+        if params.enabled {
+            band.cascade = Some(self.cascade(&band.sections));
+        }
+        self.bands.push(band);
+        Ok(self.bands.len() - 1)
+    }
+
+    pub fn remove_band(&mut self, index: usize) -> EqBand {
+        self.bands.remove(index).params
+    }
+
+    // Redesigns the band. Its state is kept as long as it stays enabled and
+    // its number of sections doesn't change, so sweeping a parameter doesn't
+    // click. On error the band is left as it was.
+    pub fn set_band(&mut self, index: usize, params: EqBand) -> Result<(), FilterError> {
+        let band = &self.bands[index];
+        if band.params == params {
+            return Ok(());
+        }
+
+        let sections = params.sections(self.sample_rate)?;
+        let cascade = match self.bands[index].cascade.take() {
+            Some(mut cascade) if params.enabled && cascade.len() == sections.len() => {
+                for (i, c) in sections.iter().enumerate() {
+                    cascade.set_coeffs(i, c);
+                }
+                Some(cascade)
+            }
+            _ if params.enabled => Some(self.cascade(&sections)),
+            _ => None,
+        };
+
+        self.bands[index] = BandState {
+            params,
+            sections,
+            cascade,
+        };
+        Ok(())
+    }
+
+    // A band that's enabled again starts from silence
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        let params = self.bands[index].params.with_enabled(enabled);
+        self.set_band(index, params)
+            .expect("the band was already valid");
+    }
+
+    // Redesigns every band for the new rate, resetting their state. Fails
+    // without changing anything if a band is above the new Nyquist frequency.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) -> Result<(), FilterError> {
+        let sections = self
+            .bands
+            .iter()
+            .map(|band| band.params.sections(sample_rate))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.sample_rate = sample_rate;
+        for (band, sections) in self.bands.iter_mut().zip(sections) {
+            band.sections = sections;
+            band.cascade = None;
+        }
+        for i in 0..self.bands.len() {
+            if self.bands[i].params.enabled {
+                self.bands[i].cascade = Some(self.cascade(&self.bands[i].sections));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_flush_denormals(&mut self, enabled: bool) {
+        self.flush_denormals = enabled;
+        for cascade in self
+            .bands
+            .iter_mut()
+            .filter_map(|band| band.cascade.as_mut())
+        {
+            cascade.set_flush_denormals(enabled);
+        }
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for cascade in self
+            .bands
+            .iter_mut()
+            .filter_map(|band| band.cascade.as_mut())
+        {
+            cascade.process(samples);
+        }
+    }
+
+    // Sections of the enabled bands, in processing order
+    pub fn sections(&self) -> Vec<BiQuadCoeffs> {
+        self.bands
+            .iter()
+            .filter(|band| band.params.enabled)
+            .flat_map(|band| band.sections.iter().copied())
+            .collect()
+    }
+
+    // Response of the whole equalizer, disabled bands excluded
+    pub fn response(&self, frequency: f32) -> Complex {
+        response::response(&self.sections(), self.sample_rate, frequency)
+    }
+
+    // The EQ curve over a frequency grid, see `response::log_frequencies`
+    pub fn frequency_table(&self, frequencies: &[f32]) -> Vec<ResponsePoint> {
+        response::frequency_table(&self.sections(), self.sample_rate, frequencies)
+    }
+
+    fn cascade(&self, sections: &[BiQuadCoeffs]) -> Cascade {
+        let mut cascade = Cascade::new(self.backend, sections);
+        cascade.set_flush_denormals(self.flush_denormals);
+        cascade
+    }
+}
+
+// How the bandwidth of a graphic EQ band follows its gain
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QMode {
+    // Every band always has the bandwidth of its slot
+    Constant,
+    // Bands get narrower as they're boosted or cut further, reaching the
+    // bandwidth of their slot at 12 dB. Small moves make broad, gentle
+    // changes; the Q never goes below a quarter of the slot's.
+    Proportional,
+}
+
+// A graphic equalizer: one bell per fractional octave band, at the ISO
+// centers. Bands at 0 dB are bypassed.
+pub struct GraphicEq {
+    bands: Vec<Band>,
+    mode: QMode,
+    gains: Vec<f32>,
+    eq: ParametricEq,
+}
+
+impl GraphicEq {
+    // On the fastest backend the CPU supports, with all bands at 0 dB
+    pub fn new(
+        sample_rate: f32,
+        fraction: u32,
+        low: f32,
+        high: f32,
+        mode: QMode,
+    ) -> Result<Self, FilterError> {
+        Self::with_backend(Backend::detect(), sample_rate, fraction, low, high, mode)
+    }
+
+    pub fn with_backend(
+        backend: Backend,
+        sample_rate: f32,
+        fraction: u32,
+        low: f32,
+        high: f32,
+        mode: QMode,
+    ) -> Result<Self, FilterError> {
+        let bands = filter_bank::bands(sample_rate, fraction, low, high)?;
+        let mut eq = ParametricEq::with_backend(backend, sample_rate);
+        for band in &bands {
+            eq.add_band(EqBand::bell(band.center, band.q(), 0.0).with_enabled(false))?;
+        }
+
+        Ok(GraphicEq {
+            gains: vec![0.0; bands.len()],
+            bands,
+            mode,
+            eq,
+        })
+    }
+
+    // The 31 band third octave equalizer, 20 Hz to 20 kHz
+    pub fn third_octave(sample_rate: f32, mode: QMode) -> Result<Self, FilterError> {
+        Self::new(sample_rate, 3, 20.0, 20000.0, mode)
+    }
+
+    pub fn bands(&self) -> &[Band] {
+        &self.bands
+    }
+
+    pub fn mode(&self) -> QMode {
+        self.mode
+    }
+
+    pub fn gain_db(&self, band: usize) -> f32 {
+        self.gains[band]
+    }
+
+    pub fn set_gain_db(&mut self, band: usize, gain_db: f32) -> Result<(), FilterError> {
+        let slot = self.bands[band].q();
+        let q = match self.mode {
+            QMode::Constant => slot,
+            QMode::Proportional => slot * (gain_db.abs() / 12.0).max(0.25),
+        };
+        let params = EqBand::bell(self.bands[band].center, q, gain_db).with_enabled(gain_db != 0.0);
+        self.eq.set_band(band, params)?;
+        self.gains[band] = gain_db;
+        Ok(())
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        self.eq.process(samples);
+    }
+
+    // The bells the bands are made of
+    pub fn eq(&self) -> &ParametricEq {
+        &self.eq
+    }
+
+    pub fn response(&self, frequency: f32) -> Complex {
+        self.eq.response(frequency)
+    }
+
+    pub fn frequency_table(&self, frequencies: &[f32]) -> Vec<ResponsePoint> {
+        self.eq.frequency_table(frequencies)
+    }
+}
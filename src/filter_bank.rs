@@ -0,0 +1,340 @@
+use std::f64::consts::PI;
+
+use wide::f32x8;
+
+use crate::{
+    biquad_lanes::BiQuadLanes8,
+    coeffs::BiQuadCoeffs,
+    design::FilterError,
+    response::{response, Complex},
+};
+
+// Fractional octave bands of ANSI S1.11 / IEC 61260, with base ten octaves:
+// band x of a 1/b octave bank is centered at 1000 Hz * G^(x/b) for odd b and
+// 1000 Hz * G^((2x+1)/2b) for even b, its edges half a band either side.
+pub const OCTAVE_RATIO: f64 = 1.995_262_314_968_879_5; // 10^(3/10)
+
+// Butterworth order of the band filters. Three meets class 1 at every
+// fraction, for bands with their upper edge below a fifth of the sample rate;
+// above that, bilinear warping widens the lower skirt too much.
+pub const DEFAULT_ORDER: u32 = 3;
+pub const MAX_ORDER: u32 = 8;
+pub const MAX_FRACTION: u32 = 24;
+
+// ISO 266 preferred numbers, the nominal octave and third octave centers
+const R10: [f32; 10] = [1.0, 1.25, 1.6, 2.0, 2.5, 3.15, 4.0, 5.0, 6.3, 8.0];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Band {
+    pub index: i32,
+    // The name of the band: ISO 266 preferred numbers for octave and third
+    // octave bands, the exact center to three significant digits otherwise
+    pub nominal: f32,
+    pub center: f32,
+    pub lower: f32,
+    pub upper: f32,
+}
+
+impl Band {
+    pub fn new(fraction: u32, index: i32) -> Self {
+        let b = fraction as f64;
+        let exponent = if fraction % 2 == 1 {
+            index as f64 / b
+        } else {
+            (2 * index + 1) as f64 / (2.0 * b)
+        };
+        let center = 1000.0 * OCTAVE_RATIO.powf(exponent);
+        let half = OCTAVE_RATIO.powf(1.0 / (2.0 * b));
+
+        let nominal = if fraction == 1 || fraction == 3 {
+            let n = (10.0 * center.log10()).round() as i32;
+            R10[n.rem_euclid(10) as usize] * 10f32.powi(n.div_euclid(10))
+        } else {
+            format!("{:.2e}", center).parse().unwrap()
+        };
+
+        Band {
+            index,
+            nominal,
+            center: center as f32,
+            lower: (center / half) as f32,
+            upper: (center * half) as f32,
+        }
+    }
+
+    // Bandwidth over center frequency
+    pub fn q(&self) -> f32 {
+        self.center / (self.upper - self.lower)
+    }
+}
+
+fn validate_fraction(fraction: u32) -> Result<(), FilterError> {
+    if !(1..=MAX_FRACTION).contains(&fraction) {
+        return Err(FilterError(format!(
+            "bands can be 1 to 1/{} octave wide, not 1/{}",
+            MAX_FRACTION, fraction
+        )));
+    }
+    Ok(())
+}
+
+// The 1/b octave bands with nominal centers from `low` to `high` Hz, leaving
+// out the ones reaching past Nyquist
+pub fn bands(
+    sample_rate: f32,
+    fraction: u32,
+    low: f32,
+    high: f32,
+) -> Result<Vec<Band>, FilterError> {
+    validate_fraction(fraction)?;
+    if !(low > 0.0 && low <= high) {
+        return Err(FilterError(format!(
+            "invalid band range {} to {} Hz",
+            low, high
+        )));
+    }
+
+    // A band either side of the range to be safe about rounding, the nominal
+    // frequencies decide
+    let position = |f: f32| (f as f64 / 1000.0).log(OCTAVE_RATIO) * fraction as f64;
+    let first = position(low).floor() as i32 - 1;
+    let last = position(high).ceil() as i32 + 1;
+
+    let bands = (first..=last)
+        .map(|index| Band::new(fraction, index))
+        .filter(|band| band.nominal >= low && band.nominal <= high)
+        .filter(|band| band.upper < sample_rate / 2.0)
+        .collect::<Vec<_>>();
+    if bands.is_empty() {
+        return Err(FilterError(format!(
+            "no 1/{} octave bands between {} and {} Hz",
+            fraction, low, high
+        )));
+    }
+    Ok(bands)
+}
+
+// Butterworth bandpass from `lower` to `upper` Hz, -3 dB at both and 0 dB at
+// their geometric mean. The lowpass prototype of the given order is turned
+// into a bandpass of twice that order in the analog domain, with prewarped
+// edges, and each pair of poles becomes a section with zeros at DC and
+// Nyquist: a complex prototype pole gives two conjugate pairs, and the real
+// one of an odd order gives two poles that are real themselves when the band
+// is wide enough.
+pub fn bandpass(sample_rate: f32, lower: f32, upper: f32, order: u32) -> Vec<BiQuadCoeffs> {
+    let prewarp = |f: f32| (PI * f as f64 / sample_rate as f64).tan();
+    let (w1, w2) = (prewarp(lower), prewarp(upper));
+    let bandwidth = w2 - w1;
+    let center = w1 * w2;
+
+    // The two bandpass poles of a lowpass prototype pole
+    let split = |p: Complex| {
+        let root = (p * p - Complex::new(center, 0.0)).sqrt();
+        (p + root, p - root)
+    };
+
+    let n = order as f64;
+    let mut pairs = (0..order)
+        .filter(|k| 2 * k + 1 < order)
+        .flat_map(|k| {
+            // Prototype poles above the real axis, their conjugates below
+            // give the other half of each pair
+            let angle = PI * (2.0 * k as f64 + n + 1.0) / (2.0 * n);
+            let (s1, s2) = split(Complex::from_polar(bandwidth / 2.0, angle));
+            vec![(s1, s1.conj()), (s2, s2.conj())]
+        })
+        .collect::<Vec<_>>();
+    if order % 2 == 1 {
+        pairs.push(split(Complex::new(-bandwidth / 2.0, 0.0)));
+    }
+
+    let mut sections = pairs
+        .iter()
+        .map(|(s1, s2)| {
+            // Bilinear transform, z = (1 + s) / (1 - s)
+            let z = |s: Complex| (Complex::ONE + s) / (Complex::ONE - s);
+            let (z1, z2) = (z(*s1), z(*s2));
+            BiQuadCoeffs::normalized_f64([1.0, 0.0, -1.0], [1.0, -(z1 + z2).re, (z1 * z2).re])
+        })
+        .collect::<Vec<_>>();
+
+    // Unity gain at the center, spread evenly over the sections
+    let digital_center = (center.sqrt().atan() / PI) as f32 * sample_rate;
+    let gain = response(&sections, sample_rate, digital_center).norm();
+    let scale = gain.powf(-1.0 / sections.len() as f64) as f32;
+    for c in &mut sections {
+        c.a0 *= scale;
+        c.a1 *= scale;
+        c.a2 *= scale;
+    }
+    sections
+}
+
+// A bank of fractional octave bandpass filters, all fed the same input, that
+// measures the energy in every band. Bands run eight at a time on the lanes
+// of `BiQuadLanes8`, so a 31 band third octave analyzer costs four filters'
+// worth of vector work per section.
+pub struct FilterBank {
+    sample_rate: f32,
+    fraction: u32,
+    bands: Vec<Band>,
+    sections: Vec<Vec<BiQuadCoeffs>>,
+    // Per group of eight bands, one kernel per section
+    groups: Vec<Vec<BiQuadLanes8>>,
+    energy: Vec<f64>,
+    samples: u64,
+}
+
+impl FilterBank {
+    pub fn new(
+        sample_rate: f32,
+        fraction: u32,
+        low: f32,
+        high: f32,
+        order: u32,
+    ) -> Result<Self, FilterError> {
+        if !(1..=MAX_ORDER).contains(&order) {
+            return Err(FilterError(format!(
+                "band filters can have order 1 to {}, not {}",
+                MAX_ORDER, order
+            )));
+        }
+
+        let bands = bands(sample_rate, fraction, low, high)?;
+        let sections = bands
+            .iter()
+            .map(|band| bandpass(sample_rate, band.lower, band.upper, order))
+            .collect::<Vec<_>>();
+
+        let groups = sections
+            .chunks(BiQuadLanes8::LANES)
+            .map(|group| {
+                let stages = group.iter().map(Vec::len).max().unwrap_or(0);
+                (0..stages)
+                    .map(|stage| {
+                        let mut kernel = BiQuadLanes8::new();
+                        for (lane, band) in group.iter().enumerate() {
+                            if let Some(coeffs) = band.get(stage) {
+                                kernel.set_coeffs(lane, coeffs);
+                            }
+                        }
+                        kernel
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(FilterBank {
+            sample_rate,
+            fraction,
+            energy: vec![0.0; bands.len()],
+            bands,
+            sections,
+            groups,
+            samples: 0,
+        })
+    }
+
+    // ISO octave bands from 31.5 Hz to 16 kHz
+    pub fn octave(sample_rate: f32) -> Result<Self, FilterError> {
+        Self::new(sample_rate, 1, 31.5, 16000.0, DEFAULT_ORDER)
+    }
+
+    // ISO third octave bands from 20 Hz to 20 kHz
+    pub fn third_octave(sample_rate: f32) -> Result<Self, FilterError> {
+        Self::new(sample_rate, 3, 20.0, 20000.0, DEFAULT_ORDER)
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    pub fn fraction(&self) -> u32 {
+        self.fraction
+    }
+
+    pub fn bands(&self) -> &[Band] {
+        &self.bands
+    }
+
+    pub fn band_sections(&self, band: usize) -> &[BiQuadCoeffs] {
+        &self.sections[band]
+    }
+
+    // Adds the input to the band energies
+    pub fn process(&mut self, input: &[f32]) {
+        self.run(input, |_, _, _| {});
+    }
+
+    // Same as `process`, also writing the band signals to the outputs, which
+    // must all be as long as the input
+    pub fn process_bands(&mut self, input: &[f32], outputs: &mut [&mut [f32]]) {
+        assert_eq!(outputs.len(), self.bands.len(), "wrong number of bands");
+        assert!(
+            outputs.iter().all(|band| band.len() == input.len()),
+            "band outputs must be as long as the input"
+        );
+        self.run(input, |group, n, y| {
+            let lanes = y.to_array();
+            let bands = &mut outputs[group * BiQuadLanes8::LANES..];
+            for (band, y) in bands.iter_mut().zip(lanes.iter()) {
+                band[n] = *y;
+            }
+        });
+    }
+
+    fn run(&mut self, input: &[f32], mut output: impl FnMut(usize, usize, f32x8)) {
+        for (group, kernels) in self.groups.iter_mut().enumerate() {
+            // Summed in f32 over short runs only, then in double precision
+            let mut total = [0.0f64; BiQuadLanes8::LANES];
+            for (chunk, samples) in input.chunks(1024).enumerate() {
+                let mut sum = f32x8::ZERO;
+                for (i, x) in samples.iter().enumerate() {
+                    let mut y = f32x8::splat(*x);
+                    for kernel in kernels.iter_mut() {
+                        y = kernel.process(y);
+                    }
+                    sum = y.mul_add(y, sum);
+                    output(group, chunk * 1024 + i, y);
+                }
+                for (total, sum) in total.iter_mut().zip(sum.to_array().iter()) {
+                    *total += *sum as f64;
+                }
+            }
+
+            let energy = &mut self.energy[group * BiQuadLanes8::LANES..];
+            for (energy, total) in energy.iter_mut().zip(total.iter()) {
+                *energy += total;
+            }
+        }
+        self.samples += input.len() as u64;
+    }
+
+    // Mean square of every band since the last reset
+    pub fn mean_square(&self) -> Vec<f64> {
+        let samples = self.samples.max(1) as f64;
+        self.energy.iter().map(|e| e / samples).collect()
+    }
+
+    // Mean square in dB relative to full scale, where a full scale sine in
+    // the band is at -3 dB
+    pub fn levels_db(&self) -> Vec<f64> {
+        self.mean_square()
+            .iter()
+            .map(|ms| 10.0 * ms.log10())
+            .collect()
+    }
+
+    pub fn reset_energy(&mut self) {
+        self.energy.iter_mut().for_each(|e| *e = 0.0);
+        self.samples = 0;
+    }
+
+    // Clears the filter state and the energies
+    pub fn reset(&mut self) {
+        for kernel in self.groups.iter_mut().flatten() {
+            kernel.reset();
+        }
+        self.reset_energy();
+    }
+}
@@ -0,0 +1,306 @@
+use std::{fmt, str::FromStr};
+
+use wide::f32x4;
+
+use crate::{design::FilterError, polyphase};
+
+// Oversampling by powers of two, one halfband stage per doubling
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OversamplingFactor {
+    X2,
+    X4,
+    X8,
+}
+
+impl OversamplingFactor {
+    pub const ALL: [OversamplingFactor; 3] = [
+        OversamplingFactor::X2,
+        OversamplingFactor::X4,
+        OversamplingFactor::X8,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OversamplingFactor::X2 => "2x",
+            OversamplingFactor::X4 => "4x",
+            OversamplingFactor::X8 => "8x",
+        }
+    }
+
+    pub fn factor(self) -> usize {
+        1 << self.stages()
+    }
+
+    pub fn stages(self) -> usize {
+        match self {
+            OversamplingFactor::X2 => 1,
+            OversamplingFactor::X4 => 2,
+            OversamplingFactor::X8 => 3,
+        }
+    }
+}
+
+impl fmt::Display for OversamplingFactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for OversamplingFactor {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        OversamplingFactor::ALL
+            .iter()
+            .copied()
+            .find(|factor| factor.name() == s)
+            .ok_or_else(|| FilterError(format!("unknown oversampling factor '{}'", s)))
+    }
+}
+
+// Coefficients of each stage, from the one at twice the base rate up. The
+// transition band is a fraction of the base rate centered on its Nyquist,
+// and every stage keeps the band below it and rejects whatever would alias
+// into it. That only takes the full transition band at the first stage: at
+// 4x and up the stopband starts far above the base Nyquist, and the stages
+// get cheaper the higher they run.
+fn design(
+    factor: OversamplingFactor,
+    attenuation_db: f64,
+    transition: f64,
+) -> Result<Vec<Vec<f64>>, FilterError> {
+    if !(transition > 0.0 && transition < 1.0) {
+        return Err(FilterError(format!(
+            "the transition band is between 0 and the whole base sample rate, not {}",
+            transition
+        )));
+    }
+    let passband = 0.5 - transition / 2.0;
+    (1..=factor.stages())
+        .map(|stage| {
+            let transition = 0.25 - passband / (1 << stage) as f64;
+            let count = polyphase::count_for(attenuation_db, transition)?;
+            polyphase::coefficients(count + count % 2, transition)
+        })
+        .collect()
+}
+
+// One halfband of `polyphase` at the lower of its two rates, where each
+// path is a cascade of first order allpasses (a + z^-1) / (1 + a z^-1). The
+// two paths are independent and run side by side on the first two lanes of
+// a vector, one section of each per step. Coefficient counts are rounded up
+// to even so both paths have as many sections.
+struct Halfband {
+    coefficients: Vec<f64>,
+    stages: Vec<f32x4>,
+    x1: Vec<f32x4>,
+    y1: Vec<f32x4>,
+}
+
+impl Halfband {
+    fn new(coefficients: Vec<f64>) -> Self {
+        let stages = coefficients
+            .chunks_exact(2)
+            .map(|a| f32x4::from([a[0] as f32, a[1] as f32, 0.0, 0.0]))
+            .collect::<Vec<_>>();
+        Halfband {
+            coefficients,
+            x1: vec![f32x4::ZERO; stages.len()],
+            y1: vec![f32x4::ZERO; stages.len()],
+            stages,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.x1.iter_mut().for_each(|x| *x = f32x4::ZERO);
+        self.y1.iter_mut().for_each(|y| *y = f32x4::ZERO);
+    }
+
+    // Both paths one sample on, path 0 in lane 0 and path 1 in lane 1
+    fn step(&mut self, mut v: f32x4) -> [f32; 4] {
+        for ((a, x1), y1) in self.stages.iter().zip(&mut self.x1).zip(&mut self.y1) {
+            let y = a.mul_add(v - *y1, *x1);
+            *x1 = v;
+            *y1 = y;
+            v = y;
+        }
+        v.to_array()
+    }
+}
+
+// Upsampling by two: the zero stuffed input through twice the halfband,
+// which is path 0 making the even output samples and path 1 the odd ones
+struct Up2 {
+    halfband: Halfband,
+}
+
+impl Up2 {
+    fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        for (x, out) in input.iter().zip(output.chunks_exact_mut(2)) {
+            let y = self.halfband.step(f32x4::splat(*x));
+            out[0] = y[0];
+            out[1] = y[1];
+        }
+    }
+}
+
+// Downsampling by two: the halfband computed only at the even samples, path
+// 0 taking the even input samples and path 1 the odd ones, a sample late
+struct Down2 {
+    halfband: Halfband,
+    delayed: f32,
+}
+
+impl Down2 {
+    fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        for (x, out) in input.chunks_exact(2).zip(output.iter_mut()) {
+            let y = self
+                .halfband
+                .step(f32x4::from([x[0], self.delayed, 0.0, 0.0]));
+            self.delayed = x[1];
+            *out = 0.5 * (y[0] + y[1]);
+        }
+    }
+}
+
+// Upsampling by 2, 4 or 8 with a cascade of IIR halfbands. Images of the
+// band below the base Nyquist are attenuated by at least `attenuation_db`
+// outside the transition band, a fraction of the base sample rate centered
+// on its Nyquist. The phase isn't linear: halfbands of allpasses are the
+// elliptic lowpass, with its delay rising towards the band edge.
+pub struct HalfbandUpsampler {
+    factor: OversamplingFactor,
+    stages: Vec<Up2>,
+    buffers: Vec<Vec<f32>>,
+}
+
+impl HalfbandUpsampler {
+    pub fn new(
+        factor: OversamplingFactor,
+        attenuation_db: f64,
+        transition: f64,
+    ) -> Result<Self, FilterError> {
+        let stages = design(factor, attenuation_db, transition)?
+            .into_iter()
+            .map(|coefficients| Up2 {
+                halfband: Halfband::new(coefficients),
+            })
+            .collect::<Vec<_>>();
+        Ok(HalfbandUpsampler {
+            factor,
+            buffers: vec![Vec::new(); stages.len() - 1],
+            stages,
+        })
+    }
+
+    pub fn factor(&self) -> OversamplingFactor {
+        self.factor
+    }
+
+    // Halfband coefficients of each stage, from the base rate up
+    pub fn coefficients(&self) -> Vec<&[f64]> {
+        self.stages
+            .iter()
+            .map(|s| s.halfband.coefficients.as_slice())
+            .collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.stages.iter_mut().for_each(|s| s.halfband.reset());
+    }
+
+    // The output is `factor` times as long as the input
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        assert_eq!(
+            output.len(),
+            input.len() * self.factor.factor(),
+            "output length mismatch"
+        );
+        let last = self.stages.len() - 1;
+        for (i, stage) in self.stages.iter_mut().enumerate() {
+            let len = input.len() << (i + 1);
+            let (before, after) = self.buffers.split_at_mut(i);
+            let from = if i == 0 { input } else { &before[i - 1][..] };
+            if i == last {
+                stage.process(from, output);
+            } else {
+                after[0].resize(len, 0.0);
+                stage.process(from, &mut after[0]);
+            }
+        }
+    }
+}
+
+// Downsampling by 2, 4 or 8 with a cascade of IIR halfbands, the
+// counterpart of `HalfbandUpsampler`: everything that would alias into the
+// band below the base Nyquist, outside the transition band, is attenuated
+// by at least `attenuation_db`
+pub struct HalfbandDownsampler {
+    factor: OversamplingFactor,
+    // From the highest rate down
+    stages: Vec<Down2>,
+    buffers: Vec<Vec<f32>>,
+}
+
+impl HalfbandDownsampler {
+    pub fn new(
+        factor: OversamplingFactor,
+        attenuation_db: f64,
+        transition: f64,
+    ) -> Result<Self, FilterError> {
+        let stages = design(factor, attenuation_db, transition)?
+            .into_iter()
+            .rev()
+            .map(|coefficients| Down2 {
+                halfband: Halfband::new(coefficients),
+                delayed: 0.0,
+            })
+            .collect::<Vec<_>>();
+        Ok(HalfbandDownsampler {
+            factor,
+            buffers: vec![Vec::new(); stages.len() - 1],
+            stages,
+        })
+    }
+
+    pub fn factor(&self) -> OversamplingFactor {
+        self.factor
+    }
+
+    // Halfband coefficients of each stage, from the base rate up
+    pub fn coefficients(&self) -> Vec<&[f64]> {
+        self.stages
+            .iter()
+            .rev()
+            .map(|s| s.halfband.coefficients.as_slice())
+            .collect()
+    }
+
+    pub fn reset(&mut self) {
+        for stage in &mut self.stages {
+            stage.halfband.reset();
+            stage.delayed = 0.0;
+        }
+    }
+
+    // The input is `factor` times as long as the output
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        assert_eq!(
+            input.len(),
+            output.len() * self.factor.factor(),
+            "input length mismatch"
+        );
+        let last = self.stages.len() - 1;
+        for (i, stage) in self.stages.iter_mut().enumerate() {
+            let len = input.len() >> (i + 1);
+            let (before, after) = self.buffers.split_at_mut(i);
+            let from = if i == 0 { input } else { &before[i - 1][..] };
+            if i == last {
+                stage.process(from, output);
+            } else {
+                after[0].resize(len, 0.0);
+                stage.process(from, &mut after[0]);
+            }
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use wide::{f32x4, f32x8};
+
+pub const MAX_ORDER: usize = 8;
+
+pub struct IirBlockFilter {
+    order: usize,
+
+    // Normalized direct form coefficients: numerator[0..=order] and
+    // denominator[1..=order], with the leading denominator term divided out
+    numerator: [f32; MAX_ORDER + 1],
+    denominator: [f32; MAX_ORDER + 1],
+
+    c4_x: [f32x4; 4],
+    c4_xm: [f32x4; MAX_ORDER],
+    c4_ym: [f32x4; MAX_ORDER],
+
+    c8_x: [f32x8; 8],
+    c8_xm: [f32x8; MAX_ORDER],
+    c8_ym: [f32x8; MAX_ORDER],
+
+    // x[n-1], x[n-2], ... and y[n-1], y[n-2], ...
+    xm: [f32; MAX_ORDER],
+    ym: [f32; MAX_ORDER],
+}
+
+impl IirBlockFilter {
+    pub fn new(numerator: &[f32], denominator: &[f32]) -> Self {
+        let mut f = IirBlockFilter {
+            order: 0,
+            numerator: [0.0; MAX_ORDER + 1],
+            denominator: [0.0; MAX_ORDER + 1],
+            c4_x: [f32x4::ZERO; 4],
+            c4_xm: [f32x4::ZERO; MAX_ORDER],
+            c4_ym: [f32x4::ZERO; MAX_ORDER],
+            c8_x: [f32x8::ZERO; 8],
+            c8_xm: [f32x8::ZERO; MAX_ORDER],
+            c8_ym: [f32x8::ZERO; MAX_ORDER],
+            xm: [0.0; MAX_ORDER],
+            ym: [0.0; MAX_ORDER],
+        };
+        f.update(numerator, denominator);
+        f
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    pub fn update(&mut self, numerator: &[f32], denominator: &[f32]) {
+        assert!(
+            !numerator.is_empty() && numerator.len() <= MAX_ORDER + 1,
+            "numerator must have between 1 and {} coefficients",
+            MAX_ORDER + 1
+        );
+        assert!(
+            !denominator.is_empty() && denominator.len() <= MAX_ORDER + 1,
+            "denominator must have between 1 and {} coefficients",
+            MAX_ORDER + 1
+        );
+        assert!(
+            denominator[0] != 0.0,
+            "leading denominator coefficient must be non-zero"
+        );
+
+        let d0 = denominator[0] as f64;
+        let mut num = [0.0f64; MAX_ORDER + 1];
+        let mut den = [0.0f64; MAX_ORDER + 1];
+        for (n, c) in num.iter_mut().zip(numerator.iter()) {
+            *n = *c as f64 / d0;
+        }
+        for (d, c) in den.iter_mut().zip(denominator.iter()) {
+            *d = *c as f64 / d0;
+        }
+
+        self.order = (numerator.len() - 1).max(denominator.len() - 1);
+        for i in 0..=MAX_ORDER {
+            self.numerator[i] = num[i] as f32;
+            self.denominator[i] = den[i] as f32;
+        }
+
+        let m4 = block_matrix::<4>(&num, &den, self.order);
+        for (c, col) in self.c4_x.iter_mut().zip(m4.x.iter()) {
+            *c = f32x4::from(to_f32(col));
+        }
+        for j in 0..MAX_ORDER {
+            self.c4_xm[j] = f32x4::from(to_f32(&m4.xm[j]));
+            self.c4_ym[j] = f32x4::from(to_f32(&m4.ym[j]));
+        }
+
+        let m8 = block_matrix::<8>(&num, &den, self.order);
+        for (c, col) in self.c8_x.iter_mut().zip(m8.x.iter()) {
+            *c = f32x8::from(to_f32(col));
+        }
+        for j in 0..MAX_ORDER {
+            self.c8_xm[j] = f32x8::from(to_f32(&m8.xm[j]));
+            self.c8_ym[j] = f32x8::from(to_f32(&m8.ym[j]));
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.xm = [0.0; MAX_ORDER];
+        self.ym = [0.0; MAX_ORDER];
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let order = self.order;
+
+        let mut y = self.numerator[0] * input;
+        for j in 0..order {
+            y += self.numerator[j + 1] * self.xm[j] - self.denominator[j + 1] * self.ym[j];
+        }
+
+        self.push_history(&[input], &[y]);
+
+        y
+    }
+
+    pub fn process4(&mut self, input: f32x4) -> f32x4 {
+        let x = input.to_array();
+        let order = self.order;
+
+        let mut y = f32x4::ZERO;
+        for (c, x) in self.c4_x.iter().zip(x.iter()) {
+            y = c.mul_add(f32x4::splat(*x), y);
+        }
+        for j in 0..order {
+            y = self.c4_xm[j].mul_add(f32x4::splat(self.xm[j]), y);
+            y = self.c4_ym[j].mul_add(f32x4::splat(self.ym[j]), y);
+        }
+
+        self.push_history(&x, &y.to_array());
+
+        y
+    }
+
+    pub fn process8(&mut self, input: f32x8) -> f32x8 {
+        let x = input.to_array();
+        let order = self.order;
+
+        // Two accumulators to shorten the dependency chain, same as the AVX kernel
+        let mut y1 = f32x8::ZERO;
+        let mut y2 = f32x8::ZERO;
+        for k in (0..8).step_by(2) {
+            y1 = self.c8_x[k].mul_add(f32x8::splat(x[k]), y1);
+            y2 = self.c8_x[k + 1].mul_add(f32x8::splat(x[k + 1]), y2);
+        }
+        for j in 0..order {
+            y1 = self.c8_xm[j].mul_add(f32x8::splat(self.xm[j]), y1);
+            y2 = self.c8_ym[j].mul_add(f32x8::splat(self.ym[j]), y2);
+        }
+
+        let y = y1 + y2;
+
+        self.push_history(&x, &y.to_array());
+
+        y
+    }
+
+    // Shift a block of new samples (oldest first) into the history buffers
+    fn push_history(&mut self, x: &[f32], y: &[f32]) {
+        let len = x.len();
+        if len >= MAX_ORDER {
+            for j in 0..MAX_ORDER {
+                self.xm[j] = x[len - 1 - j];
+                self.ym[j] = y[len - 1 - j];
+            }
+        } else {
+            self.xm.copy_within(0..MAX_ORDER - len, len);
+            self.ym.copy_within(0..MAX_ORDER - len, len);
+            for j in 0..len {
+                self.xm[j] = x[len - 1 - j];
+                self.ym[j] = y[len - 1 - j];
+            }
+        }
+    }
+}
+
+// Block coefficient matrix in column form. `x[k]` holds the coefficients
+// applied to x[n+k] for each of the output rows y[n]..y[n+ROWS-1], `xm[j]`
+// and `ym[j]` the ones applied to x[n-1-j] and y[n-1-j].
+pub(crate) struct BlockMatrix<const ROWS: usize> {
+    pub x: [[f64; ROWS]; ROWS],
+    pub xm: [[f64; ROWS]; MAX_ORDER],
+    pub ym: [[f64; ROWS]; MAX_ORDER],
+}
+
+// Generalization of the hand-written matrices in `biquad_sse2` and
+// `biquad_avx`: fill in the direct form for each row, then substitute the
+// rows computed earlier in the block for y[n+r-k].
+pub(crate) fn block_matrix<const ROWS: usize>(
+    num: &[f64; MAX_ORDER + 1],
+    den: &[f64; MAX_ORDER + 1],
+    order: usize,
+) -> BlockMatrix<ROWS> {
+    let mut x = [[0.0f64; ROWS]; ROWS];
+    let mut xm = [[0.0f64; ROWS]; MAX_ORDER];
+    let mut ym = [[0.0f64; ROWS]; MAX_ORDER];
+
+    for row in 0..ROWS {
+        for k in 0..=order {
+            if k <= row {
+                x[row - k][row] += num[k];
+            } else {
+                xm[k - row - 1][row] += num[k];
+            }
+        }
+
+        for k in 1..=order {
+            if k <= row {
+                let src = row - k;
+                for col in x.iter_mut().chain(xm.iter_mut()).chain(ym.iter_mut()) {
+                    col[row] -= den[k] * col[src];
+                }
+            } else {
+                ym[k - row - 1][row] -= den[k];
+            }
+        }
+    }
+
+    BlockMatrix { x, xm, ym }
+}
+
+pub(crate) fn to_f32<const N: usize>(col: &[f64; N]) -> [f32; N] {
+    let mut out = [0.0f32; N];
+    for (o, c) in out.iter_mut().zip(col.iter()) {
+        *o = *c as f32;
+    }
+    out
+}
@@ -1,27 +1,38 @@
+pub mod accuracy;
+pub mod allpass;
+pub mod analog;
+#[cfg(target_arch = "x86_64")]
+pub mod biquad_avx;
+pub mod biquad_coupled;
+pub mod biquad_error_feedback;
 pub mod biquad_f32;
+pub mod biquad_forms;
+pub mod biquad_lanes;
+pub mod biquad_lattice;
+pub mod biquad_portable;
+#[cfg(target_arch = "x86_64")]
 pub mod biquad_sse2;
-pub mod biquad_avx;
-
-pub struct ScopedFlushDenormals {
-    _hidden: (),
-}
-
-impl ScopedFlushDenormals {
-    pub fn new() -> Self {
-        use std::arch::x86_64;
-        unsafe {
-            x86_64::_MM_SET_FLUSH_ZERO_MODE(x86_64::_MM_FLUSH_ZERO_ON);
-        }
-
-        ScopedFlushDenormals { _hidden: () }
-    }
-}
+pub mod cascade;
+pub mod coeffs;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod crossover;
+pub mod decimation;
+pub mod denormals;
+pub mod design;
+pub mod emphasis;
+pub mod eq;
+pub mod filter_bank;
+pub mod halfband;
+pub mod hilbert;
+pub mod iir_block;
+pub mod loudness;
+#[cfg(feature = "plot")]
+pub mod plot;
+pub mod polyphase;
+pub mod response;
+pub mod svf_f32;
+pub mod svf_portable;
+pub mod weighting;
 
-impl Drop for ScopedFlushDenormals {
-    fn drop(&mut self) {
-        use std::arch::x86_64;
-        unsafe {
-            x86_64::_MM_SET_FLUSH_ZERO_MODE(x86_64::_MM_FLUSH_ZERO_OFF);
-        }
-    }
-}
+pub use denormals::ScopedFlushDenormals;
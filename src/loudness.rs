@@ -0,0 +1,178 @@
+use wide::f32x8;
+
+use crate::{biquad_lanes::BiQuadLanes8, design::FilterError, weighting::Weighting};
+
+// ITU-R BS.1770 loudness: K-weighted mean square over 400 ms blocks
+// overlapping by 75%, with an absolute gate at -70 LUFS and a relative gate
+// 10 LU below the loudness of the blocks passing the first.
+pub const BLOCK_SECONDS: f64 = 0.4;
+pub const BLOCKS_PER_WINDOW: usize = 4;
+pub const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+pub const RELATIVE_GATE_LU: f64 = -10.0;
+
+// Channels processed side by side on the lanes of the K-weighting filters
+pub const MAX_CHANNELS: usize = BiQuadLanes8::LANES;
+
+// Weight of the left and right surround channels; front channels have 1 and
+// the LFE channel is left out with 0
+pub const SURROUND_WEIGHT: f32 = 1.41;
+
+// Loudness of a weighted sum of mean squares, the offset makes a 0 dBFS
+// 997 Hz sine in one front channel read -3.01 LUFS
+fn lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+// Integrated loudness meter for up to eight channels. Energy is kept per
+// 100 ms step, the stride of the gating blocks, which is all integrated
+// loudness needs: an hour of audio is 36000 numbers.
+pub struct LoudnessMeter {
+    sample_rate: f32,
+    channels: usize,
+    weights: [f32; MAX_CHANNELS],
+    filters: Vec<BiQuadLanes8>,
+    step_len: usize,
+    // The step being filled, per channel
+    energy: [f64; MAX_CHANNELS],
+    filled: usize,
+    // Weighted mean square of every completed step
+    steps: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: f32, channels: usize) -> Result<Self, FilterError> {
+        if !(1..=MAX_CHANNELS).contains(&channels) {
+            return Err(FilterError(format!(
+                "a loudness meter has 1 to {} channels, not {}",
+                MAX_CHANNELS, channels
+            )));
+        }
+        let step_len = (sample_rate as f64 * BLOCK_SECONDS / BLOCKS_PER_WINDOW as f64).round();
+        if step_len.is_nan() || step_len < 1.0 {
+            return Err(FilterError(format!(
+                "invalid sample rate {} Hz",
+                sample_rate
+            )));
+        }
+
+        let filters = Weighting::K
+            .sections(sample_rate)
+            .iter()
+            .map(|c| {
+                let mut filter = BiQuadLanes8::new();
+                for lane in 0..channels {
+                    filter.set_coeffs(lane, c);
+                }
+                filter
+            })
+            .collect();
+
+        let mut weights = [0.0; MAX_CHANNELS];
+        weights[..channels].iter_mut().for_each(|w| *w = 1.0);
+        Ok(LoudnessMeter {
+            sample_rate,
+            channels,
+            weights,
+            filters,
+            step_len: step_len as usize,
+            energy: [0.0; MAX_CHANNELS],
+            filled: 0,
+            steps: Vec::new(),
+        })
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn weight(&self, channel: usize) -> f32 {
+        assert!(channel < self.channels, "no channel {}", channel);
+        self.weights[channel]
+    }
+
+    // Applies to energy from now on, normally set before the first sample
+    pub fn set_weight(&mut self, channel: usize, weight: f32) {
+        assert!(channel < self.channels, "no channel {}", channel);
+        self.weights[channel] = weight;
+    }
+
+    // Takes one slice per channel, all of the same length
+    pub fn process(&mut self, channels: &[&[f32]]) {
+        assert_eq!(channels.len(), self.channels, "wrong number of channels");
+        let len = channels[0].len();
+        assert!(
+            channels.iter().all(|c| c.len() == len),
+            "channels must all be the same length"
+        );
+
+        let mut frame = [0.0f32; MAX_CHANNELS];
+        for n in 0..len {
+            for (x, channel) in frame.iter_mut().zip(channels.iter()) {
+                *x = channel[n];
+            }
+            let mut y = f32x8::from(frame);
+            for filter in &mut self.filters {
+                y = filter.process(y);
+            }
+            for (energy, y) in self.energy.iter_mut().zip(y.to_array().iter()) {
+                *energy += (*y as f64) * (*y as f64);
+            }
+
+            self.filled += 1;
+            if self.filled == self.step_len {
+                let power = self
+                    .energy
+                    .iter()
+                    .zip(self.weights.iter())
+                    .map(|(e, w)| e * *w as f64)
+                    .sum::<f64>();
+                self.steps.push(power / self.step_len as f64);
+                self.energy = [0.0; MAX_CHANNELS];
+                self.filled = 0;
+            }
+        }
+    }
+
+    // Weighted mean square of every gating block so far
+    fn blocks(&self) -> impl Iterator<Item = f64> + '_ {
+        self.steps
+            .windows(BLOCKS_PER_WINDOW)
+            .map(|w| w.iter().sum::<f64>() / BLOCKS_PER_WINDOW as f64)
+    }
+
+    // Loudness of the last 400 ms, None before the first full block
+    pub fn momentary(&self) -> Option<f64> {
+        self.blocks().last().map(lufs)
+    }
+
+    // Gated loudness of everything so far, None if no block passes the gates
+    pub fn integrated(&self) -> Option<f64> {
+        let gated_mean = |threshold: f64| {
+            let (sum, count) = self
+                .blocks()
+                .filter(|power| lufs(*power) > threshold)
+                .fold((0.0, 0), |(sum, count), power| (sum + power, count + 1));
+            if count == 0 {
+                None
+            } else {
+                Some(sum / count as f64)
+            }
+        };
+
+        let relative = lufs(gated_mean(ABSOLUTE_GATE_LUFS)?) + RELATIVE_GATE_LU;
+        gated_mean(relative.max(ABSOLUTE_GATE_LUFS)).map(lufs)
+    }
+
+    pub fn reset(&mut self) {
+        for filter in &mut self.filters {
+            filter.reset();
+        }
+        self.energy = [0.0; MAX_CHANNELS];
+        self.filled = 0;
+        self.steps.clear();
+    }
+}
@@ -0,0 +1,500 @@
+use std::fmt::Write;
+
+use crate::{
+    coeffs::BiQuadCoeffs,
+    response::{frequency_table, poles_zeros, Complex},
+};
+
+// Response plots rendered straight to SVG documents, for CI artifacts and
+// reviews where there's no gnuplot around. Every function returns the whole
+// document as a string, ready to be written to a `.svg` file.
+
+const WIDTH: f64 = 800.0;
+const PANEL_HEIGHT: f64 = 320.0;
+const MARGIN_LEFT: f64 = 70.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const MARGIN_TOP: f64 = 40.0;
+const MARGIN_BOTTOM: f64 = 50.0;
+
+const COLORS: [&str; 4] = ["#1f77b4", "#d62728", "#2ca02c", "#9467bd"];
+
+// Magnitude in dB above phase in degrees, over the given frequencies (which
+// should be log spaced, see `response::log_frequencies`)
+pub fn bode(sections: &[BiQuadCoeffs], sample_rate: f32, frequencies: &[f32]) -> String {
+    let table = frequency_table(sections, sample_rate, frequencies);
+    let x_axis = |label| Axis::log(frequencies, label);
+
+    let magnitude = table
+        .iter()
+        .map(|p| (p.frequency as f64, p.magnitude_db))
+        .collect::<Vec<_>>();
+    let phase = table
+        .iter()
+        .map(|p| (p.frequency as f64, p.phase_degrees))
+        .collect::<Vec<_>>();
+
+    // Notches go to minus infinity, keep 120 dB below the peak
+    let mut magnitude_axis = Axis::linear(magnitude.iter().map(|p| p.1), "Magnitude (dB)");
+    magnitude_axis.min = magnitude_axis.min.max(magnitude_axis.max - 120.0);
+
+    document(&[
+        Panel {
+            title: "Magnitude",
+            x: x_axis(""),
+            y: magnitude_axis,
+            series: vec![Series::line(magnitude)],
+        },
+        Panel {
+            title: "Phase",
+            x: x_axis("Frequency (Hz)"),
+            y: Axis::linear(phase.iter().map(|p| p.1), "Phase (degrees)"),
+            series: vec![Series::line(phase)],
+        },
+    ])
+}
+
+pub fn group_delay(sections: &[BiQuadCoeffs], sample_rate: f32, frequencies: &[f32]) -> String {
+    let delay = frequency_table(sections, sample_rate, frequencies)
+        .iter()
+        .map(|p| (p.frequency as f64, p.group_delay_ms))
+        .collect::<Vec<_>>();
+
+    document(&[Panel {
+        title: "Group delay",
+        x: Axis::log(frequencies, "Frequency (Hz)"),
+        y: Axis::linear(delay.iter().map(|p| p.1), "Group delay (ms)"),
+        series: vec![Series::line(delay)],
+    }])
+}
+
+// Stem plot of an impulse (or any other) response against time
+pub fn impulse(samples: &[f32], sample_rate: f32) -> String {
+    let points = samples
+        .iter()
+        .enumerate()
+        .map(|(n, y)| (n as f64 * 1000.0 / sample_rate as f64, *y as f64))
+        .collect::<Vec<_>>();
+
+    let duration = samples.len().max(1) as f64 * 1000.0 / sample_rate as f64;
+    document(&[Panel {
+        title: "Impulse response",
+        x: Axis::linear([0.0, duration].iter().copied(), "Time (ms)"),
+        y: Axis::linear(points.iter().map(|p| p.1).chain(Some(0.0)), "Amplitude"),
+        series: vec![Series {
+            points,
+            style: Style::Stems,
+        }],
+    }])
+}
+
+// Poles as crosses and zeros as circles in the z plane, with the unit
+// circle. Coinciding roots are marked with their multiplicity.
+pub fn pole_zero(sections: &[BiQuadCoeffs]) -> String {
+    let mut zeros = Vec::new();
+    let mut poles = Vec::new();
+    for c in sections {
+        let roots = poles_zeros(c);
+        zeros.extend(roots.zeros);
+        poles.extend(roots.poles);
+    }
+
+    let extent = zeros
+        .iter()
+        .chain(poles.iter())
+        .map(|r| r.re.abs().max(r.im.abs()))
+        .filter(|r| r.is_finite())
+        .fold(1.0f64, f64::max)
+        * 1.15;
+    let axis = |label| Axis {
+        min: -extent,
+        max: extent,
+        log: false,
+        label,
+    };
+
+    let panel = Panel {
+        title: "Poles and zeros",
+        x: axis("Real"),
+        y: axis("Imaginary"),
+        series: vec![
+            Series {
+                points: (0..=180)
+                    .map(|i| {
+                        let (sin, cos) = (i as f64 * std::f64::consts::PI / 90.0).sin_cos();
+                        (cos, sin)
+                    })
+                    .collect(),
+                style: Style::Guide,
+            },
+            Series {
+                points: sorted_roots(&zeros),
+                style: Style::Zeros,
+            },
+            Series {
+                points: sorted_roots(&poles),
+                style: Style::Poles,
+            },
+        ],
+    };
+
+    // Square plot area so the unit circle stays round
+    let size = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let mut svg = header(WIDTH, size + MARGIN_TOP + MARGIN_BOTTOM);
+    panel.render(&mut svg, MARGIN_LEFT, MARGIN_TOP, size, size, 0);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Finite roots in sorted order, so that coinciding ones end up next to each
+// other and the renderer can draw them once with a count
+fn sorted_roots(roots: &[Complex]) -> Vec<(f64, f64)> {
+    let mut points = roots
+        .iter()
+        .filter(|r| r.norm().is_finite())
+        .map(|r| (r.re, r.im))
+        .collect::<Vec<_>>();
+    points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    points
+}
+
+struct Axis {
+    min: f64,
+    max: f64,
+    log: bool,
+    label: &'static str,
+}
+
+impl Axis {
+    // Fitted to the positive frequencies, one decade from 1 Hz without any
+    fn log(frequencies: &[f32], label: &'static str) -> Self {
+        let (min, max) = frequencies
+            .iter()
+            .map(|f| *f as f64)
+            .filter(|f| f.is_finite() && *f > 0.0)
+            .fold((f64::INFINITY, 0.0f64), |(lo, hi), f| {
+                (lo.min(f), hi.max(f))
+            });
+        let min = if min.is_finite() { min } else { 1.0 };
+        Axis {
+            min,
+            max: if max > min { max } else { min * 10.0 },
+            log: true,
+            label,
+        }
+    }
+
+    // Fitted to the finite values, rounded out to the tick spacing
+    fn linear(values: impl Iterator<Item = f64>, label: &'static str) -> Self {
+        let (mut min, mut max) = values
+            .filter(|v| v.is_finite())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                (lo.min(v), hi.max(v))
+            });
+        if min > max {
+            min = 0.0;
+            max = 1.0;
+        }
+        if max - min < 1e-9 * (1.0 + max.abs()) {
+            min -= 1.0;
+            max += 1.0;
+        }
+
+        let step = nice_step((max - min) / 5.0);
+        Axis {
+            min: (min / step).floor() * step,
+            max: (max / step).ceil() * step,
+            log: false,
+            label,
+        }
+    }
+
+    fn ticks(&self) -> Vec<f64> {
+        // The decade loop below never ends without a positive lower bound
+        if !(self.min.is_finite() && self.max.is_finite()) || (self.log && self.min <= 0.0) {
+            return Vec::new();
+        }
+        if self.log {
+            let mut ticks = Vec::new();
+            let decades = (self.max / self.min).log10();
+            let multiples: &[f64] = if decades <= 2.0 {
+                &[1.0, 2.0, 5.0]
+            } else {
+                &[1.0]
+            };
+            let mut decade = 10f64.powf(self.min.log10().floor());
+            while decade <= self.max {
+                for m in multiples {
+                    let tick = decade * m;
+                    if tick >= self.min * (1.0 - 1e-9) && tick <= self.max * (1.0 + 1e-9) {
+                        ticks.push(tick);
+                    }
+                }
+                decade *= 10.0;
+            }
+            ticks
+        } else {
+            let step = nice_step((self.max - self.min) / 5.0);
+            let first = (self.min / step).ceil() as i64;
+            let last = (self.max / step).floor() as i64;
+            (first..=last).map(|i| i as f64 * step).collect()
+        }
+    }
+
+    // Position of `v` from 0 at `min` to 1 at `max`
+    fn fraction(&self, v: f64) -> f64 {
+        if self.log {
+            (v / self.min).ln() / (self.max / self.min).ln()
+        } else {
+            (v - self.min) / (self.max - self.min)
+        }
+    }
+}
+
+// 1, 2 or 5 times a power of ten, at least `raw`
+fn nice_step(raw: f64) -> f64 {
+    let magnitude = 10f64.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|step| *step >= raw * (1.0 - 1e-9))
+        .unwrap_or(10.0 * magnitude)
+}
+
+fn tick_label(v: f64) -> String {
+    if v.abs() >= 1000.0 && (v / 1000.0).fract() == 0.0 {
+        format!("{}k", v / 1000.0)
+    } else {
+        // Round off the binary noise of the tick arithmetic
+        let rounded = (v * 1e6).round() / 1e6;
+        format!("{}", if rounded == 0.0 { 0.0 } else { rounded })
+    }
+}
+
+enum Style {
+    Line,
+    Stems,
+    // Thin dashed line for reference curves like the unit circle
+    Guide,
+    Zeros,
+    Poles,
+}
+
+struct Series {
+    points: Vec<(f64, f64)>,
+    style: Style,
+}
+
+impl Series {
+    fn line(points: Vec<(f64, f64)>) -> Self {
+        Series {
+            points,
+            style: Style::Line,
+        }
+    }
+}
+
+struct Panel {
+    title: &'static str,
+    x: Axis,
+    y: Axis,
+    series: Vec<Series>,
+}
+
+impl Panel {
+    fn render(&self, svg: &mut String, left: f64, top: f64, width: f64, height: f64, id: usize) {
+        let px = |x: f64| left + self.x.fraction(x) * width;
+        let py = |y: f64| top + (1.0 - self.y.fraction(y)) * height;
+
+        let _ = writeln!(
+            svg,
+            r#"<clipPath id="area{}"><rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}"/></clipPath>"#,
+            id, left, top, width, height
+        );
+        let _ = writeln!(
+            svg,
+            r##"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="none" stroke="#444"/>"##,
+            left, top, width, height
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="middle" font-weight="bold">{}</text>"#,
+            left + width / 2.0,
+            top - 12.0,
+            self.title
+        );
+
+        for tick in self.x.ticks() {
+            let x = px(tick);
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="#ddd"/>"##,
+                x,
+                top,
+                x,
+                top + height
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+                x,
+                top + height + 16.0,
+                tick_label(tick)
+            );
+        }
+        for tick in self.y.ticks() {
+            let y = py(tick);
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="#ddd"/>"##,
+                left,
+                y,
+                left + width,
+                y
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"#,
+                left - 6.0,
+                y + 4.0,
+                tick_label(tick)
+            );
+        }
+        if !self.x.label.is_empty() {
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+                left + width / 2.0,
+                top + height + 36.0,
+                self.x.label
+            );
+        }
+        let _ = writeln!(
+            svg,
+            r#"<text transform="translate({:.1},{:.1}) rotate(-90)" text-anchor="middle">{}</text>"#,
+            left - 50.0,
+            top + height / 2.0,
+            self.y.label
+        );
+
+        let _ = writeln!(svg, r#"<g clip-path="url(#area{})">"#, id);
+        let mut colors = COLORS.iter().cycle();
+        for series in &self.series {
+            match series.style {
+                Style::Line | Style::Guide => {
+                    let (color, extra) = match series.style {
+                        Style::Guide => ("#888", r#" stroke-dasharray="4 3""#),
+                        _ => (*colors.next().unwrap(), ""),
+                    };
+                    // Points without a place on the chart, non-finite values
+                    // or 0 Hz on a log axis, break the line instead of ending
+                    // up somewhere off it
+                    for run in series
+                        .points
+                        .split(|(x, y)| !px(*x).is_finite() || !py(*y).is_finite())
+                        .filter(|run| !run.is_empty())
+                    {
+                        let points = run
+                            .iter()
+                            .map(|(x, y)| format!("{:.2},{:.2}", px(*x), py(*y)))
+                            .collect::<Vec<_>>();
+                        let _ = writeln!(
+                            svg,
+                            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"{}/>"#,
+                            points.join(" "),
+                            color,
+                            extra
+                        );
+                    }
+                }
+                Style::Stems => {
+                    let color = colors.next().unwrap();
+                    let zero = py(0.0);
+                    for (x, y) in series.points.iter().filter(|(_, y)| y.is_finite()) {
+                        let (x, y) = (px(*x), py(*y));
+                        let _ = writeln!(
+                            svg,
+                            r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}"/><circle cx="{:.2}" cy="{:.2}" r="2" fill="{}"/>"#,
+                            x, zero, x, y, color, x, y, color
+                        );
+                    }
+                }
+                Style::Zeros | Style::Poles => {
+                    let color = colors.next().unwrap();
+                    let points = &series.points;
+                    let mut i = 0;
+                    while i < points.len() {
+                        let count = points[i..]
+                            .iter()
+                            .take_while(|p| {
+                                (p.0 - points[i].0).abs() < 1e-6 && (p.1 - points[i].1).abs() < 1e-6
+                            })
+                            .count();
+                        let (x, y) = (px(points[i].0), py(points[i].1));
+                        if let Style::Zeros = series.style {
+                            let _ = writeln!(
+                                svg,
+                                r#"<circle class="zero" cx="{:.2}" cy="{:.2}" r="6" fill="none" stroke="{}" stroke-width="2"/>"#,
+                                x, y, color
+                            );
+                        } else {
+                            let _ = writeln!(
+                                svg,
+                                r#"<path class="pole" d="M{:.2},{:.2}l12,12m0,-12l-12,12" stroke="{}" stroke-width="2"/>"#,
+                                x - 6.0,
+                                y - 6.0,
+                                color
+                            );
+                        }
+                        if count > 1 {
+                            let _ = writeln!(
+                                svg,
+                                r#"<text x="{:.2}" y="{:.2}">{}</text>"#,
+                                x + 8.0,
+                                y - 8.0,
+                                count
+                            );
+                        }
+                        i += count;
+                    }
+                }
+            }
+        }
+        svg.push_str("</g>\n");
+    }
+}
+
+fn header(width: f64, height: f64) -> String {
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="12">"#,
+        w = width,
+        h = height
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect width="{}" height="{}" fill="white"/>"#,
+        width, height
+    );
+    svg
+}
+
+// Panels stacked vertically, sharing the width
+fn document(panels: &[Panel]) -> String {
+    let panel_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let slot = PANEL_HEIGHT + MARGIN_TOP + MARGIN_BOTTOM;
+
+    let mut svg = header(WIDTH, slot * panels.len() as f64);
+    for (i, panel) in panels.iter().enumerate() {
+        panel.render(
+            &mut svg,
+            MARGIN_LEFT,
+            i as f64 * slot + MARGIN_TOP,
+            panel_width,
+            PANEL_HEIGHT,
+            i,
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
@@ -0,0 +1,107 @@
+use std::f64::consts::PI;
+
+use crate::design::FilterError;
+
+// Two path polyphase allpass halfband filters, after Valenzuela and
+// Constantinides: H(z) = (A0(z^2) + z^-1 A1(z^2)) / 2, where each path is a
+// cascade of sections (a + z^-2) / (1 + a z^-2). Both paths are allpasses,
+// so the passband and stopband are set by the phase difference of the two
+// alone: in phase below a quarter of the sample rate, opposite above it.
+//
+// The coefficients are the elliptic design in closed form, an equiripple
+// stopband for a transition band given as a fraction of the sample rate on
+// each side of fs/4. They come out ascending and alternate between the
+// paths, A0 taking the first, third and so on.
+
+// More than enough for 150 dB with a transition of a thousandth of the
+// sample rate
+pub const MAX_COEFFICIENTS: usize = 64;
+
+// Modulus of the elliptic design and the nome of its complement. The series
+// for the nome is truncated past the point where f64 would notice.
+fn transition_params(transition: f64) -> (f64, f64) {
+    let k = ((1.0 - 2.0 * transition) * PI / 4.0).tan().powi(2);
+    let root = (1.0 - k * k).sqrt().sqrt();
+    let e = 0.5 * (1.0 - root) / (1.0 + root);
+    let e4 = e.powi(4);
+    let q = e * (1.0 + e4 * (2.0 + e4 * (15.0 + 150.0 * e4)));
+    (k, q)
+}
+
+fn check(count: usize, transition: f64) -> Result<(), FilterError> {
+    if !(transition > 0.0 && transition < 0.25) {
+        return Err(FilterError(format!(
+            "a halfband transition band is between 0 and 1/4 of the sample rate, not {}",
+            transition
+        )));
+    }
+    if !(1..=MAX_COEFFICIENTS).contains(&count) {
+        return Err(FilterError(format!(
+            "a halfband filter has 1 to {} coefficients, not {}",
+            MAX_COEFFICIENTS, count
+        )));
+    }
+    Ok(())
+}
+
+// Stopband attenuation, in positive dB, of `count` coefficients
+pub fn attenuation_db(count: usize, transition: f64) -> Result<f64, FilterError> {
+    check(count, transition)?;
+    let (_, q) = transition_params(transition);
+    let a = 4.0 * q.powf((2 * count + 1) as f64 / 2.0);
+    Ok(-10.0 * (a / (1.0 + a)).log10())
+}
+
+// The fewest coefficients reaching the attenuation
+pub fn count_for(attenuation_db: f64, transition: f64) -> Result<usize, FilterError> {
+    check(1, transition)?;
+    if attenuation_db.is_nan() || attenuation_db <= 0.0 {
+        return Err(FilterError(format!(
+            "invalid stopband attenuation {} dB",
+            attenuation_db
+        )));
+    }
+    let (_, q) = transition_params(transition);
+    let power = 10f64.powf(-attenuation_db / 10.0);
+    let a = power / (1.0 - power);
+    // The filter order, odd for a halfband, is twice the count plus one
+    let order = ((a * a / 16.0).ln() / q.ln()).ceil().max(1.0);
+    let count = (order as usize / 2).max(1);
+    check(count, transition)?;
+    Ok(count)
+}
+
+pub fn coefficients(count: usize, transition: f64) -> Result<Vec<f64>, FilterError> {
+    check(count, transition)?;
+    let (k, q) = transition_params(transition);
+    let order = (2 * count + 1) as f64;
+
+    // Theta function series of the elliptic functions at each pole
+    let series = |c: f64| {
+        let mut num = 0.0;
+        for i in 0.. {
+            let term = q.powi(i * (i + 1)) * ((2 * i + 1) as f64 * c * PI / order).sin();
+            num += if i % 2 == 0 { term } else { -term };
+            if term.abs() < 1e-100 {
+                break;
+            }
+        }
+        let mut den = 0.5;
+        for i in 1.. {
+            let term = q.powi(i * i) * (2.0 * i as f64 * c * PI / order).cos();
+            den += if i % 2 == 0 { term } else { -term };
+            if term.abs() < 1e-100 {
+                break;
+            }
+        }
+        num * q.sqrt().sqrt() / den
+    };
+
+    Ok((1..=count)
+        .map(|c| {
+            let w2 = series(c as f64).powi(2);
+            let x = ((1.0 - w2 * k) * (1.0 - w2 / k)).sqrt() / (1.0 + w2);
+            (1.0 - x) / (1.0 + x)
+        })
+        .collect())
+}
@@ -0,0 +1,244 @@
+use std::{
+    f64::consts::PI,
+    ops::{Add, Div, Mul, Sub},
+};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::coeffs::BiQuadCoeffs;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+    pub const ONE: Complex = Complex { re: 1.0, im: 0.0 };
+
+    pub fn new(re: f64, im: f64) -> Self {
+        Complex { re, im }
+    }
+
+    pub fn from_polar(radius: f64, angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Complex::new(radius * cos, radius * sin)
+    }
+
+    pub fn norm(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    pub fn arg(self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn scale(self, factor: f64) -> Self {
+        Complex::new(self.re * factor, self.im * factor)
+    }
+
+    pub fn conj(self) -> Self {
+        Complex::new(self.re, -self.im)
+    }
+
+    // Principal square root, with a non-negative real part
+    pub fn sqrt(self) -> Self {
+        let r = self.norm();
+        let re = ((r + self.re) / 2.0).sqrt();
+        let im = ((r - self.re) / 2.0).sqrt();
+        Complex::new(re, if self.im < 0.0 { -im } else { im })
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+
+    fn div(self, other: Complex) -> Complex {
+        let d = other.re * other.re + other.im * other.im;
+        Complex::new(
+            (self.re * other.re + self.im * other.im) / d,
+            (self.im * other.re - self.re * other.im) / d,
+        )
+    }
+}
+
+// Normalized angular frequency, radians per sample
+fn omega(sample_rate: f32, frequency: f32) -> f64 {
+    2.0 * PI * frequency as f64 / sample_rate as f64
+}
+
+// c[0] + c[1] z^-1 + c[2] z^-2 at z = e^jw, and the same polynomial with
+// each term weighted by its delay, which gives the group delay
+fn polynomial(c: [f64; 3], w: f64) -> (Complex, Complex) {
+    let mut value = Complex::ZERO;
+    let mut weighted = Complex::ZERO;
+    for (k, c) in c.iter().enumerate() {
+        let term = Complex::from_polar(*c, -w * k as f64);
+        value = value + term;
+        weighted = weighted + term.scale(k as f64);
+    }
+    (value, weighted)
+}
+
+fn numerator(c: &BiQuadCoeffs) -> [f64; 3] {
+    [c.a0 as f64, c.a1 as f64, c.a2 as f64]
+}
+
+fn denominator(c: &BiQuadCoeffs) -> [f64; 3] {
+    [1.0, c.b1 as f64, c.b2 as f64]
+}
+
+// Frequency response of the sections in series, evaluated in double
+// precision from their (f32) coefficients
+pub fn response(sections: &[BiQuadCoeffs], sample_rate: f32, frequency: f32) -> Complex {
+    let w = omega(sample_rate, frequency);
+    sections.iter().fold(Complex::ONE, |h, c| {
+        h * polynomial(numerator(c), w).0 / polynomial(denominator(c), w).0
+    })
+}
+
+// Group delay in samples. Undefined (infinite or NaN) exactly at zeros on
+// the unit circle, e.g. at the center of a notch.
+pub fn group_delay(sections: &[BiQuadCoeffs], sample_rate: f32, frequency: f32) -> f64 {
+    let w = omega(sample_rate, frequency);
+    let delay = |c: [f64; 3]| {
+        let (value, weighted) = polynomial(c, w);
+        (weighted / value).re
+    };
+    sections
+        .iter()
+        .map(|c| delay(numerator(c)) - delay(denominator(c)))
+        .sum()
+}
+
+// Logarithmically spaced frequencies from `low` to `high`, both included
+pub fn log_frequencies(low: f32, high: f32, points: usize) -> Vec<f32> {
+    let (low, high) = (low as f64, high as f64);
+    let step = (high / low).ln() / (points.max(2) - 1) as f64;
+    (0..points)
+        .map(|i| (low * (step * i as f64).exp()) as f32)
+        .collect()
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ResponsePoint {
+    pub frequency: f32,
+    pub magnitude_db: f64,
+    pub phase_degrees: f64,
+    pub group_delay_ms: f64,
+}
+
+// Magnitude, phase and group delay over a grid of frequencies. The phase is
+// unwrapped along the grid, so it should be fine enough to not skip more
+// than half a turn between two points.
+pub fn frequency_table(
+    sections: &[BiQuadCoeffs],
+    sample_rate: f32,
+    frequencies: &[f32],
+) -> Vec<ResponsePoint> {
+    let mut previous = 0.0;
+    let mut offset = 0.0;
+
+    frequencies
+        .iter()
+        .map(|&frequency| {
+            let h = response(sections, sample_rate, frequency);
+
+            let wrapped = h.arg();
+            if wrapped - previous > PI {
+                offset -= 2.0 * PI;
+            } else if wrapped - previous < -PI {
+                offset += 2.0 * PI;
+            }
+            previous = wrapped;
+
+            ResponsePoint {
+                frequency,
+                magnitude_db: 20.0 * h.norm().log10(),
+                phase_degrees: (wrapped + offset).to_degrees(),
+                group_delay_ms: group_delay(sections, sample_rate, frequency) * 1000.0
+                    / sample_rate as f64,
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct PolesZeros {
+    pub zeros: Vec<Complex>,
+    pub poles: Vec<Complex>,
+}
+
+// Poles and zeros of one section in the z plane. First order sections have
+// one of each, a section with a0 = 0 one zero less.
+pub fn poles_zeros(c: &BiQuadCoeffs) -> PolesZeros {
+    PolesZeros {
+        zeros: roots(numerator(c)),
+        poles: roots(denominator(c)),
+    }
+}
+
+// Roots of c[0] z^2 + c[1] z + c[2], dropping the ones at the origin that
+// only come from padding a lower order polynomial, and the ones at infinity
+// of a vanishing leading term
+fn roots(c: [f64; 3]) -> Vec<Complex> {
+    let [a, b, c] = c;
+
+    if c == 0.0 {
+        return if a == 0.0 || b == 0.0 {
+            vec![]
+        } else {
+            vec![Complex::new(-b / a, 0.0)]
+        };
+    }
+    if a == 0.0 {
+        return if b == 0.0 {
+            vec![]
+        } else {
+            vec![Complex::new(-c / b, 0.0)]
+        };
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant >= 0.0 {
+        // Avoids the cancellation in -b +- sqrt(d). q can't be zero as c isn't.
+        let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+        vec![Complex::new(q / a, 0.0), Complex::new(c / q, 0.0)]
+    } else {
+        let re = -b / (2.0 * a);
+        let im = (-discriminant).sqrt() / (2.0 * a.abs());
+        vec![Complex::new(re, im), Complex::new(re, -im)]
+    }
+}
@@ -0,0 +1,82 @@
+use crate::coeffs::prewarp;
+
+// All simultaneous outputs of the state variable filter
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SvfOutputs<T> {
+    pub lowpass: T,
+    pub bandpass: T,
+    pub highpass: T,
+    pub notch: T,
+    pub peak: T,
+}
+
+// Topology-preserving (zero-delay feedback) state variable filter, after
+// Zavalishin and Simper. The state is the two trapezoidal integrators, so the
+// cutoff and Q can be changed every sample without the transients a direct
+// form biquad produces.
+#[derive(Copy, Clone)]
+pub struct SvfF32 {
+    k: f32,
+    a1: f32,
+    a2: f32,
+    a3: f32,
+
+    ic1eq: f32,
+    ic2eq: f32,
+}
+
+impl Default for SvfF32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SvfF32 {
+    pub fn new() -> Self {
+        let mut s = Self {
+            k: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            a3: 0.0,
+            ic1eq: 0.0,
+            ic2eq: 0.0,
+        };
+        s.update(44100.0, 1200.0, std::f32::consts::FRAC_1_SQRT_2);
+        s
+    }
+
+    pub fn update(&mut self, sample_rate: f32, cutoff: f32, q: f32) {
+        let g = prewarp(sample_rate, cutoff);
+        self.k = 1.0 / q;
+        self.a1 = 1.0 / (1.0 + g * (g + self.k));
+        self.a2 = g * self.a1;
+        self.a3 = g * self.a2;
+    }
+
+    pub fn reset(&mut self) {
+        self.ic1eq = 0.0;
+        self.ic2eq = 0.0;
+    }
+
+    pub fn process(&mut self, input: f32) -> SvfOutputs<f32> {
+        let v0 = input;
+        let v3 = v0 - self.ic2eq;
+        let v1 = self.a1 * self.ic1eq + self.a2 * v3;
+        let v2 = self.ic2eq + self.a2 * self.ic1eq + self.a3 * v3;
+
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+
+        let lowpass = v2;
+        let bandpass = v1;
+        let highpass = v0 - self.k * v1 - v2;
+
+        SvfOutputs {
+            lowpass,
+            bandpass,
+            highpass,
+            notch: lowpass + highpass,
+            peak: lowpass - highpass,
+        }
+    }
+}
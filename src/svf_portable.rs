@@ -0,0 +1,82 @@
+use wide::f32x4;
+
+use crate::{coeffs::prewarp, svf_f32::SvfOutputs};
+
+// Four independent channels of `SvfF32`, one per lane. Each lane has its own
+// cutoff and Q so the channels can be modulated separately.
+pub struct SvfPortable4 {
+    k: f32x4,
+    a1: f32x4,
+    a2: f32x4,
+    a3: f32x4,
+
+    ic1eq: f32x4,
+    ic2eq: f32x4,
+}
+
+impl Default for SvfPortable4 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SvfPortable4 {
+    pub fn new() -> Self {
+        let mut s = SvfPortable4 {
+            k: f32x4::ZERO,
+            a1: f32x4::ZERO,
+            a2: f32x4::ZERO,
+            a3: f32x4::ZERO,
+            ic1eq: f32x4::ZERO,
+            ic2eq: f32x4::ZERO,
+        };
+        s.update(44100.0, 1200.0, std::f32::consts::FRAC_1_SQRT_2);
+        s
+    }
+
+    pub fn update(&mut self, sample_rate: f32, cutoff: f32, q: f32) {
+        self.update_lanes(sample_rate, [cutoff; 4], [q; 4]);
+    }
+
+    pub fn update_lanes(&mut self, sample_rate: f32, cutoff: [f32; 4], q: [f32; 4]) {
+        let mut g = [0.0; 4];
+        let mut k = [0.0; 4];
+        for i in 0..4 {
+            g[i] = prewarp(sample_rate, cutoff[i]);
+            k[i] = 1.0 / q[i];
+        }
+
+        let g = f32x4::from(g);
+        self.k = f32x4::from(k);
+        self.a1 = f32x4::ONE / g.mul_add(g + self.k, f32x4::ONE);
+        self.a2 = g * self.a1;
+        self.a3 = g * self.a2;
+    }
+
+    pub fn reset(&mut self) {
+        self.ic1eq = f32x4::ZERO;
+        self.ic2eq = f32x4::ZERO;
+    }
+
+    pub fn process(&mut self, input: f32x4) -> SvfOutputs<f32x4> {
+        let v0 = input;
+        let v3 = v0 - self.ic2eq;
+        let v1 = self.a1.mul_add(self.ic1eq, self.a2 * v3);
+        let v2 = self.ic2eq + self.a2.mul_add(self.ic1eq, self.a3 * v3);
+
+        self.ic1eq = v1 + v1 - self.ic1eq;
+        self.ic2eq = v2 + v2 - self.ic2eq;
+
+        let lowpass = v2;
+        let bandpass = v1;
+        let highpass = v0 - self.k * v1 - v2;
+
+        SvfOutputs {
+            lowpass,
+            bandpass,
+            highpass,
+            notch: lowpass + highpass,
+            peak: lowpass - highpass,
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use simdiir::{
+    accuracy::{reference, ErrorStats, TestSignal},
+    cascade::{Backend, Cascade},
+    coeffs::BiQuadCoeffs,
+};
+
+mod common;
+
+use common::{reference_df1, SAMPLE_RATE};
+
+fn chain() -> Vec<BiQuadCoeffs> {
+    vec![
+        BiQuadCoeffs::highpass(SAMPLE_RATE, 200.0, 0.7),
+        BiQuadCoeffs::peaking(SAMPLE_RATE, 3000.0, 2.0, -6.0),
+        BiQuadCoeffs::lowpass(SAMPLE_RATE, 12000.0, 0.9),
+    ]
+}
+
+#[test]
+fn stats_of_known_errors() {
+    // Error of 0.1 on the first quarter and 0.2 on the last one
+    let reference = vec![1.0f64; 64];
+    let mut output = [1.0f32; 64];
+    output[..16].copy_from_slice(&[1.1; 16]);
+    output[48..].copy_from_slice(&[0.8; 16]);
+    let stats = ErrorStats::measure(&output, &reference);
+
+    assert!((stats.max_abs_error - 0.2).abs() < 1e-6);
+    assert!((stats.rms_error - (0.1f64 / 8.0).sqrt()).abs() < 1e-6);
+    assert!((stats.snr_db - 10.0 * (8.0f64 / 0.1).log10()).abs() < 1e-4);
+    assert!((stats.error_growth_db.unwrap() - 20.0 * 2.0f64.log10()).abs() < 1e-4);
+
+    let exact = ErrorStats::measure(&[1.0; 64], &reference);
+    assert_eq!(exact.max_abs_error, 0.0);
+    assert_eq!(exact.snr_db, f64::INFINITY);
+    assert_eq!(exact.error_growth_db, None);
+
+    // Nothing to grow from when the first quarter is exact, as with the
+    // impulse through a filter that starts out rounding free
+    let mut late = [1.0f32; 64];
+    late[48..].copy_from_slice(&[0.8; 16]);
+    assert_eq!(ErrorStats::measure(&late, &reference).error_growth_db, None);
+
+    // Nor from a quarter that doesn't fill two blocks
+    let short = ErrorStats::measure(&output[..8], &reference[..8]);
+    assert!(short.snr_db.is_finite());
+    assert_eq!(short.error_growth_db, None);
+}
+
+#[test]
+fn reference_is_df1_in_double_precision() {
+    let input = TestSignal::Noise.generate(1000, SAMPLE_RATE);
+    let sections = chain();
+    assert_eq!(
+        reference(&sections[..1], &input),
+        reference_df1(&sections[0], &input)
+    );
+
+    let passthrough = reference(&[], &input);
+    assert!(passthrough
+        .iter()
+        .zip(input.iter())
+        .all(|(a, b)| *a == *b as f64));
+}
+
+#[test]
+fn test_signals() {
+    let impulse = TestSignal::Impulse.generate(16, SAMPLE_RATE);
+    assert_eq!(impulse[0], 1.0);
+    assert!(impulse[1..].iter().all(|x| *x == 0.0));
+
+    let noise = TestSignal::Noise.generate(4096, SAMPLE_RATE);
+    assert_eq!(noise, TestSignal::Noise.generate(4096, SAMPLE_RATE));
+    assert!(noise.iter().all(|x| (-1.0..1.0).contains(x)));
+
+    // The sweep starts slow and ends fast: count zero crossings per half
+    let sweep = TestSignal::Sweep.generate(48000, SAMPLE_RATE);
+    assert!(sweep.iter().all(|x| x.abs() <= 0.5));
+    let crossings = |x: &[f32]| {
+        x.windows(2)
+            .filter(|w| (w[0] < 0.0) != (w[1] < 0.0))
+            .count()
+    };
+    assert!(crossings(&sweep[..24000]) * 10 < crossings(&sweep[24000..]));
+
+    for signal in TestSignal::ALL.iter() {
+        assert_eq!(signal.name().parse::<TestSignal>(), Ok(*signal));
+    }
+}
+
+#[test]
+fn backends_are_close_to_reference() {
+    let sections = chain();
+    for signal in TestSignal::ALL.iter() {
+        let input = signal.generate(8192, SAMPLE_RATE);
+        let expected = reference(&sections, &input);
+        for backend in Backend::ALL.iter().filter(|b| b.is_supported()) {
+            let mut output = input.clone();
+            Cascade::new(*backend, &sections).process(&mut output);
+            let stats = ErrorStats::measure(&output, &expected);
+            assert!(
+                stats.snr_db > 70.0,
+                "{} on {}: {:?}",
+                backend,
+                signal,
+                stats
+            );
+        }
+    }
+}
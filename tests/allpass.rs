@@ -0,0 +1,200 @@
+use simdiir::{
+    allpass::{flattening_target, thiran, FractionalDelay, PhaseEqualizer},
+    cascade::{Backend, Cascade},
+    coeffs::BiQuadCoeffs,
+    design::{FilterKind, FilterSpec},
+    response::{group_delay, log_frequencies, response},
+};
+
+mod common;
+
+use common::{noise, SAMPLE_RATE};
+
+fn stable(sections: &[BiQuadCoeffs]) -> bool {
+    sections
+        .iter()
+        .all(|c| c.b2.abs() < 1.0 && c.b1.abs() < 1.0 + c.b2)
+}
+
+fn is_allpass(sections: &[BiQuadCoeffs]) -> bool {
+    log_frequencies(20.0, 23000.0, 50)
+        .iter()
+        .all(|f| (response(sections, SAMPLE_RATE, *f).norm() - 1.0).abs() < 1e-5)
+}
+
+#[test]
+fn thiran_delay_is_flat() {
+    for order in 1..=8 {
+        for &fraction in &[-0.4, 0.0, 0.25, 0.5] {
+            let delay = order as f64 + fraction;
+            let sections = thiran(delay, order).unwrap();
+            assert_eq!(sections.len(), (order as usize).div_ceil(2));
+            assert!(stable(&sections) && is_allpass(&sections));
+
+            // Maximally flat at DC, and the flatter the higher the order
+            let at = |f: f32| group_delay(&sections, SAMPLE_RATE, f);
+            assert!((at(10.0) - delay).abs() < 1e-4, "{} {}", delay, at(10.0));
+            let edge = SAMPLE_RATE * order as f32 / 80.0;
+            assert!((at(edge) - delay).abs() < 0.01, "{}: {}", delay, at(edge));
+        }
+    }
+
+    // The first order one in closed form
+    let c = thiran(0.3, 1).unwrap()[0];
+    assert!((c.b1 - 0.7 / 1.3).abs() < 1e-6 && c.a0 == c.b1 && c.a1 == 1.0);
+
+    assert!(thiran(1.0, 2).is_err());
+    assert!(thiran(3.0, 0).is_err());
+    assert!(thiran(f64::NAN, 1).is_err());
+}
+
+#[test]
+fn fractional_delay_line() {
+    let w = 2.0 * std::f64::consts::PI * 1000.0 / SAMPLE_RATE as f64;
+    let sine = |delay: f64| {
+        (0..4800)
+            .map(|n| (w * (n as f64 - delay)).sin() as f32)
+            .collect::<Vec<_>>()
+    };
+
+    let mut line = FractionalDelay::with_backend(Backend::Scalar, 3.3, 4).unwrap();
+    let mut samples = sine(0.0);
+    line.process(&mut samples);
+    let expected = sine(3.3);
+    for n in 2400..4800 {
+        assert!((samples[n] - expected[n]).abs() < 1e-3, "{}", n);
+    }
+
+    // Changing the delay keeps the state, so a sine carries straight on
+    line.set_delay(4.1).unwrap();
+    assert_eq!(line.delay(), 4.1);
+    let mut samples = sine(-4800.0);
+    line.process(&mut samples);
+    let expected = sine(-4800.0 + 4.1);
+    let error = samples
+        .iter()
+        .zip(expected.iter())
+        .map(|(s, e)| (s - e).abs())
+        .fold(0.0, f32::max);
+    assert!(error < 0.05, "{}", error);
+    for n in 2400..4800 {
+        assert!((samples[n] - expected[n]).abs() < 1e-3, "{}", n);
+    }
+
+    assert!(line.set_delay(2.5).is_err());
+    assert_eq!((line.delay(), line.order()), (4.1, 4));
+    assert_eq!(line.sections(), thiran(4.1, 4).unwrap().as_slice());
+
+    line.reset();
+    let mut impulse = vec![0.0; 16];
+    impulse[0] = 1.0;
+    line.process(&mut impulse);
+    let energy = impulse.iter().map(|x| x * x).sum::<f32>();
+    assert!(energy > 0.99 && energy <= 1.0 + 1e-5);
+
+    // Buffers of a few samples on every backend run as one
+    let input = noise(960);
+    let mut expected = input.clone();
+    FractionalDelay::with_backend(Backend::Scalar, 3.3, 4)
+        .unwrap()
+        .process(&mut expected);
+    for backend in Backend::ALL.iter().filter(|b| b.is_supported()) {
+        let mut line = FractionalDelay::with_backend(*backend, 3.3, 4).unwrap();
+        let mut samples = input.clone();
+        for chunk in samples.chunks_mut(3) {
+            line.process(chunk);
+        }
+        for (s, e) in samples.iter().zip(expected.iter()) {
+            assert!((s - e).abs() < 1e-4, "{}: {} != {}", backend, s, e);
+        }
+    }
+}
+
+// The group delay of a 4th order Butterworth lowpass over its passband
+#[test]
+fn flattens_a_butterworth_lowpass() {
+    let lowpass = FilterSpec::new(FilterKind::Lowpass, 1000.0)
+        .with_order(4)
+        .sections(SAMPLE_RATE);
+    let target = flattening_target(&lowpass, SAMPLE_RATE, 20.0, 1000.0);
+    let ripple = |eq: Option<&PhaseEqualizer>| {
+        let total = log_frequencies(20.0, 1000.0, 200)
+            .iter()
+            .map(|f| {
+                group_delay(&lowpass, SAMPLE_RATE, *f) + eq.map_or(0.0, |eq| eq.group_delay(*f))
+            })
+            .collect::<Vec<_>>();
+        total.iter().fold(f64::MIN, |a, b| a.max(*b))
+            - total.iter().fold(f64::MAX, |a, b| a.min(*b))
+    };
+    let before = ripple(None);
+    assert!(before > 9.0);
+
+    let mut last = before;
+    for &(order, limit) in &[(4, 1.0), (6, 0.5), (8, 0.1)] {
+        let eq =
+            PhaseEqualizer::with_backend(Backend::Scalar, SAMPLE_RATE, &target, order).unwrap();
+        assert_eq!(eq.sections().len(), (order as usize).div_ceil(2));
+        assert!(stable(eq.sections()) && is_allpass(eq.sections()));
+        let after = ripple(Some(&eq));
+        assert!(
+            after < limit && after < last,
+            "order {}: {} samples",
+            order,
+            after
+        );
+        assert!(eq.rms_error() < after && eq.offset() > 0.0);
+        last = after;
+    }
+}
+
+#[test]
+fn follows_a_target() {
+    // A bump of delay around 2 kHz, on top of whatever constant the fit picks
+    let target = log_frequencies(100.0, 10000.0, 30)
+        .iter()
+        .map(|f| (*f, 8.0 / (1.0 + ((f / 2000.0).ln() / 0.5).powi(2)) as f64))
+        .collect::<Vec<_>>();
+    let eq = PhaseEqualizer::with_backend(Backend::detect(), SAMPLE_RATE, &target, 8).unwrap();
+    assert!(eq.rms_error() < 0.15, "{}", eq.rms_error());
+    for (f, delay) in target.iter() {
+        let error = eq.group_delay(*f) - delay - eq.offset();
+        assert!(error.abs() < 0.5, "{} Hz: {}", f, error);
+    }
+    assert!((eq.response(5000.0).norm() - 1.0).abs() < 1e-5);
+
+    // Runs as its sections, in buffers of any length
+    let input = noise(2000);
+    let mut eq = eq;
+    let mut output = input.clone();
+    for chunk in output.chunks_mut(7) {
+        eq.process(chunk);
+    }
+    let mut expected = input.clone();
+    Cascade::new(Backend::Scalar, eq.sections()).process(&mut expected);
+    for (a, e) in output.iter().zip(expected.iter()) {
+        assert!((a - e).abs() < 1e-4);
+    }
+
+    eq.reset();
+    let mut again = input.clone();
+    for chunk in again.chunks_mut(7) {
+        eq.process(chunk);
+    }
+    assert_eq!(again, output);
+}
+
+#[test]
+fn invalid_targets() {
+    let error = |target: &[(f32, f64)], order: u32| {
+        PhaseEqualizer::new(SAMPLE_RATE, target, order)
+            .err()
+            .unwrap()
+            .to_string()
+    };
+    assert!(error(&[(100.0, 1.0), (1000.0, 0.0)], 0).contains("order"));
+    assert!(error(&[(100.0, 1.0)], 2).contains("two or more"));
+    assert!(error(&[(1000.0, 1.0), (100.0, 0.0)], 2).contains("rising"));
+    assert!(error(&[(100.0, 1.0), (30000.0, 0.0)], 2).contains("Nyquist"));
+    assert!(error(&[(100.0, f64::NAN), (1000.0, 0.0)], 2).contains("finite"));
+}
@@ -0,0 +1,61 @@
+#![cfg(target_arch = "x86_64")]
+
+use std::arch::x86_64::*;
+
+use simdiir::{biquad_avx::BiQuadAVX, biquad_f32::BiQuadF32, coeffs::BiQuadCoeffs};
+
+mod common;
+
+use common::{noise, reference_df1, SAMPLE_RATE};
+
+fn designs() -> Vec<BiQuadCoeffs> {
+    vec![
+        BiQuadCoeffs::lowpass(SAMPLE_RATE, 1000.0, 0.707),
+        BiQuadCoeffs::lowpass(SAMPLE_RATE, 300.0, 2.0),
+        BiQuadCoeffs::lowpass(SAMPLE_RATE, 5000.0, 4.0),
+        BiQuadCoeffs::allpass(SAMPLE_RATE, 15000.0),
+        BiQuadCoeffs::identity(),
+    ]
+}
+
+#[test]
+fn blocks_continue_the_scalar_recursion() {
+    if !(is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")) {
+        return;
+    }
+    let input = noise(4800);
+    for c in designs() {
+        let mut scalar = BiQuadF32::new();
+        scalar.set_coeffs(&c);
+        let expected = input.iter().map(|x| scalar.process(*x)).collect::<Vec<_>>();
+        let exact = reference_df1(&c, &input);
+
+        let mut avx = BiQuadAVX::new();
+        avx.set_coeffs(&c);
+        let mut output = vec![0.0; input.len()];
+        for (x, y) in input.chunks_exact(8).zip(output.chunks_exact_mut(8)) {
+            unsafe { _mm256_storeu_ps(y.as_mut_ptr(), avx.process(_mm256_loadu_ps(x.as_ptr()))) };
+        }
+
+        // Every block starts from the state the previous one left, so lane i
+        // of block n is the scalar filter's sample 8n + i. The two round
+        // differently, by about as much as the scalar filter is off from the
+        // exact recursion.
+        let rounding = expected
+            .iter()
+            .zip(exact.iter())
+            .map(|(e, r)| (*e as f64 - r).abs())
+            .fold(0.0, f64::max);
+        for (n, (a, e)) in output.iter().zip(expected.iter()).enumerate() {
+            assert!(
+                ((a - e).abs() as f64) <= 8.0 * rounding + 1e-6,
+                "{:?}, block {} lane {}: {} != {}",
+                c,
+                n / 8,
+                n % 8,
+                a,
+                e
+            );
+        }
+    }
+}
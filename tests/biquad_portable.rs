@@ -0,0 +1,107 @@
+use wide::{f32x4, f32x8};
+
+use simdiir::{
+    biquad_f32::BiQuadF32,
+    biquad_portable::{BiQuadPortable4, BiQuadPortable8},
+    coeffs::BiQuadCoeffs,
+};
+
+mod common;
+
+use common::{noise, reference_df1, SAMPLE_RATE};
+
+fn designs() -> Vec<BiQuadCoeffs> {
+    vec![
+        BiQuadCoeffs::lowpass(SAMPLE_RATE, 1000.0, 0.707),
+        BiQuadCoeffs::highpass(SAMPLE_RATE, 80.0, 0.5),
+        BiQuadCoeffs::peaking(SAMPLE_RATE, 3000.0, 4.0, 9.0),
+        BiQuadCoeffs::allpass(SAMPLE_RATE, 15000.0),
+        BiQuadCoeffs::identity(),
+    ]
+}
+
+fn scalar(c: &BiQuadCoeffs, input: &[f32]) -> Vec<f32> {
+    let mut biquad = BiQuadF32::new();
+    biquad.set_coeffs(c);
+    input.iter().map(|x| biquad.process(*x)).collect()
+}
+
+// Each lane of a block is one sample, so lane i of block n has to be the
+// scalar filter's sample 4n + i (or 8n + i). The two round differently, by
+// about as much as the scalar filter is off from the exact recursion, which
+// for poles close to z = 1 is well above the float epsilon.
+fn assert_lanes_match(name: &str, c: &BiQuadCoeffs, input: &[f32], output: &[f32], lanes: usize) {
+    assert_eq!(output.len(), input.len());
+    let expected = scalar(c, input);
+    let exact = reference_df1(c, input);
+    let rounding = expected
+        .iter()
+        .zip(exact.iter())
+        .map(|(e, r)| (*e as f64 - r).abs())
+        .fold(0.0, f64::max);
+    for (n, (a, e)) in output.iter().zip(expected.iter()).enumerate() {
+        let error = (a - e).abs() as f64;
+        assert!(
+            error <= 8.0 * rounding + 1e-6,
+            "{} {:?}, block {} lane {}: {} != {}",
+            name,
+            c,
+            n / lanes,
+            n % lanes,
+            a,
+            e
+        );
+    }
+}
+
+#[test]
+fn portable4_lanes_match_scalar() {
+    let input = noise(4800);
+    for c in designs() {
+        let mut biquad = BiQuadPortable4::new();
+        biquad.set_coeffs(&c);
+        let output = input
+            .chunks(4)
+            .flat_map(|x| biquad.process(f32x4::from(x)).to_array().to_vec())
+            .collect::<Vec<_>>();
+        assert_lanes_match("portable4", &c, &input, &output, 4);
+    }
+}
+
+#[test]
+fn portable8_lanes_match_scalar() {
+    let input = noise(4800);
+    for c in designs() {
+        let mut biquad = BiQuadPortable8::new();
+        biquad.set_coeffs(&c);
+        let output = input
+            .chunks(8)
+            .flat_map(|x| biquad.process(f32x8::from(x)).to_array().to_vec())
+            .collect::<Vec<_>>();
+        assert_lanes_match("portable8", &c, &input, &output, 8);
+    }
+}
+
+#[test]
+fn single_samples_between_blocks() {
+    // Blocks and single samples interleaved run the same recursion
+    let input = noise(13 * 400);
+    for c in designs() {
+        let mut four = BiQuadPortable4::new();
+        let mut eight = BiQuadPortable8::new();
+        four.set_coeffs(&c);
+        eight.set_coeffs(&c);
+
+        let (mut out4, mut out8) = (Vec::new(), Vec::new());
+        for chunk in input.chunks(13) {
+            let (block, rest) = chunk.split_at(8);
+            out4.extend(four.process(f32x4::from(&block[..4])).to_array().iter());
+            out4.extend(four.process(f32x4::from(&block[4..])).to_array().iter());
+            out4.extend(rest.iter().map(|x| four.process_sample(*x)));
+            out8.extend(eight.process(f32x8::from(block)).to_array().iter());
+            out8.extend(rest.iter().map(|x| eight.process_sample(*x)));
+        }
+        assert_lanes_match("portable4", &c, &input, &out4, 4);
+        assert_lanes_match("portable8", &c, &input, &out8, 8);
+    }
+}
@@ -0,0 +1,170 @@
+use simdiir::{
+    cascade::{Backend, Cascade},
+    coeffs::BiQuadCoeffs,
+    design::{parse_chain, FilterKind, FilterSpec, RealizationKind},
+};
+
+mod common;
+
+use common::{magnitude, noise, SAMPLE_RATE};
+
+fn backends() -> Vec<Backend> {
+    vec![
+        Backend::Scalar,
+        Backend::Portable4,
+        Backend::Portable8,
+        #[cfg(target_arch = "x86_64")]
+        Backend::Sse2,
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx,
+    ]
+}
+
+#[test]
+fn backends_match_scalar() {
+    let sections = [
+        BiQuadCoeffs::highpass(SAMPLE_RATE, 80.0, 0.707),
+        BiQuadCoeffs::peaking(SAMPLE_RATE, 2500.0, 1.5, 4.0),
+        BiQuadCoeffs::lowpass(SAMPLE_RATE, 9000.0, 0.9),
+    ];
+    // Not a multiple of the block size, to exercise the tail
+    let input = noise(4099);
+
+    let mut expected = input.clone();
+    Cascade::new(Backend::Scalar, &sections).process(&mut expected);
+
+    for backend in backends().into_iter().filter(|b| b.is_supported()) {
+        let mut cascade = Cascade::new(backend, &sections);
+        assert_eq!(cascade.len(), 3);
+
+        // Split in two calls to check the state carries over between them
+        let mut output = input.clone();
+        let (head, tail) = output.split_at_mut(2048);
+        cascade.process(head);
+        cascade.process(tail);
+
+        // The low highpass amplifies the rounding differences between the
+        // block and the scalar recursions
+        for (o, e) in output.iter().zip(expected.iter()) {
+            assert!((o - e).abs() < 1e-3, "{}: {} != {}", backend, o, e);
+        }
+    }
+}
+
+#[test]
+fn chunks_match_one_buffer() {
+    let sections = [
+        BiQuadCoeffs::peaking(SAMPLE_RATE, 1000.0, 1.0, 6.0),
+        BiQuadCoeffs::lowpass(SAMPLE_RATE, 5000.0, 0.707),
+        BiQuadCoeffs::highpass(SAMPLE_RATE, 200.0, 0.707),
+    ];
+    // A scalar realization in between the kernel sections
+    let realizations = [
+        (sections[0], RealizationKind::Df1),
+        (sections[1], RealizationKind::Tdf2),
+        (sections[2], RealizationKind::Df1),
+    ];
+    let input = noise(960);
+
+    for backend in backends().into_iter().filter(|b| b.is_supported()) {
+        for chunk in [1, 3, 7] {
+            let build = || Cascade::with_realizations(backend, &realizations);
+            let mut whole = input.clone();
+            build().process(&mut whole);
+
+            let mut cascade = build();
+            let mut chunked = input.clone();
+            for block in chunked.chunks_mut(chunk) {
+                cascade.process(block);
+            }
+
+            // Only the rounding differs, between the block and the one
+            // sample recursions
+            for (c, w) in chunked.iter().zip(whole.iter()) {
+                assert!(
+                    (c - w).abs() < 1e-4,
+                    "{} in chunks of {}: {} != {}",
+                    backend,
+                    chunk,
+                    c,
+                    w
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn detected_backend_is_supported() {
+    assert!(Backend::detect().is_supported());
+    for backend in backends() {
+        assert_eq!(backend.name().parse::<Backend>(), Ok(backend));
+    }
+}
+
+#[test]
+fn designs_have_expected_gain() {
+    let db = |c: &BiQuadCoeffs, f: f32| 20.0 * magnitude(c, f).log10();
+    let near = |a: f64, b: f64| (a - b).abs() < 0.01;
+
+    let lowpass = BiQuadCoeffs::lowpass(SAMPLE_RATE, 1000.0, 0.707);
+    assert!(near(db(&lowpass, 1.0), 0.0));
+    assert!(near(db(&lowpass, 1000.0), -3.01));
+
+    let highpass = BiQuadCoeffs::highpass(SAMPLE_RATE, 1000.0, 0.707);
+    assert!(near(db(&highpass, 23999.0), 0.0));
+    assert!(near(db(&highpass, 1000.0), -3.01));
+
+    let bandpass = BiQuadCoeffs::bandpass(SAMPLE_RATE, 1000.0, 2.0);
+    assert!(near(db(&bandpass, 1000.0), 0.0));
+
+    let notch = BiQuadCoeffs::notch(SAMPLE_RATE, 1000.0, 2.0);
+    assert!(db(&notch, 1000.0) < -60.0);
+
+    let peak = BiQuadCoeffs::peaking(SAMPLE_RATE, 1000.0, 2.0, -6.0);
+    assert!(near(db(&peak, 1000.0), -6.0));
+    assert!(near(db(&peak, 1.0), 0.0));
+
+    let low_shelf = BiQuadCoeffs::low_shelf(SAMPLE_RATE, 300.0, 0.707, 6.0);
+    assert!(near(db(&low_shelf, 1.0), 6.0));
+    assert!(near(db(&low_shelf, 300.0), 3.0));
+    assert!(near(db(&low_shelf, 23999.0), 0.0));
+
+    let high_shelf = BiQuadCoeffs::high_shelf(SAMPLE_RATE, 5000.0, 0.707, -4.0);
+    assert!(near(db(&high_shelf, 1.0), 0.0));
+    assert!(near(db(&high_shelf, 5000.0), -2.0));
+    assert!(near(db(&high_shelf, 23999.0), -4.0));
+}
+
+#[test]
+fn parse_filter_specs() {
+    assert_eq!(
+        "lowpass:1000".parse::<FilterSpec>(),
+        Ok(FilterSpec::new(FilterKind::Lowpass, 1000.0))
+    );
+    assert_eq!(
+        "peak:3000:2:-6".parse::<FilterSpec>(),
+        Ok(FilterSpec::new(FilterKind::Peak, 3000.0)
+            .with_q(2.0)
+            .with_gain_db(-6.0))
+    );
+
+    for bad in &[
+        "",
+        "lowpass",
+        "bandstop:100",
+        "lowpass:abc",
+        "lowpass:-10",
+        "lowpass:100:0",
+        "peak:100:1:2:3",
+    ] {
+        assert!(bad.parse::<FilterSpec>().is_err(), "{}", bad);
+    }
+
+    let chain = parse_chain("# preset\nhighpass:40\n\npeak:120:1.4:-3 # room mode\n").unwrap();
+    assert_eq!(chain.len(), 2);
+    assert_eq!(chain[1].gain_db, -3.0);
+
+    let err = parse_chain("highpass:40\nlowpas:100\n").unwrap_err();
+    assert!(err.to_string().starts_with("line 2:"), "{}", err);
+}
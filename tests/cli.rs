@@ -0,0 +1,308 @@
+use std::{path::PathBuf, process::Command};
+
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+
+use simdiir::{
+    cascade::{Backend, Cascade},
+    design::FilterSpec,
+};
+
+mod common;
+
+use common::noise;
+
+fn tmp(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+fn simdiir(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_simdiir"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn write_wav(name: &str, spec: WavSpec, samples: &[f32]) -> PathBuf {
+    let path = tmp(name);
+    let mut writer = WavWriter::create(&path, spec).unwrap();
+    for &x in samples {
+        match spec.sample_format {
+            SampleFormat::Float => writer.write_sample(x).unwrap(),
+            SampleFormat::Int => {
+                let full = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                writer.write_sample((x * 0.5 * full) as i32).unwrap()
+            }
+        }
+    }
+    writer.finalize().unwrap();
+    path
+}
+
+#[test]
+fn filters_every_sample_format() {
+    let filters = ["highpass:100", "peak:1000:2:-6", "lowpass:5000:0.9"];
+    let input = noise(3 * 1001);
+
+    for &(format, bits) in &[
+        (SampleFormat::Int, 16),
+        (SampleFormat::Int, 24),
+        (SampleFormat::Int, 32),
+        (SampleFormat::Float, 32),
+    ] {
+        let spec = WavSpec {
+            channels: 3,
+            sample_rate: 44100,
+            bits_per_sample: bits,
+            sample_format: format,
+        };
+        let name = format!("{:?}{}", format, bits);
+        let input_path = write_wav(&format!("{}-in.wav", name), spec, &input);
+        let output_path = tmp(&format!("{}-out.wav", name));
+
+        let mut args = vec!["filter"];
+        for f in &filters {
+            args.extend(&["-f", f]);
+        }
+        args.push(input_path.to_str().unwrap());
+        args.push(output_path.to_str().unwrap());
+        let result = simdiir(&args);
+        assert!(result.status.success(), "{:?}", result);
+
+        let mut reader = WavReader::open(&output_path).unwrap();
+        assert_eq!(reader.spec(), spec);
+        let output = match format {
+            SampleFormat::Float => reader.samples::<f32>().map(Result::unwrap).collect(),
+            SampleFormat::Int => {
+                let full = (1i64 << (bits - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|s| s.unwrap() as f32 / full)
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(output.len(), input.len());
+
+        // Each channel filtered on its own with the same chain
+        let specs = filters
+            .iter()
+            .map(|f| f.parse::<FilterSpec>().unwrap())
+            .collect::<Vec<_>>();
+        let sections = specs
+            .iter()
+            .flat_map(|s| s.sections(44100.0))
+            .collect::<Vec<_>>();
+        for channel in 0..3 {
+            let mut expected = input
+                .iter()
+                .skip(channel)
+                .step_by(3)
+                .map(|x| match format {
+                    SampleFormat::Float => *x,
+                    SampleFormat::Int => {
+                        let full = (1i64 << (bits - 1)) as f32;
+                        (x * 0.5 * full) as i32 as f32 / full
+                    }
+                })
+                .collect::<Vec<_>>();
+            Cascade::new(Backend::Scalar, &sections).process(&mut expected);
+
+            let actual = output.iter().skip(channel).step_by(3);
+            for (a, e) in actual.zip(expected.iter()) {
+                assert!((a - e).abs() < 1e-3, "{}: {} != {}", name, a, e);
+            }
+        }
+    }
+}
+
+#[test]
+fn reports_bad_filters() {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: 8000,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let input = write_wav("bad-in.wav", spec, &noise(64));
+    let output = tmp("bad-out.wav");
+    let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+
+    let result = simdiir(&["filter", "-f", "lowpass:5000", input, output]);
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("Nyquist"));
+
+    let result = simdiir(&["filter", "-f", "bandstop:100", input, output]);
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("unknown filter type"));
+}
+
+#[test]
+fn reports_a_header_without_channels() {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: 8000,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let input = write_wav("no-channels-in.wav", spec, &noise(64));
+    // The channel count of the PCM format chunk
+    let mut bytes = std::fs::read(&input).unwrap();
+    bytes[22..24].copy_from_slice(&0u16.to_le_bytes());
+    std::fs::write(&input, bytes).unwrap();
+    let output = tmp("no-channels-out.wav");
+
+    let result = simdiir(&[
+        "filter",
+        "-f",
+        "lowpass:1000",
+        input.to_str().unwrap(),
+        output.to_str().unwrap(),
+    ]);
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("channels"));
+}
+
+fn stdout(args: &[&str]) -> String {
+    let result = simdiir(args);
+    assert!(result.status.success(), "{:?}", result);
+    String::from_utf8(result.stdout).unwrap()
+}
+
+#[test]
+fn impulse_and_step_responses() {
+    let impulse = stdout(&["impulse", "-f", "allpass:1200", "-r", "44100", "-n", "64"]);
+    let step = stdout(&["step", "-f", "allpass:1200", "-r", "44100", "-n", "64"]);
+
+    let column = |csv: &str| {
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("sample,time_ms,output"));
+        lines
+            .map(|line| line.rsplit(',').next().unwrap().parse::<f32>().unwrap())
+            .collect::<Vec<_>>()
+    };
+    let impulse = column(&impulse);
+    let step = column(&step);
+    assert_eq!(impulse.len(), 64);
+
+    // The old hardcoded filter, and the step response is the running sum of
+    // the impulse response
+    assert_eq!(impulse[0], -0.8421391);
+    let mut sum = 0.0;
+    for (h, s) in impulse.iter().zip(step.iter()) {
+        sum += h;
+        assert!((sum - s).abs() < 1e-5, "{} != {}", sum, s);
+    }
+}
+
+#[test]
+fn frequency_response_and_poles_as_json() {
+    let json = stdout(&[
+        "response",
+        "-f",
+        "lowpass:1000",
+        "--points",
+        "3",
+        "--from",
+        "100",
+        "--to",
+        "10000",
+        "--format",
+        "json",
+    ]);
+    let rows: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let rows = rows.as_array().unwrap();
+    assert_eq!(rows.len(), 3);
+    let middle = &rows[1];
+    assert_eq!(middle["frequency_hz"], 1000.0);
+    assert!((middle["magnitude_db"].as_f64().unwrap() + 3.01).abs() < 0.01);
+    assert!((middle["phase_degrees"].as_f64().unwrap() + 90.0).abs() < 0.01);
+
+    // Keys in the order of the table's columns, same as the CSV header
+    let csv = stdout(&["response", "-f", "lowpass:1000", "--format", "csv"]);
+    let header = csv.lines().next().unwrap().split(',').collect::<Vec<_>>();
+    let keys = middle.as_object().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(keys, header);
+
+    let json = stdout(&["poles", "-f", "lowpass:1000", "--format", "json"]);
+    let rows: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let rows = rows.as_array().unwrap();
+    let count = |kind: &str| rows.iter().filter(|r| r["type"] == kind).count();
+    assert_eq!((count("zero"), count("pole")), (2, 2));
+    for row in rows.iter().filter(|r| r["type"] == "zero") {
+        assert!((row["re"].as_f64().unwrap() + 1.0).abs() < 1e-3);
+    }
+}
+
+#[cfg(feature = "plot")]
+#[test]
+fn plots_to_svg_files() {
+    let dir = tmp("plots");
+    let _ = std::fs::remove_dir_all(&dir);
+    stdout(&[
+        "plot",
+        "-f",
+        "highpass:80",
+        "-f",
+        "peak:2500:2:6",
+        "-o",
+        dir.to_str().unwrap(),
+    ]);
+    for name in &["bode.svg", "group_delay.svg", "impulse.svg", "poles.svg"] {
+        let svg = std::fs::read_to_string(dir.join(name)).unwrap();
+        assert!(
+            svg.starts_with("<svg") && svg.ends_with("</svg>\n"),
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn rejects_too_few_frequency_points() {
+    for &command in &["response", "plot"] {
+        let result = simdiir(&[command, "-f", "lowpass:1000", "--points", "1"]);
+        assert!(!result.status.success(), "{}", command);
+        assert!(
+            String::from_utf8_lossy(&result.stderr).contains("at least 2 frequency points"),
+            "{}: {:?}",
+            command,
+            result
+        );
+    }
+}
+
+#[test]
+fn accuracy_report() {
+    let json = stdout(&[
+        "compare",
+        "--report",
+        "-f",
+        "lowpass:500",
+        "-s",
+        "noise",
+        "-n",
+        "4096",
+        "--format",
+        "json",
+    ]);
+    let rows: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let rows = rows.as_array().unwrap();
+    assert!(rows.len() >= 4);
+    for row in rows {
+        assert_eq!(row["signal"], "noise");
+        assert!(row["snr_db"].as_f64().unwrap() > 100.0, "{}", row);
+        assert!(row["max_abs_error"].as_f64().unwrap() < 1e-5, "{}", row);
+    }
+    let backend = |row: &serde_json::Value| row["backend"].as_str().unwrap().to_string();
+    assert_eq!(backend(&rows[0]), "scalar");
+    assert_eq!(backend(&rows[1]), "scalar flush");
+
+    let text = stdout(&["compare", "--report", "-n", "1024"]);
+    assert!(text.starts_with("signal   backend"));
+    assert_eq!(text.lines().count(), 1 + 3 * rows.len());
+
+    // Too short for the error growth to mean anything
+    let text = stdout(&["compare", "--report", "-n", "4"]);
+    for line in text.lines().skip(1) {
+        assert!(line.ends_with("n/a"), "{}", line);
+    }
+}
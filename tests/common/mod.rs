@@ -0,0 +1,56 @@
+#![allow(dead_code)]
+
+use rand::{Rng, SeedableRng};
+
+use simdiir::coeffs::BiQuadCoeffs;
+
+pub const SAMPLE_RATE: f32 = 48000.0;
+
+pub fn noise(len: usize) -> Vec<f32> {
+    let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(3_031_657_322_766_356_513);
+    (0..len).map(|_| rng.gen::<f32>() * 2.0 - 1.0).collect()
+}
+
+// Direct Form I in double precision with the exact same (f32) coefficients,
+// so any difference from it is arithmetic rounding in the filter under test
+pub fn reference_df1(c: &BiQuadCoeffs, input: &[f32]) -> Vec<f64> {
+    let (a0, a1, a2) = (c.a0 as f64, c.a1 as f64, c.a2 as f64);
+    let (b1, b2) = (c.b1 as f64, c.b2 as f64);
+    let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+    input
+        .iter()
+        .map(|&x| {
+            let x = x as f64;
+            let y = a0 * x + a1 * x1 + a2 * x2 - b1 * y1 - b2 * y2;
+            x2 = x1;
+            x1 = x;
+            y2 = y1;
+            y1 = y;
+            y
+        })
+        .collect()
+}
+
+// Error power relative to the reference signal power, in dB
+pub fn noise_floor_db(output: &[f32], reference: &[f64]) -> f64 {
+    let signal = reference.iter().map(|r| r * r).sum::<f64>();
+    let error = output
+        .iter()
+        .zip(reference.iter())
+        .map(|(o, r)| (*o as f64 - r).powi(2))
+        .sum::<f64>();
+    10.0 * (error / signal).log10()
+}
+
+// |H(e^jw)| of the coefficients at the given frequency
+pub fn magnitude(c: &BiQuadCoeffs, frequency: f32) -> f64 {
+    let w = 2.0 * std::f64::consts::PI * frequency as f64 / SAMPLE_RATE as f64;
+    let (s1, c1) = w.sin_cos();
+    let (s2, c2) = (2.0 * w).sin_cos();
+    let (a0, a1, a2) = (c.a0 as f64, c.a1 as f64, c.a2 as f64);
+    let (b1, b2) = (c.b1 as f64, c.b2 as f64);
+
+    let num = (a0 + a1 * c1 + a2 * c2).hypot(a1 * s1 + a2 * s2);
+    let den = (1.0 + b1 * c1 + b2 * c2).hypot(b1 * s1 + b2 * s2);
+    num / den
+}
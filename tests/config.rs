@@ -0,0 +1,189 @@
+use simdiir::{
+    cascade::{Backend, BackendHint, Cascade},
+    coeffs::BiQuadCoeffs,
+    config::{ChainConfig, ConfigError},
+    design::{DesignMethod, FilterKind, FilterSpec, RealizationKind},
+};
+
+mod common;
+
+use common::{magnitude, noise, SAMPLE_RATE};
+
+const PRESET: &str = r#"
+backend = "portable4"
+
+[[filter]]
+type = "highpass"
+frequency = 30.0
+order = 5
+
+[[filter]]
+type = "peak"
+frequency = 120.0
+q = 4.0
+gain_db = -6.0
+realization = "normalized-lattice"
+
+[[filter]]
+type = "highshelf"
+frequency = 8000.0
+gain_db = 2.5
+method = "magnitude-matched"
+"#;
+
+#[test]
+fn toml_and_json_presets() {
+    let config = ChainConfig::from_toml(PRESET).unwrap();
+    assert_eq!(config.backend, BackendHint::Portable4);
+    assert_eq!(
+        config.filters,
+        vec![
+            FilterSpec::new(FilterKind::Highpass, 30.0).with_order(5),
+            FilterSpec::new(FilterKind::Peak, 120.0)
+                .with_q(4.0)
+                .with_gain_db(-6.0)
+                .with_realization(RealizationKind::NormalizedLattice),
+            FilterSpec::new(FilterKind::HighShelf, 8000.0)
+                .with_gain_db(2.5)
+                .with_method(DesignMethod::MagnitudeMatched),
+        ]
+    );
+
+    assert_eq!(ChainConfig::from_toml(&config.to_toml()).unwrap(), config);
+    assert_eq!(ChainConfig::from_json(&config.to_json()).unwrap(), config);
+
+    let json = r#"{ "filters": [{ "type": "notch", "frequency": 50, "q": 10 }] }"#;
+    let config = ChainConfig::from_json(json).unwrap();
+    assert_eq!(config.backend, BackendHint::Auto);
+    assert_eq!(
+        config.filters,
+        vec![FilterSpec::new(FilterKind::Notch, 50.0).with_q(10.0)]
+    );
+}
+
+#[test]
+fn errors_point_at_the_entry() {
+    let syntax = |text: &str| match ChainConfig::from_toml(text) {
+        Err(ConfigError::Syntax(message)) => message,
+        other => panic!("expected a syntax error, got {:?}", other),
+    };
+    assert!(syntax("[[filter]]\ntype = \"lowpass\"\n").contains("frequency"));
+    assert!(syntax("[[filter]]\ntype = \"bandstop\"\nfrequency = 1.0\n").contains("bandstop"));
+    assert!(syntax("[[filter]]\ntype = \"peak\"\nfrequency = 1.0\ngain = 3.0\n").contains("gain"));
+
+    let invalid = |text: &str| match ChainConfig::from_toml(text).unwrap().validate(SAMPLE_RATE) {
+        Err(e @ ConfigError::Filter { .. }) => e.to_string(),
+        other => panic!("expected an invalid filter, got {:?}", other),
+    };
+    let message = invalid(
+        "[[filter]]\ntype = \"lowpass\"\nfrequency = 100.0\n\
+         [[filter]]\ntype = \"lowpass\"\nfrequency = 30000.0\n",
+    );
+    assert!(
+        message.starts_with("filter 2 (lowpass at 30000 Hz)"),
+        "{}",
+        message
+    );
+    assert!(message.contains("Nyquist"), "{}", message);
+
+    for (entry, problem) in &[
+        (
+            "type = \"lowpass\"\nfrequency = 100.0\ngain_db = 3.0",
+            "no gain",
+        ),
+        (
+            "type = \"peak\"\nfrequency = 100.0\norder = 4",
+            "only have order 2",
+        ),
+        (
+            "type = \"lowpass\"\nfrequency = 100.0\norder = 4\nq = 2.0",
+            "Butterworth",
+        ),
+        (
+            "type = \"lowpass\"\nfrequency = 100.0\nq = 0.0",
+            "Q must be positive",
+        ),
+        (
+            "type = \"lowpass\"\nfrequency = 100.0\norder = 3\nrealization = \"coupled\"",
+            "real poles",
+        ),
+    ] {
+        let message = invalid(&format!("[[filter]]\n{}\n", entry));
+        assert!(message.starts_with("filter 1 ("), "{}", message);
+        assert!(message.contains(problem), "{}", message);
+    }
+}
+
+#[test]
+fn butterworth_orders() {
+    for order in 1..=8 {
+        for &kind in &[FilterKind::Lowpass, FilterKind::Highpass] {
+            let sections = FilterSpec::new(kind, 1000.0)
+                .with_order(order)
+                .sections(SAMPLE_RATE);
+            assert_eq!(sections.len() as u32, order.div_ceil(2));
+
+            let db = |f: f32| {
+                sections
+                    .iter()
+                    .map(|c| 20.0 * magnitude(c, f).log10())
+                    .sum::<f64>()
+            };
+            // Maximally flat, -3 dB at the cutoff
+            let pass = match kind {
+                FilterKind::Lowpass => 10.0,
+                _ => 20000.0,
+            };
+            assert!(db(pass).abs() < 0.01, "{:?} {}", kind, order);
+            assert!((db(1000.0) + 3.01).abs() < 0.01, "{:?} {}", kind, order);
+
+            // 6N dB per octave in the highpass stopband, where there's no
+            // frequency warping
+            if kind == FilterKind::Highpass {
+                let slope = db(62.5) - db(31.25);
+                assert!((slope - 6.02 * order as f64).abs() < 0.1, "{}", slope);
+            }
+        }
+    }
+}
+
+#[test]
+fn realizations_in_one_cascade() {
+    let config = ChainConfig::from_toml(PRESET).unwrap();
+    let direct = ChainConfig {
+        filters: config
+            .filters
+            .iter()
+            .map(|f| f.with_realization(RealizationKind::Df1))
+            .collect(),
+        ..config.clone()
+    };
+    let input = noise(4096);
+
+    let mut expected = input.clone();
+    direct
+        .build_with(Backend::Scalar, SAMPLE_RATE)
+        .unwrap()
+        .process(&mut expected);
+
+    let mut cascade = config.build(SAMPLE_RATE).unwrap();
+    assert_eq!(cascade.backend(), Backend::Portable4);
+    // Three sections for the fifth order highpass, the peak and the shelf
+    assert_eq!(cascade.len(), 5);
+    let mut output = input;
+    cascade.process(&mut output);
+
+    for (o, e) in output.iter().zip(expected.iter()) {
+        assert!((o - e).abs() < 1e-3, "{} != {}", o, e);
+    }
+}
+
+#[test]
+fn unavailable_hints_fall_back() {
+    let backend = BackendHint::Avx.resolve();
+    assert!(backend.is_supported());
+    assert_eq!(BackendHint::Auto.resolve(), Backend::detect());
+
+    let sections = [BiQuadCoeffs::lowpass(SAMPLE_RATE, 100.0, 0.707)];
+    assert_eq!(Cascade::new(backend, &sections).len(), 1);
+}
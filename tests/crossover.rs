@@ -0,0 +1,164 @@
+use simdiir::{
+    cascade::{Backend, Cascade},
+    crossover::{Crossover, LinkwitzRiley},
+    response::{response, Complex},
+};
+
+mod common;
+
+use common::{noise, SAMPLE_RATE};
+
+const SPLITS: [f32; 4] = [120.0, 800.0, 3000.0, 9000.0];
+
+fn band_outputs(crossover: &mut Crossover, input: &[f32]) -> Vec<Vec<f32>> {
+    let mut bands = vec![vec![0.0; input.len()]; crossover.bands()];
+    let mut slices = bands.iter_mut().map(Vec::as_mut_slice).collect::<Vec<_>>();
+    crossover.process(input, &mut slices);
+    bands
+}
+
+#[test]
+fn sides_are_6db_down_and_sum_to_allpass() {
+    for kind in LinkwitzRiley::ALL.iter() {
+        let f = 1000.0;
+        let lowpass = kind.lowpass(SAMPLE_RATE, f);
+        let highpass = kind.highpass(SAMPLE_RATE, f);
+        let allpass = kind.allpass(SAMPLE_RATE, f);
+        // Butterworth of half the order, twice
+        assert_eq!(lowpass.len(), (kind.order() as usize / 2).div_ceil(2) * 2);
+
+        let low = response(&lowpass, SAMPLE_RATE, f).norm();
+        let high = response(&highpass, SAMPLE_RATE, f).norm();
+        assert!((20.0 * low.log10() + 6.02).abs() < 0.01, "{} {}", kind, low);
+        assert!(
+            (20.0 * high.log10() + 6.02).abs() < 0.01,
+            "{} {}",
+            kind,
+            high
+        );
+
+        for &f in &[20.0, 300.0, 1000.0, 4000.0, 20000.0] {
+            let sum = response(&lowpass, SAMPLE_RATE, f) + response(&highpass, SAMPLE_RATE, f);
+            let expected = response(&allpass, SAMPLE_RATE, f);
+            assert!((sum - expected).norm() < 1e-4, "{} at {} Hz", kind, f);
+            assert!((expected.norm() - 1.0).abs() < 1e-5);
+        }
+    }
+}
+
+#[test]
+fn bands_sum_to_allpass() {
+    let input = noise(4096);
+    for kind in LinkwitzRiley::ALL.iter() {
+        for bands in 2..=5 {
+            let splits = &SPLITS[..bands - 1];
+            let mut crossover =
+                Crossover::new(Backend::Scalar, *kind, SAMPLE_RATE, splits).unwrap();
+            let outputs = band_outputs(&mut crossover, &input);
+
+            let allpass = splits
+                .iter()
+                .flat_map(|f| kind.allpass(SAMPLE_RATE, *f))
+                .collect::<Vec<_>>();
+            let mut expected = input.clone();
+            Cascade::new(Backend::Scalar, &allpass).process(&mut expected);
+
+            for (n, expected) in expected.iter().enumerate() {
+                let sum = outputs.iter().map(|band| band[n]).sum::<f32>();
+                assert!(
+                    (sum - expected).abs() < 1e-3,
+                    "{} with {} bands, sample {}: {} != {}",
+                    kind,
+                    bands,
+                    n,
+                    sum,
+                    expected
+                );
+            }
+
+            // And the same holds for the responses of the band paths
+            for &f in &[50.0, 120.0, 2000.0, 15000.0] {
+                let sum = (0..bands)
+                    .map(|b| response(crossover.band_sections(b), SAMPLE_RATE, f))
+                    .fold(Complex::ZERO, |a, b| a + b);
+                assert!(
+                    (sum.norm() - 1.0).abs() < 1e-3,
+                    "{} at {} Hz: {}",
+                    kind,
+                    f,
+                    sum.norm()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn bands_pass_their_range() {
+    let crossover =
+        Crossover::new(Backend::Scalar, LinkwitzRiley::Lr4, SAMPLE_RATE, &SPLITS).unwrap();
+    let centers = [40.0, 310.0, 1550.0, 5200.0, 16000.0];
+    for (band, center) in centers.iter().enumerate() {
+        for other in 0..crossover.bands() {
+            let gain = response(crossover.band_sections(other), SAMPLE_RATE, *center).norm();
+            if other == band {
+                assert!(gain > 0.8, "band {} at {} Hz: {}", other, center, gain);
+            } else {
+                assert!(gain < 0.15, "band {} at {} Hz: {}", other, center, gain);
+            }
+        }
+    }
+}
+
+#[test]
+fn backends_match_scalar() {
+    let input = noise(4096);
+    for kind in LinkwitzRiley::ALL.iter() {
+        let expected = band_outputs(
+            &mut Crossover::new(Backend::Scalar, *kind, SAMPLE_RATE, &SPLITS).unwrap(),
+            &input,
+        );
+        for backend in Backend::ALL.iter().filter(|b| b.is_supported()) {
+            // In buffers that don't fill the backend's blocks
+            let mut crossover = Crossover::new(*backend, *kind, SAMPLE_RATE, &SPLITS).unwrap();
+            let mut actual = vec![Vec::new(); crossover.bands()];
+            for chunk in input.chunks(7) {
+                for (band, out) in actual.iter_mut().zip(band_outputs(&mut crossover, chunk)) {
+                    band.extend(out);
+                }
+            }
+            for (band, (a, e)) in actual.iter().zip(expected.iter()).enumerate() {
+                let error = a
+                    .iter()
+                    .zip(e.iter())
+                    .map(|(a, e)| (a - e).abs())
+                    .fold(0.0, f32::max);
+                assert!(
+                    error < 1e-3,
+                    "{} {} band {}: {}",
+                    backend,
+                    kind,
+                    band,
+                    error
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn invalid_splits() {
+    let error = |splits: &[f32]| {
+        Crossover::new(Backend::Scalar, LinkwitzRiley::Lr4, SAMPLE_RATE, splits)
+            .err()
+            .unwrap()
+            .to_string()
+    };
+    assert!(error(&[]).contains("2 to 5 bands"));
+    assert!(error(&[100.0, 200.0, 300.0, 400.0, 500.0]).contains("2 to 5 bands"));
+    assert!(error(&[1000.0, 500.0]).contains("increasing"));
+    assert!(error(&[1000.0, 1000.0]).contains("increasing"));
+    assert!(error(&[30000.0]).contains("Nyquist"));
+    assert!(error(&[-5.0]).contains("positive"));
+    assert_eq!("lr8".parse::<LinkwitzRiley>(), Ok(LinkwitzRiley::Lr8));
+}
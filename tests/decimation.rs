@@ -0,0 +1,261 @@
+use std::f64::consts::PI;
+
+use simdiir::{
+    coeffs::BiQuadCoeffs,
+    decimation::{AntiAlias, AntiAliasKind, Decimator, Interpolator},
+    response::response,
+};
+
+mod common;
+
+use common::{noise, noise_floor_db, SAMPLE_RATE};
+
+fn db(x: f64) -> f64 {
+    20.0 * x.log10()
+}
+
+// Chebyshev needs a wider transition band to stay within the maximum order
+fn spec(kind: AntiAliasKind) -> AntiAlias {
+    match kind {
+        AntiAliasKind::Chebyshev => AntiAlias::new(kind).with_passband(0.5),
+        AntiAliasKind::Elliptic => AntiAlias::new(kind),
+    }
+}
+
+// The sections one after the other in Direct Form I, double precision all
+// the way through
+fn reference(sections: &[BiQuadCoeffs], input: &[f32]) -> Vec<f64> {
+    let mut signal = input.iter().map(|x| *x as f64).collect::<Vec<_>>();
+    for c in sections {
+        let (a0, a1, a2) = (c.a0 as f64, c.a1 as f64, c.a2 as f64);
+        let (b1, b2) = (c.b1 as f64, c.b2 as f64);
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        for v in signal.iter_mut() {
+            let y = a0 * *v + a1 * x1 + a2 * x2 - b1 * y1 - b2 * y2;
+            x2 = x1;
+            x1 = *v;
+            y2 = y1;
+            y1 = y;
+            *v = y;
+        }
+    }
+    signal
+}
+
+// Amplitude of the component at `frequency`, over a whole number of cycles
+// so nothing leaks between bins
+fn amplitude(signal: &[f32], frequency: f64, sample_rate: f64) -> f64 {
+    let w = 2.0 * PI * frequency / sample_rate;
+    let (re, im) = signal
+        .iter()
+        .enumerate()
+        .fold((0.0, 0.0), |(re, im), (n, x)| {
+            let (s, c) = (w * n as f64).sin_cos();
+            (re + *x as f64 * c, im - *x as f64 * s)
+        });
+    2.0 * re.hypot(im) / signal.len() as f64
+}
+
+fn tone(frequency: f64, sample_rate: f64, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|n| (2.0 * PI * frequency * n as f64 / sample_rate).sin() as f32)
+        .collect()
+}
+
+#[test]
+fn lowpass_meets_the_spec() {
+    for kind in AntiAliasKind::ALL.iter() {
+        for factor in [2, 4, 6, 48] {
+            let spec = spec(*kind);
+            let sections = spec.sections(factor).unwrap();
+            let nyquist = SAMPLE_RATE / 2.0 / factor as f32;
+
+            for i in 0..=100 {
+                let f = nyquist * spec.passband as f32 * i as f32 / 100.0;
+                let gain = db(response(&sections, SAMPLE_RATE, f).norm());
+                assert!(
+                    gain < 0.01 && gain > -spec.ripple_db - 0.01,
+                    "{} by {}: {:.3} dB at {} Hz",
+                    kind,
+                    factor,
+                    gain,
+                    f
+                );
+            }
+            for i in 0..=1000 {
+                let f = nyquist + (SAMPLE_RATE / 2.0 - nyquist) * i as f32 / 1000.0;
+                let gain = db(response(&sections, SAMPLE_RATE, f).norm());
+                assert!(
+                    gain < -spec.attenuation_db + 0.1,
+                    "{} by {}: {:.2} dB at {} Hz",
+                    kind,
+                    factor,
+                    gain,
+                    f
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn elliptic_is_the_cheaper_lowpass() {
+    for factor in [2, 4, 48] {
+        let elliptic = AntiAlias::new(AntiAliasKind::Elliptic).with_passband(0.5);
+        let chebyshev = AntiAlias::new(AntiAliasKind::Chebyshev).with_passband(0.5);
+        assert!(elliptic.order(factor).unwrap() < chebyshev.order(factor).unwrap());
+        assert!(elliptic.with_passband(0.8).order(factor).is_ok());
+    }
+    // More attenuation or ripple to spare trades against the order
+    let spec = AntiAlias::default();
+    let order = spec.order(4).unwrap();
+    assert!(spec.with_attenuation_db(100.0).order(4).unwrap() > order);
+    assert!(spec.with_ripple_db(1.0).order(4).unwrap() < order);
+}
+
+#[test]
+fn decimator_is_filtering_then_dropping() {
+    let input = noise(48 * 512);
+    for kind in AntiAliasKind::ALL.iter() {
+        for factor in [2, 3, 4, 6, 12, 24, 48] {
+            let mut decimator = Decimator::new(factor, spec(*kind)).unwrap();
+            let mut output = vec![0.0; input.len() / factor];
+            decimator.process(&input, &mut output);
+
+            let reference = reference(decimator.sections(), &input)
+                .into_iter()
+                .step_by(factor)
+                .collect::<Vec<_>>();
+            let floor = noise_floor_db(&output, &reference);
+            assert!(floor < -140.0, "{} by {}: {:.1} dB", kind, factor, floor);
+        }
+    }
+}
+
+#[test]
+fn decimator_rejects_aliases() {
+    // 48 kHz down to 1 kHz: the passband ends at 400 Hz and everything from
+    // 500 Hz up would fold back onto the band
+    let spec = AntiAlias::default();
+    for f in [300.0, 500.0, 520.0, 700.0, 1500.0, 3100.0, 10300.0, 23900.0] {
+        let mut decimator = Decimator::new(48, spec).unwrap();
+        let input = tone(f, 48000.0, 48000 * 2);
+        let mut output = vec![0.0; 2000];
+        decimator.process(&input, &mut output);
+
+        let settled = &output[1000..];
+        let rms = (settled.iter().map(|x| (*x as f64).powi(2)).sum::<f64>() / settled.len() as f64
+            * 2.0)
+            .sqrt();
+        if f < 500.0 {
+            assert!(
+                db(rms).abs() < spec.ripple_db,
+                "{} Hz: {:.3} dB",
+                f,
+                db(rms)
+            );
+        } else {
+            assert!(
+                db(rms) < -spec.attenuation_db + 0.5,
+                "{} Hz: {:.2} dB",
+                f,
+                db(rms)
+            );
+        }
+    }
+}
+
+#[test]
+fn interpolator_rejects_images() {
+    let spec = AntiAlias::default();
+    for (factor, f) in [(2, 9000.0), (4, 3000.0), (48, 300.0)] {
+        let low = 48000.0 / factor as f64;
+        let mut interpolator = Interpolator::new(factor, spec).unwrap();
+        let input = tone(f, low, 2 * low as usize);
+        let mut output = vec![0.0; input.len() * factor];
+        interpolator.process(&input, &mut output);
+
+        let settled = &output[48000..];
+        let gain = db(amplitude(settled, f, 48000.0));
+        assert!(gain.abs() < spec.ripple_db, "by {}: {:.3} dB", factor, gain);
+        for k in 1..factor {
+            for image in [k as f64 * low - f, k as f64 * low + f] {
+                if image < 24000.0 {
+                    let level = db(amplitude(settled, image, 48000.0));
+                    assert!(
+                        level < -spec.attenuation_db + 0.5,
+                        "by {}: {:.2} dB at {} Hz",
+                        factor,
+                        level,
+                        image
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn interpolator_is_stuffing_then_filtering() {
+    let input = noise(512);
+    for kind in AntiAliasKind::ALL.iter() {
+        for factor in [2, 3, 4, 12, 24, 48] {
+            let mut interpolator = Interpolator::new(factor, spec(*kind)).unwrap();
+            let mut output = vec![0.0; input.len() * factor];
+            interpolator.process(&input, &mut output);
+
+            let mut stuffed = vec![0.0; output.len()];
+            for (s, x) in stuffed.iter_mut().step_by(factor).zip(&input) {
+                *s = *x * factor as f32;
+            }
+            let reference = reference(interpolator.sections(), &stuffed);
+            let floor = noise_floor_db(&output, &reference);
+            assert!(floor < -140.0, "{} by {}: {:.1} dB", kind, factor, floor);
+        }
+    }
+}
+
+#[test]
+fn blocks_and_reset() {
+    let spec = AntiAlias::default();
+    let factor = 6;
+    let input = noise(factor * 256);
+
+    let mut decimator = Decimator::new(factor, spec).unwrap();
+    let mut whole = vec![0.0; input.len() / factor];
+    decimator.process(&input, &mut whole);
+    decimator.reset();
+    let mut blocks = vec![0.0; whole.len()];
+    for (input, output) in input.chunks(factor * 7).zip(blocks.chunks_mut(7)) {
+        decimator.process(input, output);
+    }
+    assert_eq!(whole, blocks);
+
+    let mut interpolator = Interpolator::new(factor, spec).unwrap();
+    let mut whole = vec![0.0; input.len() * factor];
+    interpolator.process(&input, &mut whole);
+    interpolator.reset();
+    let mut blocks = vec![0.0; whole.len()];
+    for (input, output) in input.chunks(3).zip(blocks.chunks_mut(3 * factor)) {
+        interpolator.process(input, output);
+    }
+    assert_eq!(whole, blocks);
+}
+
+#[test]
+fn names_and_errors() {
+    for kind in AntiAliasKind::ALL.iter() {
+        assert_eq!(kind.name().parse::<AntiAliasKind>().unwrap(), *kind);
+        assert_eq!(kind.to_string(), kind.name());
+    }
+    assert!("butterworth".parse::<AntiAliasKind>().is_err());
+    assert_eq!(AntiAlias::default().kind, AntiAliasKind::Elliptic);
+
+    let spec = AntiAlias::default();
+    assert!(Decimator::new(1, spec).is_err());
+    assert!(Interpolator::new(0, spec).is_err());
+    assert!(Decimator::new(4, spec.with_passband(1.0)).is_err());
+    assert!(Decimator::new(4, spec.with_ripple_db(0.0)).is_err());
+    assert!(Interpolator::new(4, spec.with_attenuation_db(0.05)).is_err());
+    assert!(Decimator::new(4, spec.with_passband(0.99)).is_err());
+}
@@ -0,0 +1,102 @@
+#![cfg(target_arch = "x86_64")]
+
+use std::hint::black_box;
+
+use simdiir::denormals::ScopedFlushDenormals;
+
+const FLUSH_TO_ZERO: u32 = 1 << 15;
+const DENORMALS_ARE_ZERO: u32 = 1 << 6;
+const ROUND_TOWARD_ZERO: u32 = 3 << 13;
+const DIVIDE_BY_ZERO_MASK: u32 = 1 << 9;
+const DIVIDE_BY_ZERO_FLAG: u32 = 1 << 2;
+
+fn mxcsr() -> u32 {
+    let mut mxcsr = 0u32;
+    unsafe {
+        std::arch::asm!("stmxcsr [{}]", in(reg) &mut mxcsr, options(nostack, preserves_flags));
+    }
+    mxcsr
+}
+
+fn set_mxcsr(mxcsr: u32) {
+    unsafe {
+        std::arch::asm!(
+            "ldmxcsr [{}]",
+            in(reg) &mxcsr,
+            options(nostack, readonly, preserves_flags)
+        );
+    }
+}
+
+#[test]
+fn restores_the_prior_register() {
+    let default = mxcsr();
+    for &prior in &[
+        default & !(FLUSH_TO_ZERO | DENORMALS_ARE_ZERO),
+        default | FLUSH_TO_ZERO,
+        default | FLUSH_TO_ZERO | DENORMALS_ARE_ZERO,
+        (default | ROUND_TOWARD_ZERO) & !DIVIDE_BY_ZERO_MASK,
+    ] {
+        set_mxcsr(prior);
+        {
+            let guard = ScopedFlushDenormals::with_daz();
+            assert!(guard.is_active());
+            // Only the flush bits change
+            assert_eq!(mxcsr(), prior | FLUSH_TO_ZERO | DENORMALS_ARE_ZERO);
+        }
+        assert_eq!(mxcsr(), prior, "{:#x}", prior);
+    }
+    set_mxcsr(default);
+}
+
+#[test]
+fn clears_exception_flags_raised_inside() {
+    let default = mxcsr();
+    set_mxcsr(default & !DIVIDE_BY_ZERO_FLAG);
+    {
+        let _guard = ScopedFlushDenormals::new();
+        assert!(black_box(1.0f32) / black_box(0.0f32) == f32::INFINITY);
+        assert_ne!(mxcsr() & DIVIDE_BY_ZERO_FLAG, 0);
+    }
+    // The whole register is written back, status flags included
+    assert_eq!(mxcsr(), default & !DIVIDE_BY_ZERO_FLAG);
+    set_mxcsr(default);
+}
+
+#[test]
+fn nested_scopes_unwind() {
+    let default = mxcsr() & !(FLUSH_TO_ZERO | DENORMALS_ARE_ZERO);
+    set_mxcsr(default);
+    {
+        let _outer = ScopedFlushDenormals::new();
+        assert_eq!(mxcsr(), default | FLUSH_TO_ZERO);
+        {
+            let _inner = ScopedFlushDenormals::with_daz();
+            assert_eq!(mxcsr(), default | FLUSH_TO_ZERO | DENORMALS_ARE_ZERO);
+        }
+        assert_eq!(mxcsr(), default | FLUSH_TO_ZERO);
+    }
+    assert_eq!(mxcsr(), default);
+}
+
+#[test]
+fn daz_only_when_asked() {
+    let default = mxcsr() & !(FLUSH_TO_ZERO | DENORMALS_ARE_ZERO);
+    set_mxcsr(default);
+    {
+        let _guard = ScopedFlushDenormals::new();
+        assert_eq!(mxcsr() & DENORMALS_ARE_ZERO, 0);
+        // A denormal result comes out as zero
+        assert_eq!(black_box(f32::MIN_POSITIVE) / black_box(4.0f32), 0.0);
+    }
+    {
+        let _guard = ScopedFlushDenormals::with_daz();
+        assert_ne!(mxcsr() & DENORMALS_ARE_ZERO, 0);
+        // A denormal input reads as zero
+        let denormal = f32::from_bits(1);
+        assert_eq!(black_box(denormal) * black_box(1.0f32), 0.0);
+    }
+    // Exactly as before, including the flags raised along the way
+    assert_eq!(mxcsr(), default);
+    assert!(black_box(f32::from_bits(1)) * black_box(1.0f32) != 0.0);
+}
@@ -0,0 +1,242 @@
+use simdiir::{
+    analog::Prototype,
+    coeffs::BiQuadCoeffs,
+    design::{DesignMethod, FilterKind, FilterSpec},
+    response::response,
+};
+
+const SAMPLE_RATE: f32 = 44100.0;
+
+fn db(x: f64) -> f64 {
+    20.0 * x.log10()
+}
+
+fn specs(frequency: f32) -> Vec<FilterSpec> {
+    vec![
+        FilterSpec::new(FilterKind::Lowpass, frequency),
+        FilterSpec::new(FilterKind::Lowpass, frequency).with_q(0.5),
+        FilterSpec::new(FilterKind::Lowpass, frequency).with_order(5),
+        FilterSpec::new(FilterKind::Highpass, frequency).with_q(2.0),
+        FilterSpec::new(FilterKind::Highpass, frequency).with_order(3),
+        FilterSpec::new(FilterKind::Bandpass, frequency).with_q(2.0),
+        FilterSpec::new(FilterKind::Notch, frequency).with_q(2.0),
+        FilterSpec::new(FilterKind::Peak, frequency)
+            .with_q(2.0)
+            .with_gain_db(6.0),
+        FilterSpec::new(FilterKind::Peak, frequency)
+            .with_q(0.5)
+            .with_gain_db(-12.0),
+        FilterSpec::new(FilterKind::LowShelf, frequency).with_gain_db(-6.0),
+        FilterSpec::new(FilterKind::HighShelf, frequency).with_gain_db(6.0),
+        FilterSpec::new(FilterKind::Allpass, frequency),
+        FilterSpec::new(FilterKind::Allpass, frequency).with_order(2),
+    ]
+}
+
+// The analog prototype's response at the digital filter's frequency, in dB
+fn analog_db(spec: &FilterSpec, frequency: f32) -> f64 {
+    let ratio = (frequency / spec.frequency) as f64;
+    spec.prototypes()
+        .iter()
+        .map(|p| db(p.response(ratio).norm()))
+        .sum()
+}
+
+fn frequencies(high: f32) -> impl Iterator<Item = f32> {
+    (0..=200)
+        .map(|k| 20.0 * 1000f32.powf(k as f32 / 200.0))
+        .filter(move |f| *f <= high)
+}
+
+#[test]
+fn bilinear_prototypes_are_the_cookbook() {
+    for &frequency in &[50.0, 1000.0, 15000.0] {
+        for spec in specs(frequency) {
+            let sections = spec.sections(SAMPLE_RATE);
+            let prototypes = spec.prototypes();
+            assert_eq!(sections.len(), prototypes.len());
+            for (c, p) in sections.iter().zip(prototypes.iter()) {
+                let d = p.discretize(DesignMethod::Bilinear, SAMPLE_RATE, frequency);
+                let pairs = [
+                    (c.a0, d.a0),
+                    (c.a1, d.a1),
+                    (c.a2, d.a2),
+                    (c.b1, d.b1),
+                    (c.b2, d.b2),
+                ];
+                for (x, y) in pairs.iter() {
+                    assert!(
+                        (x - y).abs() <= 1e-5 * x.abs().max(1.0),
+                        "{}: {:?} {:?}",
+                        spec,
+                        c,
+                        d
+                    );
+                }
+            }
+        }
+    }
+}
+
+// Below an eighth of the sample rate, wherever the prototype is within 40 dB
+// of full scale. Impulse invariance only for the responses it suits, and
+// only in their passband: aliasing sets a floor under the stopband.
+#[test]
+fn close_to_the_prototype() {
+    for method in &[
+        DesignMethod::MatchedZ,
+        DesignMethod::ImpulseInvariant,
+        DesignMethod::MagnitudeMatched,
+    ] {
+        for &frequency in &[100.0, 1000.0, 4000.0] {
+            for spec in specs(frequency) {
+                let (floor, tolerance) = match method {
+                    DesignMethod::ImpulseInvariant => match spec.kind {
+                        FilterKind::Lowpass | FilterKind::Bandpass if frequency <= 1000.0 => {
+                            (-10.0, 0.5)
+                        }
+                        _ => continue,
+                    },
+                    // The zeros at infinity that matched-Z leaves out cost it
+                    // a dB or more past a cutoff of a tenth of the sample rate
+                    DesignMethod::MatchedZ if frequency > 1000.0 => continue,
+                    DesignMethod::MatchedZ => (-40.0, 1.0),
+                    // Three fitting points leave a broad bell reaching past
+                    // Nyquist up to two thirds of a dB out
+                    _ if spec.kind == FilterKind::Peak && spec.q < 1.0 => (-40.0, 0.7),
+                    _ => (-40.0, 0.25),
+                };
+
+                let sections = spec.with_method(*method).sections(SAMPLE_RATE);
+                for f in frequencies(SAMPLE_RATE / 8.0) {
+                    let expected = analog_db(&spec, f);
+                    if expected < floor {
+                        continue;
+                    }
+                    let actual = db(response(&sections, SAMPLE_RATE, f).norm());
+                    assert!(
+                        (actual - expected).abs() < tolerance,
+                        "{} {} at {} Hz: {} dB, expected {}",
+                        method,
+                        spec,
+                        f,
+                        actual,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn no_cramping_near_nyquist() {
+    let shelf = FilterSpec::new(FilterKind::HighShelf, 10000.0).with_gain_db(6.0);
+    let bell = FilterSpec::new(FilterKind::Peak, 10000.0)
+        .with_q(2.0)
+        .with_gain_db(6.0);
+    for spec in &[shelf, bell] {
+        let error = |method: DesignMethod| {
+            let sections = spec.with_method(method).sections(SAMPLE_RATE);
+            frequencies(20000.0)
+                .map(|f| {
+                    (db(response(&sections, SAMPLE_RATE, f).norm()) - analog_db(spec, f)).abs()
+                })
+                .fold(0.0, f64::max)
+        };
+        assert!(error(DesignMethod::Bilinear) > 0.5, "{}", spec);
+        assert!(error(DesignMethod::MagnitudeMatched) < 0.25, "{}", spec);
+        assert!(error(DesignMethod::MatchedZ) < 0.25, "{}", spec);
+    }
+}
+
+#[test]
+fn stable_with_the_right_structure() {
+    for method in DesignMethod::ALL.iter() {
+        for &frequency in &[20.0, 1000.0, 10000.0, 19000.0] {
+            for spec in specs(frequency) {
+                let spec = spec.with_method(*method);
+                let sections = spec.sections(SAMPLE_RATE);
+                for c in &sections {
+                    assert!(
+                        c.b2.abs() < 1.0 && c.b1.abs() < 1.0 + c.b2,
+                        "{} {}: {:?}",
+                        method,
+                        spec,
+                        c
+                    );
+                }
+
+                let gain = |f: f32| response(&sections, SAMPLE_RATE, f).norm();
+                match spec.kind {
+                    FilterKind::Allpass if *method != DesignMethod::ImpulseInvariant => {
+                        for f in frequencies(20000.0) {
+                            assert!((gain(f) - 1.0).abs() < 1e-4, "{} {}", method, spec);
+                        }
+                    }
+                    // The zeros stay on the unit circle, at the exact frequency.
+                    // At 20 Hz f32 coefficients only get them close.
+                    FilterKind::Notch
+                        if *method != DesignMethod::ImpulseInvariant && frequency > 20.0 =>
+                    {
+                        assert!(db(gain(frequency)) < -60.0, "{} {}", method, spec);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn prototypes_and_names() {
+    // The shelves meet at half their gain at the cutoff
+    let shelf = Prototype::high_shelf(std::f64::consts::FRAC_1_SQRT_2, 6.0);
+    assert!((db(shelf.response(1.0).norm()) - 3.0).abs() < 1e-9);
+    assert!((db(shelf.response(1e6).norm()) - 6.0).abs() < 1e-6);
+    assert!(db(Prototype::notch(2.0).response(1.0).norm()) < -200.0);
+
+    for method in DesignMethod::ALL.iter() {
+        assert_eq!(method.name().parse::<DesignMethod>().unwrap(), *method);
+    }
+    assert!("bilinear-prewarped".parse::<DesignMethod>().is_err());
+    assert_eq!(
+        FilterSpec::new(FilterKind::Lowpass, 1000.0).method,
+        DesignMethod::Bilinear
+    );
+}
+
+// Deep cuts and shelves close to Nyquist have no numerator matching all
+// three points, and used to come out as NaN
+#[test]
+fn magnitude_matched_is_finite_and_stable() {
+    for kind in FilterKind::ALL.iter() {
+        for &frequency in &[20.0, 1000.0, 10000.0, 15000.0, 18000.0, 20000.0] {
+            for &q in &[0.1, 0.5, 0.707, 2.0, 10.0] {
+                for &gain_db in &[-24.0, -6.0, 0.0, 6.0, 24.0] {
+                    let spec = FilterSpec::new(*kind, frequency)
+                        .with_q(q)
+                        .with_gain_db(gain_db)
+                        .with_method(DesignMethod::MagnitudeMatched);
+                    for c in spec.sections(SAMPLE_RATE) {
+                        assert!(
+                            [c.a0, c.a1, c.a2, c.b1, c.b2].iter().all(|c| c.is_finite())
+                                && c.b2.abs() < 1.0
+                                && c.b1.abs() < 1.0 + c.b2,
+                            "{}: {:?}",
+                            spec,
+                            c
+                        );
+                        let has_gain = matches!(
+                            kind,
+                            FilterKind::Peak | FilterKind::LowShelf | FilterKind::HighShelf
+                        );
+                        if has_gain && gain_db == 0.0 {
+                            assert_eq!(c, BiQuadCoeffs::identity(), "{}", spec);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
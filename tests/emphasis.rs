@@ -0,0 +1,115 @@
+use simdiir::{
+    cascade::{Backend, Cascade},
+    emphasis::Emphasis,
+    response::response,
+};
+
+mod common;
+
+use common::noise;
+
+const SAMPLE_RATES: [f32; 4] = [32000.0, 44100.0, 48000.0, 96000.0];
+
+fn db(x: f64) -> f64 {
+    20.0 * x.log10()
+}
+
+fn frequencies(sample_rate: f32) -> impl Iterator<Item = f32> {
+    (0..=300)
+        .map(|k| 20.0 * 1000f32.powf(k as f32 / 300.0))
+        .filter(move |f| *f < 0.45 * sample_rate)
+}
+
+#[test]
+fn riaa_table() {
+    // The RIAA playback curve as usually tabled, to a hundredth of a dB
+    let table = [
+        (20.0, 19.27),
+        (50.0, 16.95),
+        (100.0, 13.09),
+        (200.0, 8.22),
+        (500.0, 2.65),
+        (1000.0, 0.0),
+        (2000.0, -2.59),
+        (5000.0, -8.21),
+        (10000.0, -13.73),
+        (20000.0, -19.62),
+    ];
+    for (f, expected) in table.iter() {
+        let db = Emphasis::Riaa.reference_db(*f);
+        assert!((db - expected).abs() < 0.01, "{} dB at {} Hz", db, f);
+    }
+}
+
+#[test]
+fn close_to_analog_up_to_20_khz() {
+    for emphasis in Emphasis::ALL.iter() {
+        for &sample_rate in SAMPLE_RATES.iter() {
+            let deemphasis = emphasis.deemphasis(sample_rate);
+            let preemphasis = emphasis.preemphasis(sample_rate);
+            // Looser at 32 kHz, where 15 kHz is most of the way to Nyquist
+            let tolerance = if sample_rate < 44100.0 { 0.2 } else { 0.1 };
+
+            for f in frequencies(sample_rate) {
+                let expected = emphasis.reference_db(f as f64);
+                let de = db(response(&deemphasis, sample_rate, f).norm());
+                let pre = db(response(&preemphasis, sample_rate, f).norm());
+                assert!(
+                    (de - expected).abs() < tolerance && (pre + expected).abs() < tolerance,
+                    "{} at {} Hz, {} Hz: {} and {} dB, expected {}",
+                    emphasis,
+                    sample_rate,
+                    f,
+                    de,
+                    pre,
+                    expected
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn preemphasis_undoes_deemphasis() {
+    let input = noise(4096);
+    for emphasis in Emphasis::ALL.iter() {
+        let mut sections = emphasis.preemphasis(44100.0);
+        sections.extend(emphasis.deemphasis(44100.0));
+        for c in &sections {
+            assert!(c.b2.abs() < 1.0, "{} is unstable", emphasis);
+        }
+
+        let mut output = input.clone();
+        Cascade::new(Backend::Scalar, &sections).process(&mut output);
+        let error = input
+            .iter()
+            .zip(output.iter())
+            .fold(0.0f32, |max, (x, y)| max.max((x - y).abs()));
+        assert!(error < 1e-3, "{}: {}", emphasis, error);
+    }
+}
+
+#[test]
+fn backends_agree() {
+    let input = noise(4096);
+    for emphasis in Emphasis::ALL.iter() {
+        let sections = emphasis.deemphasis(44100.0);
+        let mut expected = input.clone();
+        Cascade::new(Backend::Scalar, &sections).process(&mut expected);
+        for backend in Backend::ALL.iter().filter(|b| b.is_supported()) {
+            let mut output = input.clone();
+            Cascade::new(*backend, &sections).process(&mut output);
+            for (x, y) in expected.iter().zip(output.iter()) {
+                assert!((x - y).abs() < 1e-3, "{} on {}", emphasis, backend);
+            }
+        }
+    }
+}
+
+#[test]
+fn names() {
+    for emphasis in Emphasis::ALL.iter() {
+        assert_eq!(emphasis.name().parse::<Emphasis>().unwrap(), *emphasis);
+    }
+    assert!("riaa2".parse::<Emphasis>().is_err());
+}
@@ -0,0 +1,191 @@
+use simdiir::{
+    cascade::{Backend, Cascade},
+    eq::{BandKind, EqBand, ParametricEq, Slope},
+    response::{log_frequencies, response},
+};
+
+mod common;
+
+use common::{noise, noise_floor_db, SAMPLE_RATE};
+
+fn eq(backend: Backend) -> ParametricEq {
+    let mut eq = ParametricEq::with_backend(backend, SAMPLE_RATE);
+    eq.add_band(EqBand::low_cut(40.0, Slope::Db24)).unwrap();
+    eq.add_band(EqBand::low_shelf(120.0, 3.0)).unwrap();
+    eq.add_band(EqBand::bell(1000.0, 2.0, -6.0)).unwrap();
+    eq.add_band(EqBand::high_shelf(8000.0, 2.0).with_enabled(false))
+        .unwrap();
+    eq.add_band(EqBand::high_cut(18000.0, Slope::Db12).with_q(0.9))
+        .unwrap();
+    eq
+}
+
+fn db(x: f64) -> f64 {
+    20.0 * x.log10()
+}
+
+#[test]
+fn response_is_product_of_enabled_bands() {
+    let eq = eq(Backend::Scalar);
+    assert_eq!(eq.len(), 5);
+    assert_eq!(eq.sections().len(), 2 + 1 + 1 + 1);
+
+    for f in log_frequencies(20.0, 20000.0, 30) {
+        let expected = eq
+            .bands()
+            .filter(|band| band.enabled)
+            .map(|band| response(&band.spec().sections(SAMPLE_RATE), SAMPLE_RATE, f))
+            .fold(response(&[], SAMPLE_RATE, f), |a, b| a * b);
+        let actual = eq.response(f);
+        assert!((actual - expected).norm() < 1e-9, "{} Hz", f);
+    }
+
+    let table = eq.frequency_table(&[1000.0]);
+    assert!((table[0].magnitude_db + 6.0).abs() < 0.1);
+}
+
+#[test]
+fn band_shapes() {
+    let single = |band: EqBand, f: f32| {
+        let mut eq = ParametricEq::with_backend(Backend::Scalar, SAMPLE_RATE);
+        eq.add_band(band).unwrap();
+        db(eq.response(f).norm())
+    };
+
+    assert!((single(EqBand::bell(2000.0, 1.0, 9.0), 2000.0) - 9.0).abs() < 0.01);
+    assert!((single(EqBand::low_shelf(200.0, -4.0), 20.0) + 4.0).abs() < 0.1);
+    assert!((single(EqBand::high_shelf(2000.0, 5.0), 20000.0) - 5.0).abs() < 0.1);
+
+    // An octave past the corner a Butterworth cut is down by its slope,
+    // give or take the bilinear warping
+    for slope in Slope::ALL.iter() {
+        let corner = single(EqBand::low_cut(400.0, *slope), 400.0);
+        let octave = single(EqBand::low_cut(400.0, *slope), 200.0);
+        let expected = -10.0 * (1.0 + 4f64.powi(slope.order() as i32)).log10();
+        assert!((corner + 3.01).abs() < 0.05, "{:?}: {}", slope, corner);
+        assert!((octave - expected).abs() < 0.1, "{:?}: {}", slope, octave);
+        assert_eq!(slope.db_per_octave(), 6 * slope.order());
+    }
+
+    // The Q of a 12 dB cut is its resonance, steeper cuts ignore it
+    let resonant = EqBand::high_cut(1000.0, Slope::Db12).with_q(2.0);
+    assert!((single(resonant, 1000.0) - db(2.0)).abs() < 0.05);
+    let butterworth = resonant.with_slope(Slope::Db24);
+    assert!((single(butterworth, 1000.0) + 3.01).abs() < 0.05);
+}
+
+#[test]
+fn processing_matches_cascade_on_every_backend() {
+    let input = noise(4096);
+    let mut expected = input.clone();
+    Cascade::new(Backend::Scalar, &eq(Backend::Scalar).sections()).process(&mut expected);
+
+    for backend in Backend::ALL.iter().filter(|b| b.is_supported()) {
+        let mut output = input.clone();
+        eq(*backend).process(&mut output);
+        let expected = expected.iter().map(|x| *x as f64).collect::<Vec<_>>();
+        // The block kernels only get about 60 dB out of the resonant 40 Hz
+        // section of the low cut, the precomputed pole powers are that close
+        // to each other
+        let floor = noise_floor_db(&output, &expected);
+        assert!(floor < -50.0, "{}: {} dB", backend, floor);
+    }
+}
+
+#[test]
+fn host_sized_buffers_match_one_buffer() {
+    let input = noise(960);
+    for backend in Backend::ALL.iter().filter(|b| b.is_supported()) {
+        let bell = || {
+            let mut eq = ParametricEq::with_backend(*backend, SAMPLE_RATE);
+            eq.add_band(EqBand::bell(1000.0, 1.0, 6.0)).unwrap();
+            eq
+        };
+        let mut whole = input.clone();
+        bell().process(&mut whole);
+
+        for chunk in [1, 3, 7] {
+            let mut eq = bell();
+            let mut chunked = input.clone();
+            for block in chunked.chunks_mut(chunk) {
+                eq.process(block);
+            }
+            // Off by rounding at most, not by the zeros a padded block would
+            // have fed into the state
+            for (c, w) in chunked.iter().zip(whole.iter()) {
+                assert!(
+                    (c - w).abs() < 1e-4,
+                    "{} in chunks of {}: {} != {}",
+                    backend,
+                    chunk,
+                    c,
+                    w
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn parameter_changes_keep_state() {
+    let input = noise(2048);
+    let (first, second) = input.split_at(1024);
+    let before = eq(Backend::Scalar).sections();
+
+    let mut equalizer = eq(Backend::Scalar);
+    let mut output = input.clone();
+    equalizer.process(&mut output[..1024]);
+    let louder = equalizer.band(2).with_gain_db(4.0).with_q(1.0);
+    equalizer.set_band(2, louder).unwrap();
+    equalizer.process(&mut output[1024..]);
+
+    // The same change on a plain cascade, whose sections keep their state
+    let mut cascade = Cascade::new(Backend::Scalar, &before);
+    let mut expected = first.to_vec();
+    cascade.process(&mut expected);
+    cascade.set_coeffs(3, &louder.spec().sections(SAMPLE_RATE)[0]);
+    let mut rest = second.to_vec();
+    cascade.process(&mut rest);
+    expected.extend(rest);
+
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn disabled_bands_are_bypassed() {
+    let input = noise(1024);
+    let mut eq = eq(Backend::Scalar);
+    for i in 0..eq.len() {
+        eq.set_enabled(i, false);
+    }
+    assert!(eq.sections().is_empty());
+
+    let mut output = input.clone();
+    eq.process(&mut output);
+    assert_eq!(output, input);
+    assert!((eq.response(1000.0).norm() - 1.0).abs() < 1e-12);
+
+    eq.set_enabled(2, true);
+    let removed = eq.remove_band(2);
+    assert_eq!(removed.kind, BandKind::Bell);
+    assert_eq!(eq.len(), 4);
+}
+
+#[test]
+fn invalid_bands_leave_the_eq_unchanged() {
+    let mut eq = eq(Backend::Scalar);
+    let before = eq.sections();
+
+    let error = eq.add_band(EqBand::bell(30000.0, 1.0, 3.0)).unwrap_err();
+    assert!(error.to_string().contains("Nyquist"));
+    let error = eq.set_band(2, EqBand::bell(1000.0, -1.0, 3.0)).unwrap_err();
+    assert!(error.to_string().contains("Q must be positive"));
+    let error = eq.set_sample_rate(32000.0).unwrap_err();
+    assert!(error.to_string().contains("Nyquist"));
+
+    assert_eq!(eq.sections(), before);
+    assert_eq!(eq.sample_rate(), SAMPLE_RATE);
+
+    eq.set_sample_rate(96000.0).unwrap();
+    assert!((db(eq.response(1000.0).norm()) + 6.0).abs() < 0.1);
+}
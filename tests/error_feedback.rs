@@ -0,0 +1,74 @@
+use std::convert::TryFrom;
+
+use wide::f32x4;
+
+use simdiir::{
+    biquad_error_feedback::{BiQuadErrorFeedback, BiQuadErrorFeedbackPortable4},
+    biquad_f32::BiQuadF32,
+    biquad_portable::BiQuadPortable4,
+    coeffs::BiQuadCoeffs,
+};
+
+mod common;
+
+use common::{noise, noise_floor_db, reference_df1, SAMPLE_RATE};
+
+#[test]
+fn error_feedback_lowers_noise_floor() {
+    let c = BiQuadCoeffs::lowpass(SAMPLE_RATE, 20.0, 0.707);
+    let input = noise(1 << 16);
+    let expected = reference_df1(&c, &input);
+
+    let mut plain = BiQuadF32::new();
+    plain.set_coeffs(&c);
+    let plain = input.iter().map(|&x| plain.process(x)).collect::<Vec<_>>();
+
+    let mut feedback = BiQuadErrorFeedback::new();
+    feedback.set_coeffs(&c);
+    let feedback = input
+        .iter()
+        .map(|&x| feedback.process(x))
+        .collect::<Vec<_>>();
+
+    let plain_floor = noise_floor_db(&plain, &expected);
+    let feedback_floor = noise_floor_db(&feedback, &expected);
+    assert!(
+        feedback_floor < plain_floor - 40.0,
+        "{:.1} dB vs {:.1} dB",
+        feedback_floor,
+        plain_floor
+    );
+}
+
+#[test]
+fn simd_error_feedback_lowers_noise_floor() {
+    let c = BiQuadCoeffs::lowpass(SAMPLE_RATE, 20.0, 0.707);
+    let input = noise(1 << 16);
+    let expected = reference_df1(&c, &input);
+
+    let run = |process: &mut dyn FnMut(f32x4) -> f32x4| {
+        let mut output = Vec::with_capacity(input.len());
+        for chunk in input.chunks_exact(4) {
+            let y = process(f32x4::from(<[f32; 4]>::try_from(chunk).unwrap()));
+            output.extend_from_slice(&y.to_array());
+        }
+        output
+    };
+
+    let mut plain = BiQuadPortable4::new();
+    plain.set_coeffs(&c);
+    let plain = run(&mut |x| plain.process(x));
+
+    let mut feedback = BiQuadErrorFeedbackPortable4::new();
+    feedback.set_coeffs(&c);
+    let feedback = run(&mut |x| feedback.process(x));
+
+    let plain_floor = noise_floor_db(&plain, &expected);
+    let feedback_floor = noise_floor_db(&feedback, &expected);
+    assert!(
+        feedback_floor < plain_floor - 40.0,
+        "{:.1} dB vs {:.1} dB",
+        feedback_floor,
+        plain_floor
+    );
+}
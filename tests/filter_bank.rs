@@ -0,0 +1,221 @@
+use simdiir::{
+    cascade::{Backend, Cascade},
+    design::FilterError,
+    eq::{GraphicEq, QMode},
+    filter_bank::{bandpass, bands, Band, FilterBank, OCTAVE_RATIO},
+    response::response,
+};
+
+mod common;
+
+use common::{noise, SAMPLE_RATE};
+
+fn db(x: f64) -> f64 {
+    20.0 * x.log10()
+}
+
+#[test]
+fn iso_nominal_centers() {
+    let octaves = bands(SAMPLE_RATE, 1, 31.5, 16000.0).unwrap();
+    let nominal = octaves.iter().map(|b| b.nominal).collect::<Vec<_>>();
+    assert_eq!(
+        nominal,
+        vec![31.5, 63.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0]
+    );
+
+    let thirds = bands(SAMPLE_RATE, 3, 20.0, 20000.0).unwrap();
+    assert_eq!(thirds.len(), 31);
+    let nominal = thirds.iter().map(|b| b.nominal).collect::<Vec<_>>();
+    assert_eq!(&nominal[..6], &[20.0, 25.0, 31.5, 40.0, 50.0, 63.0]);
+    assert_eq!(
+        &nominal[26..],
+        &[8000.0, 10000.0, 12500.0, 16000.0, 20000.0]
+    );
+
+    // Exact centers and edges in base ten octaves
+    let band = Band::new(3, 0);
+    assert_eq!((band.center, band.nominal), (1000.0, 1000.0));
+    assert!((band.upper / band.lower - OCTAVE_RATIO.powf(1.0 / 3.0) as f32).abs() < 1e-5);
+    let sixth = Band::new(6, 0);
+    assert!((sixth.center - 1000.0 * OCTAVE_RATIO.powf(1.0 / 12.0) as f32).abs() < 1e-2);
+    assert_eq!(sixth.nominal, 1060.0);
+
+    // The 20 kHz third octave reaches past Nyquist at 44.1 kHz
+    assert_eq!(bands(44100.0, 3, 20.0, 20000.0).unwrap().len(), 30);
+}
+
+// IEC 61260 class 1 limits on the attenuation relative to the center, at
+// octave band breakpoints G^(k/8), stretched for fractional bands
+const CLASS_1: [(f64, f64, f64); 9] = [
+    (0.0, -0.3, 0.3),
+    (0.125, -0.3, 0.4),
+    (0.25, -0.3, 0.6),
+    (0.375, -0.3, 1.3),
+    (0.5, 2.0, 5.0),
+    (1.0, 17.5, f64::INFINITY),
+    (2.0, 42.5, f64::INFINITY),
+    (3.0, 62.0, f64::INFINITY),
+    (4.0, 70.0, f64::INFINITY),
+];
+
+fn assert_class_1(band: &Band, sections: &[simdiir::coeffs::BiQuadCoeffs], fraction: u32) {
+    let g = OCTAVE_RATIO;
+    let center = band.center as f64;
+    for (exponent, min, max) in CLASS_1.iter() {
+        let octave = g.powf(*exponent);
+        let ratio =
+            1.0 + (g.powf(1.0 / (2.0 * fraction as f64)) - 1.0) / (g.sqrt() - 1.0) * (octave - 1.0);
+        for f in [center * ratio, center / ratio].iter() {
+            if *f >= SAMPLE_RATE as f64 / 2.0 {
+                continue;
+            }
+            let attenuation = -db(response(sections, SAMPLE_RATE, *f as f32).norm());
+            assert!(
+                attenuation >= *min && attenuation <= *max,
+                "1/{} octave band at {} Hz: {} dB at {} Hz",
+                fraction,
+                band.nominal,
+                attenuation,
+                f
+            );
+        }
+    }
+}
+
+#[test]
+fn bands_meet_class_1() {
+    for &fraction in &[1, 3, 6, 12] {
+        let bands = bands(SAMPLE_RATE, fraction, 31.5, 20000.0).unwrap();
+        for band in bands.iter().filter(|b| b.upper < SAMPLE_RATE / 5.0) {
+            let sections = bandpass(SAMPLE_RATE, band.lower, band.upper, 3);
+            assert_eq!(sections.len(), 3);
+            assert_class_1(band, &sections, fraction);
+
+            let edge = |f: f32| db(response(&sections, SAMPLE_RATE, f).norm());
+            assert!(edge(band.center).abs() < 0.01);
+            // f32 coefficients only roughly place the poles of bands a few Hz
+            // wide, which still meet the mask but miss the edges by tenths of
+            // a dB
+            if band.upper - band.lower > 10.0 {
+                assert!((edge(band.lower) + 3.01).abs() < 0.1);
+                assert!((edge(band.upper) + 3.01).abs() < 0.1);
+            }
+        }
+    }
+}
+
+#[test]
+fn lanes_match_cascades() {
+    let input = noise(3000);
+    let mut bank = FilterBank::third_octave(SAMPLE_RATE).unwrap();
+    let mut outputs = vec![vec![0.0; input.len()]; bank.bands().len()];
+    let mut slices = outputs
+        .iter_mut()
+        .map(Vec::as_mut_slice)
+        .collect::<Vec<_>>();
+    bank.process_bands(&input, &mut slices);
+
+    for (i, output) in outputs.iter().enumerate() {
+        let mut expected = input.clone();
+        Cascade::new(Backend::Scalar, bank.band_sections(i)).process(&mut expected);
+        for (a, e) in output.iter().zip(expected.iter()) {
+            assert!((a - e).abs() < 1e-5, "band {}: {} != {}", i, a, e);
+        }
+
+        let energy = output.iter().map(|y| (*y as f64).powi(2)).sum::<f64>();
+        let measured = bank.mean_square()[i] * input.len() as f64;
+        assert!((energy / measured - 1.0).abs() < 1e-4, "band {}", i);
+    }
+}
+
+#[test]
+fn sine_lands_in_its_band() {
+    let mut bank = FilterBank::third_octave(SAMPLE_RATE).unwrap();
+    let sine = (0..48000)
+        .map(|n| (2.0 * std::f32::consts::PI * 1000.0 * n as f32 / SAMPLE_RATE).sin())
+        .collect::<Vec<_>>();
+    bank.process(&sine[..24000]);
+    bank.process(&sine[24000..]);
+
+    let levels = bank.levels_db();
+    let peak = bank
+        .bands()
+        .iter()
+        .position(|b| b.nominal == 1000.0)
+        .unwrap();
+    assert!((levels[peak] + 3.01).abs() < 0.1, "{}", levels[peak]);
+    assert!(levels[peak - 1] < -18.0 && levels[peak + 1] < -18.0);
+    assert!(levels
+        .iter()
+        .enumerate()
+        .all(|(i, l)| i == peak || *l < -18.0));
+
+    bank.reset();
+    assert!(bank.mean_square().iter().all(|ms| *ms == 0.0));
+}
+
+#[test]
+fn invalid_banks() {
+    let error = |result: Result<FilterBank, FilterError>| result.err().unwrap().to_string();
+    assert!(error(FilterBank::new(SAMPLE_RATE, 0, 20.0, 20000.0, 3)).contains("1/0"));
+    assert!(error(FilterBank::new(SAMPLE_RATE, 3, 20.0, 20000.0, 9)).contains("order"));
+    assert!(error(FilterBank::new(SAMPLE_RATE, 1, 1100.0, 1900.0, 3)).contains("no 1/1 octave"));
+    assert!(error(FilterBank::new(SAMPLE_RATE, 1, 2000.0, 1000.0, 3)).contains("range"));
+}
+
+#[test]
+fn graphic_eq() {
+    for mode in [QMode::Constant, QMode::Proportional].iter() {
+        let mut eq =
+            GraphicEq::with_backend(Backend::Scalar, SAMPLE_RATE, 3, 20.0, 20000.0, *mode).unwrap();
+        assert_eq!(eq.bands().len(), 31);
+        assert!(eq.eq().sections().is_empty());
+
+        let band = eq.bands().iter().position(|b| b.nominal == 1000.0).unwrap();
+        for &gain in &[-12.0, -3.0, 6.0, 12.0] {
+            eq.set_gain_db(band, gain).unwrap();
+            assert_eq!(eq.gain_db(band), gain);
+            assert!((db(eq.response(1000.0).norm()) - gain as f64).abs() < 0.01);
+        }
+        assert_eq!(eq.eq().sections().len(), 1);
+        eq.set_gain_db(band, 0.0).unwrap();
+        assert!(eq.eq().sections().is_empty());
+    }
+
+    // Constant Q keeps the width, proportional Q makes small moves broad
+    let width = |mode: QMode, gain: f32| {
+        let mut eq =
+            GraphicEq::with_backend(Backend::Scalar, SAMPLE_RATE, 3, 20.0, 20000.0, mode).unwrap();
+        eq.set_gain_db(17, gain).unwrap();
+        eq.eq().band(17).q
+    };
+    let slot = Band::new(3, 0).q();
+    assert_eq!(width(QMode::Constant, 3.0), slot);
+    assert_eq!(width(QMode::Constant, 12.0), slot);
+    assert_eq!(width(QMode::Proportional, 12.0), slot);
+    assert_eq!(width(QMode::Proportional, -6.0), slot / 2.0);
+    assert_eq!(width(QMode::Proportional, 1.0), slot / 4.0);
+}
+
+// The 16 kHz octave is wide enough at 48 kHz that the real prototype pole
+// becomes two real bandpass poles, which share one section
+#[test]
+fn wide_band_near_nyquist() {
+    let mut bank = FilterBank::octave(SAMPLE_RATE).unwrap();
+    let band = bank.bands().len() - 1;
+    assert_eq!(bank.bands()[band].nominal, 16000.0);
+    let sections = bank.band_sections(band).to_vec();
+    assert_eq!(sections.len(), 3);
+    let gain = |f: f32| db(response(&sections, SAMPLE_RATE, f).norm());
+    for edge in [bank.bands()[band].lower, bank.bands()[band].upper].iter() {
+        assert!((gain(*edge) + 3.01).abs() < 0.01, "{} Hz", edge);
+    }
+
+    let sine = (0..48000)
+        .map(|n| (2.0 * std::f32::consts::PI * 16000.0 * n as f32 / SAMPLE_RATE).sin())
+        .collect::<Vec<_>>();
+    bank.process(&sine);
+    let level = bank.levels_db()[band];
+    let expected = gain(16000.0) - 3.01;
+    assert!((level - expected).abs() < 0.02, "{} != {}", level, expected);
+}
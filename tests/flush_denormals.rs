@@ -0,0 +1,79 @@
+use simdiir::{
+    cascade::{Backend, Cascade},
+    coeffs::BiQuadCoeffs,
+    denormals::{is_flushing_denormals, ScopedFlushDenormals, DENORMAL_FLUSH_THRESHOLD},
+};
+
+mod common;
+
+use common::{noise, SAMPLE_RATE};
+
+// The backends whose kernels flush their feedback state themselves
+fn flushing_backends() -> Vec<Backend> {
+    let mut backends = vec![Backend::Scalar];
+    #[cfg(target_arch = "x86_64")]
+    backends.extend(&[Backend::Sse2, Backend::Avx]);
+    backends.retain(|b| b.is_supported());
+    backends
+}
+
+fn run(backend: Backend, flush: bool, input: &[f32]) -> Vec<f32> {
+    let c = BiQuadCoeffs::lowpass(SAMPLE_RATE, 1000.0, 0.707);
+    let mut cascade = Cascade::new(backend, &[c]);
+    cascade.set_flush_denormals(flush);
+    let mut samples = input.to_vec();
+    cascade.process(&mut samples);
+    samples
+}
+
+#[test]
+fn tail_decays_to_exactly_zero() {
+    let mut impulse = vec![0.0; 9600];
+    impulse[0] = 1.0;
+    for backend in flushing_backends() {
+        let flushed = run(backend, true, &impulse);
+        // Silent for good once the state is below the threshold
+        let last = flushed.iter().rposition(|y| *y != 0.0).unwrap();
+        assert!(last < 2000, "{}: {}", backend, last);
+
+        // Left alone the same tail goes through the denormal range
+        let plain = run(backend, false, &impulse);
+        assert!(
+            plain
+                .iter()
+                .any(|y| *y != 0.0 && y.abs() < f32::MIN_POSITIVE),
+            "{}",
+            backend
+        );
+    }
+}
+
+#[test]
+fn no_change_above_the_threshold() {
+    let input = noise(9600);
+    for backend in flushing_backends() {
+        let flushed = run(backend, true, &input);
+        assert!(flushed.iter().all(|y| y.abs() > DENORMAL_FLUSH_THRESHOLD));
+        let plain = run(backend, false, &input);
+        let bits = |v: &[f32]| v.iter().map(|y| y.to_bits()).collect::<Vec<_>>();
+        assert_eq!(bits(&flushed), bits(&plain), "{}", backend);
+    }
+}
+
+#[test]
+fn scope_flushes_on_the_host() {
+    let supported = cfg!(any(
+        target_arch = "x86_64",
+        all(target_arch = "x86", target_feature = "sse"),
+        target_arch = "aarch64"
+    ));
+    assert!(!is_flushing_denormals());
+    {
+        let guard = ScopedFlushDenormals::new();
+        assert_eq!(guard.is_active(), supported);
+        assert_eq!(is_flushing_denormals(), supported);
+        let result = std::hint::black_box(f32::MIN_POSITIVE) / std::hint::black_box(4.0f32);
+        assert_eq!(result == 0.0, supported);
+    }
+    assert!(!is_flushing_denormals());
+}
@@ -0,0 +1,188 @@
+use std::f64::consts::PI;
+
+use simdiir::halfband::{HalfbandDownsampler, HalfbandUpsampler, OversamplingFactor};
+
+mod common;
+
+use common::noise;
+
+const ATTENUATION: f64 = 96.0;
+
+// 20 kHz and up at 44.1 kHz
+const TRANSITION: f64 = 0.0930;
+
+const LEN: usize = 4096;
+
+// Amplitude of the component `cycles` times around the block, on a whole
+// number of cycles so nothing leaks between bins
+fn amplitude(signal: &[f32], cycles: usize) -> f64 {
+    let w = 2.0 * PI * cycles as f64 / signal.len() as f64;
+    let (re, im) = signal
+        .iter()
+        .enumerate()
+        .fold((0.0, 0.0), |(re, im), (n, x)| {
+            let (s, c) = (w * n as f64).sin_cos();
+            (re + *x as f64 * c, im - *x as f64 * s)
+        });
+    2.0 * re.hypot(im) / signal.len() as f64
+}
+
+fn db(x: f64) -> f64 {
+    20.0 * x.log10()
+}
+
+// `cycles` per LEN samples, repeated so the filters settle over the first
+// block and the second one is measured
+fn tone(cycles: usize, len: usize) -> Vec<f32> {
+    (0..2 * len)
+        .map(|n| (2.0 * PI * cycles as f64 * n as f64 / len as f64).sin() as f32)
+        .collect()
+}
+
+#[test]
+fn images_are_rejected() {
+    for factor in OversamplingFactor::ALL.iter() {
+        let mut up = HalfbandUpsampler::new(*factor, ATTENUATION, TRANSITION).unwrap();
+        let n = factor.factor();
+        // Up to the top of the passband, 20 kHz at 44.1 kHz
+        for &cycles in &[37, 512, 1500, 1857] {
+            up.reset();
+            let input = tone(cycles, LEN);
+            let mut output = vec![0.0; input.len() * n];
+            up.process(&input, &mut output);
+            let block = &output[LEN * n..];
+
+            let gain = db(amplitude(block, cycles));
+            assert!(gain.abs() < 0.01, "{} at {}: {} dB", factor, cycles, gain);
+            // Every image of the tone around multiples of the base rate
+            for k in 1..n {
+                for image in [k * LEN - cycles, k * LEN + cycles].iter() {
+                    let level = db(amplitude(block, *image));
+                    assert!(
+                        level < -ATTENUATION + 1.0,
+                        "{} at {}: image at {} is {} dB",
+                        factor,
+                        cycles,
+                        image,
+                        level
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn aliases_are_rejected() {
+    for factor in OversamplingFactor::ALL.iter() {
+        let mut down = HalfbandDownsampler::new(*factor, ATTENUATION, TRANSITION).unwrap();
+        let n = factor.factor();
+        // Tones that would land on 37 and 1857 cycles of the output block,
+        // from just past the transition band all the way up
+        let passband = [37, 1857];
+        for &target in &passband {
+            for k in 1..n {
+                for &cycles in &[k * LEN - target, k * LEN + target] {
+                    down.reset();
+                    let input = tone(cycles, LEN * n);
+                    let mut output = vec![0.0; input.len() / n];
+                    down.process(&input, &mut output);
+                    let level = db(amplitude(&output[LEN..], target));
+                    assert!(
+                        level < -ATTENUATION + 1.0,
+                        "{}: {} cycles alias at {} dB",
+                        factor,
+                        cycles,
+                        level
+                    );
+                }
+            }
+
+            down.reset();
+            let input = tone(target, LEN * n);
+            let mut output = vec![0.0; input.len() / n];
+            down.process(&input, &mut output);
+            let gain = db(amplitude(&output[LEN..], target));
+            assert!(gain.abs() < 0.01, "{}: {} dB", factor, gain);
+        }
+    }
+}
+
+#[test]
+fn stages_and_design() {
+    let up = HalfbandUpsampler::new(OversamplingFactor::X8, ATTENUATION, TRANSITION).unwrap();
+    let down = HalfbandDownsampler::new(OversamplingFactor::X8, ATTENUATION, TRANSITION).unwrap();
+    let counts = up
+        .coefficients()
+        .iter()
+        .map(|c| c.len())
+        .collect::<Vec<_>>();
+    // The stages above 2x have a wide transition band and little to do
+    assert_eq!(counts.len(), 3);
+    assert!(
+        counts[0] > counts[1] && counts[1] > counts[2],
+        "{:?}",
+        counts
+    );
+    assert!(counts.iter().all(|c| c % 2 == 0));
+    assert_eq!(up.coefficients(), down.coefficients());
+
+    // More attenuation or a narrower transition costs coefficients
+    let count = |attenuation: f64, transition: f64| {
+        HalfbandUpsampler::new(OversamplingFactor::X2, attenuation, transition)
+            .unwrap()
+            .coefficients()[0]
+            .len()
+    };
+    assert!(count(120.0, TRANSITION) > count(ATTENUATION, TRANSITION));
+    assert!(count(ATTENUATION, 0.05) > count(ATTENUATION, TRANSITION));
+
+    for factor in OversamplingFactor::ALL.iter() {
+        assert_eq!(
+            factor.name().parse::<OversamplingFactor>().unwrap(),
+            *factor
+        );
+    }
+    assert_eq!(OversamplingFactor::X8.factor(), 8);
+    assert!("3x".parse::<OversamplingFactor>().is_err());
+    assert!(HalfbandUpsampler::new(OversamplingFactor::X2, ATTENUATION, 0.0).is_err());
+    assert!(HalfbandDownsampler::new(OversamplingFactor::X2, -1.0, TRANSITION).is_err());
+}
+
+#[test]
+fn blocks_and_round_trip() {
+    let input = noise(1024);
+    for factor in OversamplingFactor::ALL.iter() {
+        let n = factor.factor();
+        let mut up = HalfbandUpsampler::new(*factor, ATTENUATION, TRANSITION).unwrap();
+        let mut whole = vec![0.0; input.len() * n];
+        up.process(&input, &mut whole);
+
+        up.reset();
+        let mut parts = vec![0.0; input.len() * n];
+        let (first, second) = parts.split_at_mut(333 * n);
+        up.process(&input[..333], first);
+        up.process(&input[333..], second);
+        assert_eq!(whole, parts);
+
+        let mut down = HalfbandDownsampler::new(*factor, ATTENUATION, TRANSITION).unwrap();
+        let mut back = vec![0.0; input.len()];
+        down.process(&whole[..400 * n], &mut back[..400]);
+        down.process(&whole[400 * n..], &mut back[400..]);
+        down.reset();
+        let mut at_once = vec![0.0; input.len()];
+        down.process(&whole, &mut at_once);
+        assert_eq!(back, at_once);
+
+        // A tone survives the round trip at its level
+        let tone = tone(100, 1024);
+        let mut high = vec![0.0; tone.len() * n];
+        up.reset();
+        down.reset();
+        up.process(&tone, &mut high);
+        let mut low = vec![0.0; tone.len()];
+        down.process(&high, &mut low);
+        let gain = db(amplitude(&low[1024..], 100));
+        assert!(gain.abs() < 0.01, "{}: {} dB", factor, gain);
+    }
+}
@@ -0,0 +1,203 @@
+use simdiir::{
+    cascade::{Backend, Cascade},
+    design::FilterError,
+    hilbert::HilbertPair,
+    polyphase,
+    response::response,
+};
+
+mod common;
+
+use common::{noise, SAMPLE_RATE};
+
+// Olli Niemitalo's published coefficients, each branch's squared
+const NIEMITALO: [[f64; 4]; 2] = [
+    [
+        0.402_192_116_242_6,
+        0.856_171_088_242,
+        0.972_290_954_565_1,
+        0.995_288_479_127_8,
+    ],
+    [
+        0.692_387_8,
+        0.936_065_432_295_9,
+        0.988_229_522_686,
+        0.998_748_845_273_7,
+    ],
+];
+
+#[test]
+fn halfband_design() {
+    // Two paths of allpasses in phase below fs/4 and opposite above
+    for &(count, transition) in &[(3, 0.1), (8, 0.05), (12, 0.01)] {
+        let a = polyphase::coefficients(count, transition).unwrap();
+        assert!(a.windows(2).all(|w| w[0] < w[1]) && a[0] > 0.0 && a[count - 1] < 1.0);
+
+        let attenuation = polyphase::attenuation_db(count, transition).unwrap();
+        assert_eq!(
+            polyphase::count_for(attenuation - 0.1, transition).unwrap(),
+            count
+        );
+        assert_eq!(
+            polyphase::count_for(attenuation + 0.1, transition).unwrap(),
+            count + 1
+        );
+
+        let path = |f: f64, start: usize| {
+            let w = 2.0 * std::f64::consts::PI * f;
+            a.iter().skip(start).step_by(2).fold(0.0, |phase, a| {
+                let (s, c) = (2.0 * w).sin_cos();
+                // (a + z^-2) / (1 + a z^-2)
+                phase + (-s).atan2(a + c) - (-a * s).atan2(1.0 + a * c)
+            }) - w * start as f64
+        };
+        let mut worst = 0.0f64;
+        for k in 1..1000 {
+            let f = 0.5 * k as f64 / 1000.0;
+            if (f - 0.25).abs() < transition {
+                continue;
+            }
+            let half = (path(f, 0) - path(f, 1)) / 2.0;
+            let gain = half.cos().abs();
+            if f < 0.25 {
+                // The stopband's leakage is what the passband misses
+                let ripple = 10f64.powf(-attenuation / 20.0);
+                assert!(
+                    gain >= (1.0 - ripple * ripple).sqrt() - 1e-9,
+                    "{} at {}",
+                    gain,
+                    f
+                );
+            } else {
+                worst = worst.max(gain);
+            }
+        }
+        let measured = -20.0 * worst.log10();
+        assert!(
+            (measured - attenuation).abs() < 0.1,
+            "{} {}",
+            measured,
+            attenuation
+        );
+    }
+
+    assert!(polyphase::coefficients(4, 0.25).is_err());
+    assert!(polyphase::coefficients(0, 0.1).is_err());
+    assert!(polyphase::count_for(-3.0, 0.1).is_err());
+}
+
+#[test]
+fn ninety_degrees_over_the_band() {
+    for &(low, error) in &[(20.0, 1.0), (100.0, 0.1), (500.0, 0.01)] {
+        let pair = HilbertPair::new(SAMPLE_RATE, low, error).unwrap();
+        assert!(pair.phase_error_degrees() <= error);
+        let fewer = HilbertPair::with_count(SAMPLE_RATE, low, pair.coefficients().len() - 2);
+        assert!(fewer.unwrap().phase_error_degrees() > error);
+
+        let in_phase = pair.branch_sections(0);
+        let quadrature = pair.branch_sections(1);
+        let (from, to) = pair.band();
+        for k in 0..=200 {
+            let f = from * (to / from).powf(k as f32 / 200.0);
+            let i = response(&in_phase, SAMPLE_RATE, f);
+            let q = response(&quadrature, SAMPLE_RATE, f);
+            assert!((i.norm() - 1.0).abs() < 1e-4 && (q.norm() - 1.0).abs() < 1e-4);
+
+            // f32 coefficients add to the designed error
+            let difference = (i / q).arg().to_degrees();
+            assert!(
+                (difference - 90.0).abs() < error * 1.05 + 1e-3,
+                "{} Hz: {} degrees",
+                f,
+                difference
+            );
+        }
+    }
+}
+
+#[test]
+fn niemitalo_coefficients() {
+    let pair = HilbertPair::with_count(44100.0, 40.0, 8).unwrap();
+    let a = pair.coefficients();
+    for (branch, expected) in NIEMITALO.iter().enumerate() {
+        for (k, e) in expected.iter().enumerate() {
+            let designed = a[2 * k + branch];
+            assert!((designed - e * e).abs() < 3e-4, "{} {}", designed, e * e);
+        }
+    }
+    assert!(pair.phase_error_degrees() < 0.75);
+}
+
+#[test]
+fn lanes_match_the_sections() {
+    let input = noise(1001);
+    let mut pair = HilbertPair::new(SAMPLE_RATE, 50.0, 0.5).unwrap();
+    let mut in_phase = vec![0.0; input.len()];
+    let mut quadrature = vec![0.0; input.len()];
+    // Odd blocks move the pairs of lanes along by one sample
+    let mut start = 0;
+    for len in [1, 64, 7, 128, 2, 799].iter() {
+        let end = start + len;
+        pair.process(
+            &input[start..end],
+            &mut in_phase[start..end],
+            &mut quadrature[start..end],
+        );
+        start = end;
+    }
+
+    for (branch, output) in [&in_phase, &quadrature].iter().enumerate() {
+        let mut expected = input.clone();
+        Cascade::new(Backend::Scalar, &pair.branch_sections(branch)).process(&mut expected);
+        for (a, e) in output.iter().zip(expected.iter()) {
+            assert!((a - e).abs() < 1e-4, "branch {}: {} != {}", branch, a, e);
+        }
+    }
+
+    pair.reset();
+    let (i, q) = pair.process_sample(input[0]);
+    assert_eq!((i, q), (in_phase[0], quadrature[0]));
+}
+
+#[test]
+fn analytic_signal_of_a_cosine() {
+    let mut pair = HilbertPair::new(SAMPLE_RATE, 40.0, 0.1).unwrap();
+    let error = pair.phase_error_degrees().to_radians();
+    for &f in &[60.0, 1000.0, 10000.0, 23000.0] {
+        pair.reset();
+        let w = 2.0 * std::f32::consts::PI * f / SAMPLE_RATE;
+        let input = (0..48000)
+            .map(|n| (w as f64 * n as f64).cos() as f32)
+            .collect::<Vec<_>>();
+        let mut in_phase = vec![0.0; input.len()];
+        let mut quadrature = vec![0.0; input.len()];
+        pair.process(&input, &mut in_phase, &mut quadrature);
+
+        // A flat envelope, and the phase turning forwards at the frequency
+        for n in 24000..48000 {
+            let envelope = in_phase[n].hypot(quadrature[n]);
+            assert!(
+                (envelope - 1.0).abs() < (error / 2.0) as f32 + 1e-3,
+                "{} Hz: {}",
+                f,
+                envelope
+            );
+            let step = quadrature[n].atan2(in_phase[n]) - quadrature[n - 1].atan2(in_phase[n - 1]);
+            let step = (step - w + std::f32::consts::PI).rem_euclid(2.0 * std::f32::consts::PI);
+            assert!(
+                (step - std::f32::consts::PI).abs() < error as f32 + 1e-3,
+                "{} Hz: {}",
+                f,
+                step - std::f32::consts::PI
+            );
+        }
+    }
+}
+
+#[test]
+fn invalid_pairs() {
+    let error = |result: Result<HilbertPair, FilterError>| result.err().unwrap().to_string();
+    assert!(error(HilbertPair::with_count(SAMPLE_RATE, 100.0, 7)).contains("even"));
+    assert!(error(HilbertPair::with_count(SAMPLE_RATE, 12000.0, 8)).contains("quarter"));
+    assert!(error(HilbertPair::new(SAMPLE_RATE, 100.0, 0.0)).contains("phase error"));
+}
@@ -0,0 +1,162 @@
+use wide::{f32x4, f32x8};
+
+use simdiir::{
+    design::{FilterKind, FilterSpec},
+    iir_block::{IirBlockFilter, MAX_ORDER},
+};
+
+mod common;
+
+use common::{noise, noise_floor_db, SAMPLE_RATE};
+
+// Direct form coefficients of a Butterworth filter, the product of its
+// sections
+fn butterworth(kind: FilterKind, frequency: f32, order: u32) -> (Vec<f32>, Vec<f32>) {
+    let mut num = vec![1.0f64];
+    let mut den = vec![1.0f64];
+    let multiply = |p: &[f64], c: [f64; 3]| {
+        let mut out = vec![0.0; p.len() + 2];
+        for (i, p) in p.iter().enumerate() {
+            for (k, c) in c.iter().enumerate() {
+                out[i + k] += p * c;
+            }
+        }
+        out
+    };
+    for c in FilterSpec::new(kind, frequency)
+        .with_order(order)
+        .sections(SAMPLE_RATE)
+    {
+        num = multiply(&num, [c.a0 as f64, c.a1 as f64, c.a2 as f64]);
+        den = multiply(&den, [1.0, c.b1 as f64, c.b2 as f64]);
+    }
+    // First order sections leave trailing zeros
+    num.truncate(order as usize + 1);
+    den.truncate(order as usize + 1);
+    let f32s = |p: Vec<f64>| p.iter().map(|c| *c as f32).collect::<Vec<_>>();
+    (f32s(num), f32s(den))
+}
+
+// Direct form in double precision, with the coefficients switched at the
+// given samples and the history kept
+fn reference(filters: &[(usize, &[f32], &[f32])], input: &[f32]) -> Vec<f64> {
+    let mut xm = [0.0f64; MAX_ORDER];
+    let mut ym = [0.0f64; MAX_ORDER];
+    let mut current = 0;
+    input
+        .iter()
+        .enumerate()
+        .map(|(n, &x)| {
+            if current + 1 < filters.len() && n == filters[current + 1].0 {
+                current += 1;
+            }
+            let (_, num, den) = filters[current];
+            let d0 = den[0] as f64;
+            let x = x as f64;
+            let mut y = num[0] as f64 * x;
+            for j in 1..num.len() {
+                y += num[j] as f64 * xm[j - 1];
+            }
+            for j in 1..den.len() {
+                y -= den[j] as f64 * ym[j - 1];
+            }
+            let y = y / d0;
+            xm.copy_within(0..MAX_ORDER - 1, 1);
+            ym.copy_within(0..MAX_ORDER - 1, 1);
+            xm[0] = x;
+            ym[0] = y;
+            y
+        })
+        .collect()
+}
+
+fn run(filter: &mut IirBlockFilter, lanes: usize, input: &[f32]) -> Vec<f32> {
+    match lanes {
+        1 => input.iter().map(|x| filter.process(*x)).collect(),
+        4 => input
+            .chunks(4)
+            .flat_map(|x| filter.process4(f32x4::from(x)).to_array().to_vec())
+            .collect(),
+        _ => input
+            .chunks(8)
+            .flat_map(|x| filter.process8(f32x8::from(x)).to_array().to_vec())
+            .collect(),
+    }
+}
+
+#[test]
+fn blocks_match_the_direct_form() {
+    let input = noise(8000);
+    for order in 1..=MAX_ORDER as u32 {
+        for &(kind, frequency) in &[
+            (FilterKind::Lowpass, 12000.0),
+            (FilterKind::Highpass, 8000.0),
+        ] {
+            let (num, den) = butterworth(kind, frequency, order);
+            let expected = reference(&[(0, &num, &den)], &input);
+            for &lanes in &[1, 4, 8] {
+                let mut filter = IirBlockFilter::new(&num, &den);
+                assert_eq!(filter.order(), order as usize);
+                let output = run(&mut filter, lanes, &input);
+                let floor = noise_floor_db(&output, &expected);
+                assert!(
+                    floor < -100.0,
+                    "{} order {}, {} lanes: {} dB",
+                    kind,
+                    order,
+                    lanes,
+                    floor
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn unnormalized_denominator() {
+    // Scaled by a power of two, so normalizing gives back the same floats
+    let (num, den) = butterworth(FilterKind::Lowpass, 12000.0, 4);
+    let scaled = |p: &[f32]| p.iter().map(|c| c * 4.0).collect::<Vec<_>>();
+    let input = noise(1000);
+    for &lanes in &[1, 4, 8] {
+        let expected = run(&mut IirBlockFilter::new(&num, &den), lanes, &input);
+        let mut filter = IirBlockFilter::new(&scaled(&num), &scaled(&den));
+        assert_eq!(run(&mut filter, lanes, &input), expected);
+    }
+}
+
+#[test]
+fn update_keeps_running() {
+    let (first_num, first_den) = butterworth(FilterKind::Lowpass, 10000.0, 2);
+    let (second_num, second_den) = butterworth(FilterKind::Lowpass, 12000.0, 4);
+    let input = noise(4000);
+    let expected = reference(
+        &[
+            (0, &first_num, &first_den),
+            (2000, &second_num, &second_den),
+        ],
+        &input,
+    );
+
+    for &lanes in &[1, 4, 8] {
+        let mut filter = IirBlockFilter::new(&first_num, &first_den);
+        let mut output = run(&mut filter, lanes, &input[..2000]);
+        filter.update(&second_num, &second_den);
+        assert_eq!(filter.order(), 4);
+        output.extend(run(&mut filter, lanes, &input[2000..]));
+        let floor = noise_floor_db(&output, &expected);
+        assert!(floor < -100.0, "{} lanes: {} dB", lanes, floor);
+
+        // And starts over from silence after a reset
+        filter.reset();
+        let again = run(&mut filter, lanes, &input[2000..]);
+        let fresh = reference(&[(0, &second_num, &second_den)], &input[2000..]);
+        assert!(noise_floor_db(&again, &fresh) < -100.0);
+    }
+}
+
+#[test]
+#[should_panic(expected = "leading denominator")]
+fn zero_leading_denominator() {
+    IirBlockFilter::new(&[1.0], &[0.0, 1.0]);
+}
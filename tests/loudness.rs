@@ -0,0 +1,100 @@
+use std::f64::consts::PI;
+
+use simdiir::loudness::{LoudnessMeter, SURROUND_WEIGHT};
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+// A 1 kHz sine at the given level, for the given number of seconds
+fn sine(level_db: f64, seconds: f64) -> Vec<f32> {
+    let amplitude = 10f64.powf(level_db / 20.0);
+    let len = (seconds * SAMPLE_RATE as f64) as usize;
+    (0..len)
+        .map(|n| (amplitude * (2.0 * PI * 1000.0 * n as f64 / SAMPLE_RATE as f64).sin()) as f32)
+        .collect()
+}
+
+fn stereo(segments: &[(f64, f64)]) -> f64 {
+    let signal = segments
+        .iter()
+        .flat_map(|(level, seconds)| sine(*level, *seconds))
+        .collect::<Vec<_>>();
+    let mut meter = LoudnessMeter::new(SAMPLE_RATE, 2).unwrap();
+    // In uneven chunks, which must not matter
+    for chunk in signal.chunks(12345) {
+        meter.process(&[chunk, chunk]);
+    }
+    meter.integrated().unwrap()
+}
+
+// EBU Tech 3341 test cases 1 to 5, the same sine in both channels
+#[test]
+fn ebu_tech_3341() {
+    let cases: [(&[(f64, f64)], f64); 5] = [
+        (&[(-23.0, 20.0)], -23.0),
+        (&[(-33.0, 20.0)], -33.0),
+        (&[(-36.0, 10.0), (-23.0, 60.0), (-36.0, 10.0)], -23.0),
+        (
+            &[
+                (-72.0, 10.0),
+                (-36.0, 10.0),
+                (-23.0, 60.0),
+                (-36.0, 10.0),
+                (-72.0, 10.0),
+            ],
+            -23.0,
+        ),
+        (&[(-26.0, 20.0), (-20.0, 20.1), (-26.0, 20.0)], -23.0),
+    ];
+    for (i, (segments, expected)) in cases.iter().enumerate() {
+        let loudness = stereo(segments);
+        assert!(
+            (loudness - expected).abs() < 0.1,
+            "case {}: {} LUFS",
+            i + 1,
+            loudness
+        );
+    }
+}
+
+#[test]
+fn channel_weights() {
+    // A full scale sine in one front channel
+    let signal = sine(0.0, 2.0);
+    let silence = vec![0.0; signal.len()];
+    let mut meter = LoudnessMeter::new(SAMPLE_RATE, 5).unwrap();
+    meter.process(&[&signal, &silence, &silence, &silence, &silence]);
+    let front = meter.integrated().unwrap();
+    assert!((front + 3.01).abs() < 0.05, "{} LUFS", front);
+    assert!((meter.momentary().unwrap() - front).abs() < 0.05);
+
+    // The same in a surround channel is 1.5 dB louder
+    meter.reset();
+    meter.set_weight(3, SURROUND_WEIGHT);
+    meter.process(&[&silence, &silence, &silence, &signal, &silence]);
+    let surround = meter.integrated().unwrap();
+    assert!((surround - front - 10.0 * 1.41f64.log10()).abs() < 0.01);
+
+    // And left out of an LFE channel
+    meter.reset();
+    meter.set_weight(3, 0.0);
+    meter.process(&[&silence, &silence, &silence, &signal, &silence]);
+    assert_eq!(meter.integrated(), None);
+}
+
+#[test]
+fn gating() {
+    let mut meter = LoudnessMeter::new(SAMPLE_RATE, 1).unwrap();
+    assert_eq!((meter.momentary(), meter.integrated()), (None, None));
+
+    // Below the absolute gate
+    meter.process(&[&sine(-75.0, 1.0)]);
+    assert!(meter.momentary().unwrap() < -70.0);
+    assert_eq!(meter.integrated(), None);
+
+    meter.process(&[&sine(-20.0, 10.0)]);
+    let loudness = meter.integrated().unwrap();
+    assert!((loudness + 23.0).abs() < 0.1, "{} LUFS", loudness);
+
+    assert!(LoudnessMeter::new(SAMPLE_RATE, 0).is_err());
+    assert!(LoudnessMeter::new(SAMPLE_RATE, 9).is_err());
+}
@@ -0,0 +1,96 @@
+use simdiir::{
+    cascade::{Backend, Cascade},
+    coeffs::BiQuadCoeffs,
+    plot,
+    response::log_frequencies,
+};
+
+mod common;
+
+use common::SAMPLE_RATE;
+
+// Every opened element is closed, in order, and the root is the svg element
+fn assert_well_formed(svg: &str) {
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    let mut open = Vec::new();
+    for tag in svg.split('<').skip(1) {
+        let tag = &tag[..tag.find('>').expect("unterminated tag")];
+        let name = tag
+            .trim_start_matches('/')
+            .split_whitespace()
+            .next()
+            .unwrap();
+        if tag.starts_with('/') {
+            assert_eq!(open.pop(), Some(name), "mismatched </{}>", name);
+        } else if !tag.ends_with('/') {
+            open.push(name);
+        }
+    }
+    assert!(open.is_empty(), "unclosed {:?}", open);
+    assert!(!svg.contains("NaN") && !svg.contains("inf"));
+}
+
+fn polyline_points(svg: &str) -> Vec<usize> {
+    svg.split("<polyline points=\"")
+        .skip(1)
+        .map(|rest| rest[..rest.find('"').unwrap()].split(' ').count())
+        .collect()
+}
+
+#[test]
+fn bode_and_group_delay() {
+    let sections = [
+        BiQuadCoeffs::highpass(SAMPLE_RATE, 50.0, 0.8),
+        BiQuadCoeffs::notch(SAMPLE_RATE, 1000.0, 2.0),
+    ];
+    let frequencies = log_frequencies(20.0, 20000.0, 100);
+
+    let bode = plot::bode(&sections, SAMPLE_RATE, &frequencies);
+    assert_well_formed(&bode);
+    assert_eq!(polyline_points(&bode), vec![100, 100]);
+    assert!(bode.contains(">Magnitude (dB)<") && bode.contains(">Phase (degrees)<"));
+    assert!(bode.contains(">1k<") && bode.contains(">10k<"));
+
+    let delay = plot::group_delay(&sections, SAMPLE_RATE, &frequencies);
+    assert_well_formed(&delay);
+    assert_eq!(polyline_points(&delay), vec![100]);
+}
+
+#[test]
+fn impulse_stems() {
+    let mut samples = vec![0.0; 64];
+    samples[0] = 1.0;
+    let sections = [BiQuadCoeffs::lowpass(SAMPLE_RATE, 2000.0, 0.7)];
+    Cascade::new(Backend::Scalar, &sections).process(&mut samples);
+
+    let svg = plot::impulse(&samples, SAMPLE_RATE);
+    assert_well_formed(&svg);
+    assert_eq!(svg.matches("<circle").count(), 64);
+}
+
+#[test]
+fn poles_and_zeros() {
+    // Double zero at -1 and a conjugate pole pair, plus a notch with zeros on
+    // the unit circle
+    let sections = [
+        BiQuadCoeffs::lowpass(SAMPLE_RATE, 2000.0, 0.7),
+        BiQuadCoeffs::notch(SAMPLE_RATE, 6000.0, 2.0),
+    ];
+
+    let svg = plot::pole_zero(&sections);
+    assert_well_formed(&svg);
+    assert_eq!(svg.matches("class=\"zero\"").count(), 3);
+    assert_eq!(svg.matches("class=\"pole\"").count(), 4);
+    assert!(svg.contains(">2</text>"));
+}
+
+#[test]
+fn degenerate_frequency_grids() {
+    let sections = [BiQuadCoeffs::lowpass(SAMPLE_RATE, 1000.0, 0.7)];
+    for frequencies in &[vec![], vec![1000.0], vec![0.0, 1000.0]] {
+        let bode = plot::bode(&sections, SAMPLE_RATE, frequencies);
+        assert_well_formed(&bode);
+        let delay = plot::group_delay(&sections, SAMPLE_RATE, frequencies);
+        assert_well_formed(&delay);
+    }
+}
@@ -0,0 +1,98 @@
+use simdiir::{
+    coeffs::BiQuadCoeffs,
+    response::{frequency_table, group_delay, log_frequencies, poles_zeros, response, Complex},
+};
+
+mod common;
+
+use common::{magnitude, SAMPLE_RATE};
+
+fn chain() -> Vec<BiQuadCoeffs> {
+    vec![
+        BiQuadCoeffs::highpass(SAMPLE_RATE, 50.0, 0.8),
+        BiQuadCoeffs::peaking(SAMPLE_RATE, 1000.0, 3.0, 9.0),
+        BiQuadCoeffs::allpass(SAMPLE_RATE, 4000.0),
+    ]
+}
+
+#[test]
+fn magnitude_matches_sections() {
+    let sections = chain();
+    for &f in &[20.0, 100.0, 999.0, 5000.0, 23000.0] {
+        let expected = sections.iter().map(|c| magnitude(c, f)).product::<f64>();
+        let actual = response(&sections, SAMPLE_RATE, f).norm();
+        assert!((actual / expected - 1.0).abs() < 1e-9, "{} Hz", f);
+    }
+}
+
+#[test]
+fn group_delay_is_phase_derivative() {
+    let sections = chain();
+    let frequencies = (1..20000).map(|i| i as f32).collect::<Vec<_>>();
+    let table = frequency_table(&sections, SAMPLE_RATE, &frequencies);
+
+    for pair in table.windows(2) {
+        let dphase = (pair[1].phase_degrees - pair[0].phase_degrees).to_radians();
+        let dw = 2.0 * std::f64::consts::PI * (pair[1].frequency - pair[0].frequency) as f64;
+        let numeric_ms = -dphase / dw * 1000.0;
+        let mid = (pair[0].frequency + pair[1].frequency) / 2.0;
+        let analytic_ms = group_delay(&sections, SAMPLE_RATE, mid) * 1000.0 / SAMPLE_RATE as f64;
+        assert!(
+            (numeric_ms - analytic_ms).abs() < 1e-3 * (1.0 + analytic_ms.abs()),
+            "{} Hz: {} != {}",
+            mid,
+            numeric_ms,
+            analytic_ms
+        );
+    }
+}
+
+#[test]
+fn poles_and_zeros_rebuild_the_section() {
+    for c in chain()
+        .into_iter()
+        .chain(Some(BiQuadCoeffs::lowpass(SAMPLE_RATE, 100.0, 0.5)))
+    {
+        let roots = poles_zeros(&c);
+
+        // Monic polynomial from its roots, compared to the section's
+        let monic = |roots: &[Complex]| {
+            let mut p = vec![Complex::ONE];
+            for r in roots {
+                let mut next = vec![Complex::ZERO; p.len() + 1];
+                for (i, c) in p.iter().enumerate() {
+                    next[i] = next[i] + *c;
+                    next[i + 1] = next[i + 1] - *c * *r;
+                }
+                p = next;
+            }
+            p
+        };
+
+        let poles = monic(&roots.poles);
+        let expected = [1.0, c.b1 as f64, c.b2 as f64];
+        for (p, e) in poles.iter().zip(expected.iter()) {
+            assert!((p.re - e).abs() < 1e-9 && p.im.abs() < 1e-9, "{:?}", c);
+        }
+
+        let zeros = monic(&roots.zeros);
+        let expected = [c.a0 as f64, c.a1 as f64, c.a2 as f64];
+        for (z, e) in zeros.iter().zip(expected.iter()) {
+            assert!((z.re * expected[0] - e).abs() < 1e-6, "{:?}", c);
+        }
+    }
+
+    // The first order allpass has one pole and one zero, at reciprocal radii
+    let roots = poles_zeros(&BiQuadCoeffs::allpass(SAMPLE_RATE, 4000.0));
+    assert_eq!((roots.zeros.len(), roots.poles.len()), (1, 1));
+    assert!((roots.zeros[0].norm() * roots.poles[0].norm() - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn log_grid() {
+    let grid = log_frequencies(20.0, 20000.0, 4);
+    let expected = [20.0, 200.0, 2000.0, 20000.0];
+    for (f, e) in grid.iter().zip(expected.iter()) {
+        assert!((f / e - 1.0).abs() < 1e-5, "{} != {}", f, e);
+    }
+}
@@ -0,0 +1,150 @@
+use wide::f32x4;
+
+use simdiir::{
+    svf_f32::{SvfF32, SvfOutputs},
+    svf_portable::SvfPortable4,
+};
+
+mod common;
+
+use common::{noise, SAMPLE_RATE};
+
+// The outputs as analog filters, numerator and denominator coefficients of
+// s^2, s and 1 with s normalized to the cutoff: the bandpass is the one with
+// gain Q at the cutoff
+fn prototypes(q: f64) -> [(&'static str, [f64; 3], [f64; 3]); 5] {
+    let den = [1.0, 1.0 / q, 1.0];
+    [
+        ("lowpass", [0.0, 0.0, 1.0], den),
+        ("bandpass", [0.0, 1.0, 0.0], den),
+        ("highpass", [1.0, 0.0, 0.0], den),
+        ("notch", [1.0, 0.0, 1.0], den),
+        ("peak", [-1.0, 0.0, 1.0], den),
+    ]
+}
+
+// Response of num / den at s = j ratio, as real and imaginary part
+fn analog(num: [f64; 3], den: [f64; 3], ratio: f64) -> (f64, f64) {
+    let eval = |c: [f64; 3]| (c[2] - c[0] * ratio * ratio, c[1] * ratio);
+    let ((nr, ni), (dr, di)) = (eval(num), eval(den));
+    let d = dr * dr + di * di;
+    ((nr * dr + ni * di) / d, (ni * dr - nr * di) / d)
+}
+
+fn outputs(o: SvfOutputs<f32>) -> [f32; 5] {
+    [o.lowpass, o.bandpass, o.highpass, o.notch, o.peak]
+}
+
+fn lane(o: &SvfOutputs<f32x4>, i: usize) -> [f32; 5] {
+    [o.lowpass, o.bandpass, o.highpass, o.notch, o.peak].map(|v| v.to_array()[i])
+}
+
+#[test]
+fn outputs_are_the_bilinear_prototypes() {
+    for &(cutoff, q) in &[(100.0, 0.707), (1000.0, 0.5), (1000.0, 4.0), (12000.0, 1.0)] {
+        let mut svf = SvfF32::new();
+        svf.update(SAMPLE_RATE, cutoff, q);
+        let impulse = (0..1 << 15)
+            .map(|n| outputs(svf.process(if n == 0 { 1.0 } else { 0.0 })))
+            .collect::<Vec<_>>();
+
+        let g = (std::f64::consts::PI * cutoff as f64 / SAMPLE_RATE as f64).tan();
+        for &f in &[20.0, 300.0, cutoff, 5000.0, 20000.0] {
+            let w = 2.0 * std::f64::consts::PI * f as f64 / SAMPLE_RATE as f64;
+            // Where the bilinear transform puts f on the analog axis
+            let ratio = (w / 2.0).tan() / g;
+            for (output, (name, num, den)) in prototypes(q as f64).iter().enumerate() {
+                let actual = impulse
+                    .iter()
+                    .enumerate()
+                    .fold((0.0, 0.0), |(re, im), (n, h)| {
+                        let (sin, cos) = (w * n as f64).sin_cos();
+                        let h = h[output] as f64;
+                        (re + h * cos, im - h * sin)
+                    });
+                let expected = analog(*num, *den, ratio);
+                let error = (actual.0 - expected.0).hypot(actual.1 - expected.1);
+                assert!(
+                    error < 1e-4 * expected.0.hypot(expected.1).max(1.0),
+                    "{} at {} Hz, q {}, {} Hz: {:?} != {:?}",
+                    name,
+                    cutoff,
+                    q,
+                    f,
+                    actual,
+                    expected
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn modulation_stays_bounded() {
+    let input = noise(48000);
+    for &q in &[0.5, 2.0, 10.0] {
+        let mut svf = SvfF32::new();
+        let mut peak = 0.0f32;
+        for (n, x) in input.iter().enumerate() {
+            // Swept between 50 Hz and 15 kHz five times a second
+            let lfo = (2.0 * std::f32::consts::PI * 5.0 * n as f32 / SAMPLE_RATE).sin();
+            svf.update(SAMPLE_RATE, 50.0 * 300f32.powf(0.5 + 0.5 * lfo), q);
+            for y in outputs(svf.process(*x)).iter() {
+                assert!(y.is_finite(), "q {} at {}", q, n);
+                peak = peak.max(y.abs());
+            }
+        }
+        assert!(peak < 2.0 * q.max(1.0), "q {}: {}", q, peak);
+
+        // And rings out once the input stops
+        for _ in 0..48000 {
+            svf.process(0.0);
+        }
+        assert!(outputs(svf.process(0.0)).iter().all(|y| y.abs() < 1e-6));
+    }
+}
+
+#[test]
+fn lanes_match_scalar() {
+    let cutoffs = [80.0, 700.0, 3000.0, 16000.0];
+    let qs = [0.5, 0.707, 3.0, 8.0];
+    let input = noise(4800);
+
+    let mut lanes = SvfPortable4::new();
+    let mut scalar = [SvfF32::new(); 4];
+    for (n, x) in input.iter().enumerate() {
+        // Changed every sample, and differently per lane
+        let shift = 1.0 + 0.5 * (n as f32 / 600.0).sin();
+        let cutoff = cutoffs.map(|c| c * shift);
+        lanes.update_lanes(SAMPLE_RATE, cutoff, qs);
+        let out = lanes.process(f32x4::splat(*x));
+
+        for (i, svf) in scalar.iter_mut().enumerate() {
+            svf.update(SAMPLE_RATE, cutoff[i], qs[i]);
+            let expected = outputs(svf.process(*x));
+            for (a, e) in lane(&out, i).iter().zip(expected.iter()) {
+                assert!(
+                    (a - e).abs() < 1e-4 * e.abs().max(1.0),
+                    "lane {} at {}: {} != {}",
+                    i,
+                    n,
+                    a,
+                    e
+                );
+            }
+        }
+    }
+
+    // One cutoff for all lanes is the same as four equal ones
+    let mut shared = SvfPortable4::new();
+    shared.update(SAMPLE_RATE, 1000.0, 2.0);
+    let mut each = SvfPortable4::new();
+    each.update_lanes(SAMPLE_RATE, [1000.0; 4], [2.0; 4]);
+    for x in input.iter().take(100) {
+        let (a, b) = (
+            shared.process(f32x4::splat(*x)),
+            each.process(f32x4::splat(*x)),
+        );
+        assert_eq!(a.lowpass.to_array(), b.lowpass.to_array());
+    }
+}
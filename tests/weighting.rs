@@ -0,0 +1,163 @@
+use simdiir::{response::response, weighting::Weighting};
+
+fn db(x: f64) -> f64 {
+    20.0 * x.log10()
+}
+
+// IEC 61672-1 table 3: nominal third octave frequency, A and C weightings in
+// dB, and the class 1 tolerance limits
+#[rustfmt::skip]
+const IEC_61672: [(f32, f64, f64, f64, f64); 34] = [
+    (10.0, -70.4, -14.3, 3.5, f64::NEG_INFINITY),
+    (12.5, -63.4, -11.2, 3.0, f64::NEG_INFINITY),
+    (16.0, -56.7, -8.5, 2.5, -4.5),
+    (20.0, -50.5, -6.2, 2.5, -2.5),
+    (25.0, -44.7, -4.4, 2.5, -2.0),
+    (31.5, -39.4, -3.0, 2.0, -2.0),
+    (40.0, -34.6, -2.0, 1.5, -1.5),
+    (50.0, -30.2, -1.3, 1.5, -1.5),
+    (63.0, -26.2, -0.8, 1.5, -1.5),
+    (80.0, -22.5, -0.5, 1.5, -1.5),
+    (100.0, -19.1, -0.3, 1.5, -1.5),
+    (125.0, -16.1, -0.2, 1.5, -1.5),
+    (160.0, -13.4, -0.1, 1.5, -1.5),
+    (200.0, -10.9, 0.0, 1.4, -1.4),
+    (250.0, -8.6, 0.0, 1.4, -1.4),
+    (315.0, -6.6, 0.0, 1.4, -1.4),
+    (400.0, -4.8, 0.0, 1.4, -1.4),
+    (500.0, -3.2, 0.0, 1.4, -1.4),
+    (630.0, -1.9, 0.0, 1.4, -1.4),
+    (800.0, -0.8, 0.0, 1.4, -1.4),
+    (1000.0, 0.0, 0.0, 1.1, -1.1),
+    (1250.0, 0.6, 0.0, 1.4, -1.4),
+    (1600.0, 1.0, -0.1, 1.6, -1.6),
+    (2000.0, 1.2, -0.2, 1.6, -1.6),
+    (2500.0, 1.3, -0.3, 1.6, -1.6),
+    (3150.0, 1.2, -0.5, 1.6, -1.6),
+    (4000.0, 1.0, -0.8, 1.6, -1.6),
+    (5000.0, 0.5, -1.3, 2.1, -2.1),
+    (6300.0, -0.1, -2.0, 2.1, -2.6),
+    (8000.0, -1.1, -3.0, 2.1, -3.1),
+    (10000.0, -2.5, -4.4, 2.6, -3.6),
+    (12500.0, -4.3, -6.2, 3.0, -6.0),
+    (16000.0, -6.6, -8.5, 3.5, -17.0),
+    (20000.0, -9.3, -11.2, 4.0, f64::NEG_INFINITY),
+];
+
+const SAMPLE_RATES: [f32; 6] = [8000.0, 16000.0, 32000.0, 44100.0, 48000.0, 96000.0];
+
+#[test]
+fn closed_forms_match_the_table() {
+    for (f, a, c, _, _) in IEC_61672.iter() {
+        // The table rounds to a tenth of a dB, and its frequencies are
+        // nominal rather than exact
+        let exact = 1000.0 * 10f64.powf((10.0 * (*f as f64 / 1000.0).log10()).round() / 10.0);
+        assert!(
+            (Weighting::A.reference_db(exact) - a).abs() < 0.051,
+            "A at {} Hz",
+            f
+        );
+        assert!(
+            (Weighting::C.reference_db(exact) - c).abs() < 0.051,
+            "C at {} Hz",
+            f
+        );
+    }
+}
+
+#[test]
+fn class_1_at_every_sample_rate() {
+    for &sample_rate in SAMPLE_RATES.iter() {
+        let a = Weighting::A.sections(sample_rate);
+        let c = Weighting::C.sections(sample_rate);
+        assert_eq!((a.len(), c.len()), (3, 2));
+
+        for (f, a_db, c_db, above, below) in IEC_61672.iter() {
+            if *f >= sample_rate / 2.0 {
+                continue;
+            }
+            for (sections, nominal) in [(&a, a_db), (&c, c_db)].iter() {
+                let error = db(response(sections, sample_rate, *f).norm()) - *nominal;
+                assert!(
+                    error <= *above && error >= *below,
+                    "{} dB off at {} Hz, {} Hz sample rate",
+                    error,
+                    f,
+                    sample_rate
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn close_to_analog_below_a_tenth_of_the_sample_rate() {
+    for &sample_rate in SAMPLE_RATES.iter() {
+        for weighting in [Weighting::A, Weighting::C].iter() {
+            let sections = weighting.sections(sample_rate);
+            assert!(db(response(&sections, sample_rate, 1000.0).norm()).abs() < 1e-4);
+            for (f, _, _, _, _) in IEC_61672.iter().filter(|t| t.0 < sample_rate / 10.0) {
+                let error = db(response(&sections, sample_rate, *f).norm())
+                    - weighting.reference_db(*f as f64);
+                assert!(
+                    error.abs() < 0.15,
+                    "{} at {} Hz: {} dB",
+                    weighting,
+                    f,
+                    error
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn k_weighting_coefficients() {
+    // BS.1770-4 tables 1 and 2, at 48 kHz
+    let sections = Weighting::K.sections(48000.0);
+    let expected = [
+        [
+            1.535_124_859_586_97,
+            -2.691_696_189_406_38,
+            1.198_392_810_852_85,
+            -1.690_659_293_182_41,
+            0.732_480_774_215_85,
+        ],
+        [1.0, -2.0, 1.0, -1.990_047_454_833_98, 0.990_072_250_366_21],
+    ];
+    for (c, e) in sections.iter().zip(expected.iter()) {
+        let actual = [c.a0, c.a1, c.a2, c.b1, c.b2];
+        for (a, e) in actual.iter().zip(e.iter()) {
+            assert!((*a as f64 - e).abs() < 1e-6, "{:?}", c);
+        }
+    }
+
+    // The same curve at other rates, away from Nyquist. At 96 kHz the
+    // highpass poles are close enough to 1 for f32 coefficients to move the
+    // 20 Hz response by a few hundredths of a dB.
+    for &sample_rate in &[44100.0, 96000.0] {
+        let sections = Weighting::K.sections(sample_rate);
+        for f in &[20.0, 50.0, 100.0, 1000.0, 2000.0, 4000.0] {
+            let error = db(response(&sections, sample_rate, *f).norm())
+                - Weighting::K.reference_db(*f as f64);
+            assert!(
+                error.abs() < 0.06,
+                "{} Hz at {} Hz: {} dB",
+                f,
+                sample_rate,
+                error
+            );
+        }
+    }
+    assert!((Weighting::K.reference_db(1000.0) - 0.691).abs() < 0.01);
+}
+
+#[test]
+fn names() {
+    for weighting in Weighting::ALL.iter() {
+        assert_eq!(weighting.name().parse::<Weighting>().unwrap(), *weighting);
+    }
+    assert_eq!("A".parse::<Weighting>().unwrap(), Weighting::A);
+    assert!("b".parse::<Weighting>().is_err());
+    assert!(Weighting::Z.sections(48000.0).is_empty());
+}